@@ -0,0 +1,97 @@
+//! B-Treeをtと操作列の組み合わせごとにBTreeMapと比べる
+//!
+//! cargo bench --bench btree -- [--n 100000] [--t 2,4,16,64]
+//!     [--workload sequential,zipf] [--seed 1] [--json]
+
+use std::process::ExitCode;
+use wasm::{
+    CountingAlloc, MAX_KEY_RANGE, Workload, bench_to_csv, bench_to_json,
+    run_suite,
+};
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+struct Options {
+    n: usize,
+    ts: Vec<usize>,
+    workloads: Vec<Workload>,
+    seed: u64,
+    json: bool,
+}
+
+fn parse_list<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, String> {
+    s.split(',')
+        .map(|x| x.parse().map_err(|_| format!("invalid value: {x}")))
+        .collect()
+}
+
+fn parse(
+    mut args: impl Iterator<Item = String>,
+) -> Result<Options, String> {
+    let mut options = Options {
+        n: 100_000,
+        ts: vec![2, 4, 8, 16, 32, 64],
+        workloads: Workload::ALL.to_vec(),
+        seed: 1,
+        json: false,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--n" => {
+                options.n = value()?
+                    .parse()
+                    .map_err(|_| "invalid --n".to_string())?
+            }
+            "--t" => options.ts = parse_list(&value()?)?,
+            "--workload" => {
+                options.workloads = value()?
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?
+            }
+            "--seed" => {
+                options.seed = value()?
+                    .parse()
+                    .map_err(|_| "invalid --seed".to_string())?
+            }
+            "--json" => options.json = true,
+            // cargo bench が渡すフラグ
+            "--bench" => {}
+            _ => return Err(format!("unknown option: {arg}")),
+        }
+    }
+    if options.ts.iter().any(|&t| t < 2) {
+        return Err("t must be at least 2".to_string());
+    }
+    if options.n > MAX_KEY_RANGE {
+        return Err(format!("--n must be at most {MAX_KEY_RANGE}"));
+    }
+    Ok(options)
+}
+
+fn main() -> ExitCode {
+    let options = match parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let results = run_suite(
+        &options.ts,
+        &options.workloads,
+        options.n,
+        options.seed,
+    );
+    if options.json {
+        println!("{}", bench_to_json(&results));
+    } else {
+        print!("{}", bench_to_csv(&results));
+    }
+    ExitCode::SUCCESS
+}
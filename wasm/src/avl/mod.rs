@@ -0,0 +1,5 @@
+mod node;
+mod operation;
+
+pub use node::{AvlEvent, AvlNode, RotationCase};
+pub use operation::AvlTree;
@@ -0,0 +1,288 @@
+use js_sys::Object;
+use std::cmp::Ordering;
+use wasm_bindgen::prelude::*;
+
+/// 不均衡の形と、それを解消する回転の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotationCase {
+    /// 左の子の左部分木が高い: 右回転1回
+    LL,
+
+    /// 左の子の右部分木が高い: 左回転してから右回転
+    LR,
+
+    /// 右の子の左部分木が高い: 右回転してから左回転
+    RL,
+
+    /// 右の子の右部分木が高い: 左回転1回
+    RR,
+}
+
+impl RotationCase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RotationCase::LL => "LL",
+            RotationCase::LR => "LR",
+            RotationCase::RL => "RL",
+            RotationCase::RR => "RR",
+        }
+    }
+}
+
+/// 挿入・削除中に発生した回転
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AvlEvent {
+    /// 回転の種類
+    pub case: RotationCase,
+
+    /// 不均衡になったノードのキー
+    pub key: i32,
+
+    /// 回転前の不均衡ノードの平衡係数
+    pub balance_factor: i32,
+}
+
+impl AvlEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"type".into(),
+            &self.case.as_str().into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"key".into(),
+            &JsValue::from(self.key),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"balanceFactor".into(),
+            &JsValue::from(self.balance_factor),
+        );
+        obj.into()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AvlNode {
+    /// キー
+    key: i32,
+
+    /// このノードを根とする部分木の高さ (葉は1)
+    height: i32,
+
+    /// 左の子
+    left: Option<Box<AvlNode>>,
+
+    /// 右の子
+    right: Option<Box<AvlNode>>,
+}
+
+fn height(node: &Option<Box<AvlNode>>) -> i32 {
+    node.as_ref().map_or(0, |n| n.height)
+}
+
+impl AvlNode {
+    pub fn new(key: i32) -> Self {
+        AvlNode {
+            key,
+            height: 1,
+            left: None,
+            right: None,
+        }
+    }
+
+    pub fn key(&self) -> i32 {
+        self.key
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn left(&self) -> Option<&AvlNode> {
+        self.left.as_deref()
+    }
+
+    pub fn right(&self) -> Option<&AvlNode> {
+        self.right.as_deref()
+    }
+
+    /// 平衡係数 (左部分木の高さ - 右部分木の高さ)
+    pub fn balance_factor(&self) -> i32 {
+        height(&self.left) - height(&self.right)
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        match k.cmp(&self.key) {
+            Ordering::Equal => true,
+            Ordering::Less => {
+                self.left.as_ref().is_some_and(|n| n.search(k))
+            }
+            Ordering::Greater => {
+                self.right.as_ref().is_some_and(|n| n.search(k))
+            }
+        }
+    }
+
+    fn update_height(&mut self) {
+        self.height = 1 + height(&self.left).max(height(&self.right));
+    }
+
+    fn rotate_right(mut self: Box<Self>) -> Box<AvlNode> {
+        let mut pivot = self.left.take().unwrap();
+        self.left = pivot.right.take();
+        self.update_height();
+        pivot.right = Some(self);
+        pivot.update_height();
+        pivot
+    }
+
+    fn rotate_left(mut self: Box<Self>) -> Box<AvlNode> {
+        let mut pivot = self.right.take().unwrap();
+        self.right = pivot.left.take();
+        self.update_height();
+        pivot.left = Some(self);
+        pivot.update_height();
+        pivot
+    }
+
+    /// 高さを更新し、平衡係数が±2になっていれば回転で修復
+    fn rebalance(
+        mut self: Box<Self>,
+        events: &mut Vec<AvlEvent>,
+    ) -> Box<AvlNode> {
+        self.update_height();
+        let balance_factor = self.balance_factor();
+
+        let case = if balance_factor > 1 {
+            let left = self.left.as_ref().unwrap();
+            if left.balance_factor() >= 0 {
+                RotationCase::LL
+            } else {
+                RotationCase::LR
+            }
+        } else if balance_factor < -1 {
+            let right = self.right.as_ref().unwrap();
+            if right.balance_factor() <= 0 {
+                RotationCase::RR
+            } else {
+                RotationCase::RL
+            }
+        } else {
+            return self;
+        };
+
+        events.push(AvlEvent {
+            case,
+            key: self.key,
+            balance_factor,
+        });
+
+        match case {
+            RotationCase::LL => self.rotate_right(),
+            RotationCase::LR => {
+                self.left = self.left.take().map(|n| n.rotate_left());
+                self.rotate_right()
+            }
+            RotationCase::RL => {
+                self.right = self.right.take().map(|n| n.rotate_right());
+                self.rotate_left()
+            }
+            RotationCase::RR => self.rotate_left(),
+        }
+    }
+
+    /// キーkを挿入し、新しい部分木の根を返す
+    ///
+    /// 既にkが存在する場合、insertedはfalseのまま
+    pub fn insert(
+        node: Option<Box<AvlNode>>,
+        k: i32,
+        inserted: &mut bool,
+        events: &mut Vec<AvlEvent>,
+    ) -> Box<AvlNode> {
+        let mut node = match node {
+            None => {
+                *inserted = true;
+                return Box::new(AvlNode::new(k));
+            }
+            Some(node) => node,
+        };
+
+        match k.cmp(&node.key) {
+            Ordering::Equal => return node,
+            Ordering::Less => {
+                node.left = Some(Self::insert(
+                    node.left.take(),
+                    k,
+                    inserted,
+                    events,
+                ));
+            }
+            Ordering::Greater => {
+                node.right = Some(Self::insert(
+                    node.right.take(),
+                    k,
+                    inserted,
+                    events,
+                ));
+            }
+        }
+
+        node.rebalance(events)
+    }
+
+    /// キーkを削除し、新しい部分木の根を返す
+    pub fn delete(
+        node: Option<Box<AvlNode>>,
+        k: i32,
+        deleted: &mut bool,
+        events: &mut Vec<AvlEvent>,
+    ) -> Option<Box<AvlNode>> {
+        let mut node = node?;
+
+        match k.cmp(&node.key) {
+            Ordering::Less => {
+                node.left =
+                    Self::delete(node.left.take(), k, deleted, events);
+            }
+            Ordering::Greater => {
+                node.right =
+                    Self::delete(node.right.take(), k, deleted, events);
+            }
+            Ordering::Equal => {
+                *deleted = true;
+                match (node.left.take(), node.right.take()) {
+                    (None, child) | (child, None) => return child,
+                    (Some(left), Some(right)) => {
+                        // 右部分木の最小キー(後継)で置き換え
+                        let (right, successor) =
+                            Self::remove_min(right, events);
+                        node.key = successor;
+                        node.left = Some(left);
+                        node.right = right;
+                    }
+                }
+            }
+        }
+
+        Some(node.rebalance(events))
+    }
+
+    /// 部分木から最小キーを取り除き、(新しい部分木, 最小キー) を返す
+    fn remove_min(
+        mut node: Box<AvlNode>,
+        events: &mut Vec<AvlEvent>,
+    ) -> (Option<Box<AvlNode>>, i32) {
+        match node.left.take() {
+            None => (node.right.take(), node.key),
+            Some(left) => {
+                let (left, min) = Self::remove_min(left, events);
+                node.left = left;
+                (Some(node.rebalance(events)), min)
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::avl::node::{AvlEvent, AvlNode};
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+// AVL Tree
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct AvlTree {
+    // 根
+    root: Option<Box<AvlNode>>,
+
+    // キーの数
+    len: usize,
+
+    // 直前の操作で発生した回転
+    events: Vec<AvlEvent>,
+}
+
+#[wasm_bindgen]
+impl AvlTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        AvlTree::default()
+    }
+
+    /// キーkを探索
+    pub fn search(&self, k: i32) -> bool {
+        self.root.as_ref().is_some_and(|root| root.search(k))
+    }
+
+    /// キーkを挿入 (既に存在する場合はfalse)
+    pub fn insert(&mut self, k: i32) -> bool {
+        self.events.clear();
+        let mut inserted = false;
+        self.root = Some(AvlNode::insert(
+            self.root.take(),
+            k,
+            &mut inserted,
+            &mut self.events,
+        ));
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// キーkを削除 (存在しない場合はfalse)
+    pub fn delete(&mut self, k: i32) -> bool {
+        self.events.clear();
+        let mut deleted = false;
+        self.root = AvlNode::delete(
+            self.root.take(),
+            k,
+            &mut deleted,
+            &mut self.events,
+        );
+        if deleted {
+            self.len -= 1;
+        }
+        deleted
+    }
+
+    fn node_to_js_value(node: &AvlNode) -> JsValue {
+        let obj = js_sys::Object::new();
+
+        let keys = Array::of1(&JsValue::from(node.key()));
+        let _ = js_sys::Reflect::set(&obj, &"keys".into(), &keys.into());
+
+        // 片方の子しかない場合も左右が区別できるよう、空の子はnullにする
+        let is_leaf = node.left().is_none() && node.right().is_none();
+        let children = if is_leaf {
+            Array::new()
+        } else {
+            Array::from_iter([node.left(), node.right()].iter().map(
+                |child| match child {
+                    Some(child) => Self::node_to_js_value(child),
+                    None => JsValue::NULL,
+                },
+            ))
+        };
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"children".into(),
+            &children.into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"isLeaf".into(),
+            &JsValue::from(is_leaf),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"height".into(),
+            &JsValue::from(node.height()),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"balanceFactor".into(),
+            &JsValue::from(node.balance_factor()),
+        );
+
+        obj.into()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        match &self.root {
+            Some(root) => Self::node_to_js_value(root),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// 直前の操作で発生した回転のイベントを取得
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(AvlEvent::to_js_value))
+    }
+
+    /// キーの総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    #[wasm_bindgen]
+    pub fn get_height(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.height() as usize)
+    }
+}
+
+impl AvlTree {
+    /// 直前の操作で発生した回転
+    pub fn events(&self) -> &[AvlEvent] {
+        &self.events
+    }
+
+    /// 根への参照
+    pub fn root(&self) -> Option<&AvlNode> {
+        self.root.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::avl::node::RotationCase;
+
+    /// 高さ・平衡係数・順序を検査し、部分木の高さを返す
+    fn check(
+        node: Option<&AvlNode>,
+        lo: Option<i32>,
+        hi: Option<i32>,
+    ) -> i32 {
+        let Some(node) = node else {
+            return 0;
+        };
+        assert!(lo.is_none_or(|l| node.key() > l));
+        assert!(hi.is_none_or(|h| node.key() < h));
+
+        let left = check(node.left(), lo, Some(node.key()));
+        let right = check(node.right(), Some(node.key()), hi);
+        assert_eq!(node.height(), 1 + left.max(right));
+        assert_eq!(node.balance_factor(), left - right);
+        assert!(
+            node.balance_factor().abs() <= 1,
+            "node {} is unbalanced",
+            node.key()
+        );
+        node.height()
+    }
+
+    fn rotation_cases(t: &AvlTree) -> Vec<RotationCase> {
+        t.events().iter().map(|e| e.case).collect()
+    }
+
+    #[test]
+    fn test_avl_insertion_and_search() {
+        let mut t = AvlTree::new();
+
+        for k in [10, 20, 5, 6, 12, 30, 7, 17] {
+            assert!(t.insert(k), "{k} should be inserted");
+            check(t.root(), None, None);
+        }
+
+        assert!(t.search(6), "6 should be present");
+        assert!(!t.search(15), "15 should not be present");
+        assert!(!t.insert(6), "duplicate key should be rejected");
+        assert_eq!(t.get_total_keys(), 8);
+    }
+
+    #[test]
+    fn test_avl_rotation_cases() {
+        let cases = [
+            ([3, 2, 1], RotationCase::LL),
+            ([3, 1, 2], RotationCase::LR),
+            ([1, 3, 2], RotationCase::RL),
+            ([1, 2, 3], RotationCase::RR),
+        ];
+
+        for (keys, expected) in cases {
+            let mut t = AvlTree::new();
+            for k in keys {
+                t.insert(k);
+            }
+            assert_eq!(rotation_cases(&t), vec![expected], "{keys:?}");
+            assert_eq!(t.events()[0].key, keys[0]);
+            assert_eq!(t.root().unwrap().key(), 2);
+            assert_eq!(t.get_height(), 2);
+        }
+    }
+
+    #[test]
+    fn test_avl_sequential_insert_is_balanced() {
+        let mut t = AvlTree::new();
+        for k in 1..=1023 {
+            t.insert(k);
+        }
+        check(t.root(), None, None);
+        assert_eq!(t.get_height(), 10);
+    }
+
+    #[test]
+    fn test_avl_delete_triggers_rotation() {
+        let mut t = AvlTree::new();
+        for k in [2, 1, 3, 4] {
+            t.insert(k);
+        }
+
+        // 1を消すと根2の右側が2段高くなる
+        assert!(t.delete(1), "1 should be deleted");
+        assert_eq!(rotation_cases(&t), vec![RotationCase::RR]);
+        assert_eq!(t.root().unwrap().key(), 3);
+        check(t.root(), None, None);
+    }
+
+    #[test]
+    fn test_avl_delete_keeps_balance() {
+        let mut t = AvlTree::new();
+        for k in 1..=200 {
+            t.insert((k * 37) % 211);
+        }
+
+        for k in 1..=200 {
+            let key = (k * 53) % 211;
+            let present = t.search(key);
+            assert_eq!(t.delete(key), present);
+            assert!(!t.search(key), "{key} should not be present");
+            check(t.root(), None, None);
+        }
+    }
+
+    #[test]
+    fn test_avl_delete_nonexistent_key() {
+        let mut t = AvlTree::new();
+        t.insert(10);
+
+        assert!(!t.delete(99), "99 should not be deleted");
+        assert_eq!(t.get_total_keys(), 1);
+        assert!(t.delete(10));
+        assert_eq!(t.get_total_keys(), 0);
+        assert_eq!(t.get_height(), 0);
+    }
+}
@@ -0,0 +1,11 @@
+//! B-Treeのベンチマーク (wasm以外のターゲット用)
+//!
+//! `cargo bench` で benches/btree.rs から呼ばれる
+
+mod alloc;
+mod run;
+mod workload;
+
+pub use alloc::{AllocCounts, CountingAlloc};
+pub use run::{BenchResult, Subject, run, run_suite, to_csv, to_json};
+pub use workload::Workload;
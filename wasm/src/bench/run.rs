@@ -0,0 +1,230 @@
+use crate::bench::alloc::AllocCounts;
+use crate::bench::workload::Workload;
+use crate::btree::BTree;
+use crate::json::JsonValue;
+use crate::trace::NoopTracer;
+use crate::workload::WorkloadOp;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// 計測する構造
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subject {
+    /// 最小次数tのB-Tree
+    BTree(usize),
+
+    /// 比較用の標準ライブラリのBTreeMap (キーごとの個数を持つ多重集合)
+    BTreeMap,
+}
+
+impl Subject {
+    pub fn name(self) -> &'static str {
+        match self {
+            Subject::BTree(_) => "btree",
+            Subject::BTreeMap => "btreemap",
+        }
+    }
+}
+
+/// 1つの構造と操作列の計測結果
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchResult {
+    pub subject: Subject,
+    pub workload: Workload,
+    pub ops: usize,
+    pub nanos: u64,
+
+    /// 計測中の確保の回数とバイト数
+    pub allocations: usize,
+    pub bytes: usize,
+
+    /// 見つかった探索と成功した削除の数 (構造どうしで一致するはず)
+    pub hits: usize,
+}
+
+impl BenchResult {
+    /// 1秒あたりの操作数
+    pub fn ops_per_sec(&self) -> f64 {
+        self.ops as f64 * 1e9 / self.nanos.max(1) as f64
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let t = match self.subject {
+            Subject::BTree(t) => Some(t),
+            Subject::BTreeMap => None,
+        };
+        JsonValue::object([
+            ("structure", self.subject.name().into()),
+            ("t", t.into()),
+            ("workload", self.workload.name().into()),
+            ("ops", self.ops.into()),
+            ("nanos", (self.nanos as f64).into()),
+            ("opsPerSec", self.ops_per_sec().into()),
+            ("allocations", self.allocations.into()),
+            ("bytes", self.bytes.into()),
+            ("hits", self.hits.into()),
+        ])
+    }
+}
+
+/// 規模nの操作列を1回実行して計測する (操作列の生成は計測に含めない)
+pub fn run(
+    subject: Subject,
+    workload: Workload,
+    n: usize,
+    seed: u64,
+) -> BenchResult {
+    let ops = workload.ops(n, seed);
+    let before = AllocCounts::now();
+    let start = Instant::now();
+    let hits = match subject {
+        Subject::BTree(t) => run_btree(t, &ops),
+        Subject::BTreeMap => run_btree_map(&ops),
+    };
+    let nanos = start.elapsed().as_nanos() as u64;
+    let allocs = AllocCounts::now().since(before);
+    BenchResult {
+        subject,
+        workload,
+        ops: ops.len(),
+        nanos,
+        allocations: allocs.allocations,
+        bytes: allocs.bytes,
+        hits,
+    }
+}
+
+fn run_btree(t: usize, ops: &[WorkloadOp]) -> usize {
+    let mut tree = BTree::new(t);
+    let mut hits = 0;
+    for &op in ops {
+        hits += match op {
+            WorkloadOp::Insert(k) => {
+                tree.insert_with(k, &mut NoopTracer);
+                0
+            }
+            WorkloadOp::Search(k) => black_box(tree.search(k)) as usize,
+            WorkloadOp::Delete(k) => {
+                tree.delete_with(k, &mut NoopTracer) as usize
+            }
+        };
+    }
+    black_box(tree);
+    hits
+}
+
+fn run_btree_map(ops: &[WorkloadOp]) -> usize {
+    let mut map: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut hits = 0;
+    for &op in ops {
+        hits += match op {
+            WorkloadOp::Insert(k) => {
+                *map.entry(k).or_default() += 1;
+                0
+            }
+            WorkloadOp::Search(k) => {
+                black_box(map.contains_key(&k)) as usize
+            }
+            WorkloadOp::Delete(k) => match map.get_mut(&k) {
+                Some(1) => map.remove(&k).is_some() as usize,
+                Some(count) => {
+                    *count -= 1;
+                    1
+                }
+                None => 0,
+            },
+        };
+    }
+    black_box(map);
+    hits
+}
+
+/// 各操作列について、各tのB-TreeとBTreeMapを計測する
+pub fn run_suite(
+    ts: &[usize],
+    workloads: &[Workload],
+    n: usize,
+    seed: u64,
+) -> Vec<BenchResult> {
+    let subjects: Vec<Subject> = ts
+        .iter()
+        .map(|&t| Subject::BTree(t))
+        .chain([Subject::BTreeMap])
+        .collect();
+    workloads
+        .iter()
+        .flat_map(|&w| subjects.iter().map(move |&s| run(s, w, n, seed)))
+        .collect()
+}
+
+/// ヘッダ付きのCSV
+pub fn to_csv(results: &[BenchResult]) -> String {
+    let mut csv = String::from(
+        "structure,t,workload,ops,nanos,ops_per_sec,allocations,bytes,hits\n",
+    );
+    for r in results {
+        let t = match r.subject {
+            Subject::BTree(t) => t.to_string(),
+            Subject::BTreeMap => String::new(),
+        };
+        writeln!(
+            csv,
+            "{},{},{},{},{},{:.0},{},{},{}",
+            r.subject.name(),
+            t,
+            r.workload.name(),
+            r.ops,
+            r.nanos,
+            r.ops_per_sec(),
+            r.allocations,
+            r.bytes,
+            r.hits
+        )
+        .unwrap();
+    }
+    csv
+}
+
+/// 結果の配列のJSON文字列
+pub fn to_json(results: &[BenchResult]) -> String {
+    JsonValue::Array(results.iter().map(BenchResult::to_json).collect())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_btree_agrees_with_btree_map() {
+        for w in Workload::ALL {
+            let results = run_suite(&[2, 3, 16], &[w], 400, 9);
+            assert_eq!(results.len(), 4);
+            let hits = results[3].hits;
+            assert!(results.iter().all(|r| r.hits == hits), "{w:?}");
+            assert!(results.iter().all(|r| r.ops == results[0].ops));
+        }
+    }
+
+    #[test]
+    fn test_reports() {
+        let results = run_suite(&[4], &[Workload::Sequential], 10, 1);
+        let csv = to_csv(&results);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("btree,4,sequential,20,"));
+        assert!(lines[2].starts_with("btreemap,,sequential,20,"));
+        assert!(lines[2].ends_with(",10"));
+
+        let json = crate::json::parse(&to_json(&results)).unwrap();
+        let rows = json.as_array().unwrap();
+        assert_eq!(rows[0].get("t").and_then(JsonValue::as_i64), Some(4));
+        assert_eq!(rows[1].get("t"), Some(&JsonValue::Null));
+        assert_eq!(
+            rows[1].get("hits").and_then(JsonValue::as_i64),
+            Some(10)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::workload::{Mix, Pattern, WorkloadGenerator, WorkloadOp};
+use std::str::FromStr;
+
+/// 操作列の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Workload {
+    /// 0..nを昇順に挿入してから、同じ順で探索
+    Sequential,
+
+    /// n-1..0を降順に挿入してから、同じ順で探索
+    Reverse,
+
+    /// 0..nの一様乱数をn回挿入してから、n回探索
+    Random,
+
+    /// 順位rのキーが1/rに比例して選ばれるキーをn回挿入してから、n回探索
+    Zipf,
+
+    /// 一様乱数のキーで探索50%、挿入30%、削除20%をn回
+    Mixed,
+}
+
+impl Workload {
+    pub const ALL: [Workload; 5] = [
+        Workload::Sequential,
+        Workload::Reverse,
+        Workload::Random,
+        Workload::Zipf,
+        Workload::Mixed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Workload::Sequential => "sequential",
+            Workload::Reverse => "reverse",
+            Workload::Random => "random",
+            Workload::Zipf => "zipf",
+            Workload::Mixed => "mixed",
+        }
+    }
+
+    /// 規模nの操作列。同じシードからは常に同じ列になる
+    ///
+    /// nはMAX_KEY_RANGE以下でなければならない
+    pub fn ops(self, n: usize, seed: u64) -> Vec<WorkloadOp> {
+        let pattern = match self {
+            Workload::Sequential => Pattern::Sequential,
+            Workload::Reverse => Pattern::Reverse,
+            Workload::Random | Workload::Mixed => Pattern::Uniform,
+            Workload::Zipf => Pattern::Zipf,
+        };
+        let mut generator =
+            WorkloadGenerator::with_pattern(pattern, n, seed);
+        if self == Workload::Mixed {
+            let mix = Mix {
+                insert: 0.3,
+                delete: 0.2,
+                search: 0.5,
+            };
+            return generator.ops(n, mix);
+        }
+        let keys: Vec<i32> = generator
+            .ops(n, Mix::INSERT_ONLY)
+            .into_iter()
+            .map(WorkloadOp::key)
+            .collect();
+        let inserts = keys.iter().map(|&k| WorkloadOp::Insert(k));
+        let searches = keys.iter().map(|&k| WorkloadOp::Search(k));
+        inserts.chain(searches).collect()
+    }
+}
+
+impl FromStr for Workload {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Workload::ALL
+            .into_iter()
+            .find(|w| w.name() == s)
+            .ok_or_else(|| format!("unknown workload: {s}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workloads_are_reproducible() {
+        for w in Workload::ALL {
+            assert_eq!(w.ops(500, 3), w.ops(500, 3));
+            assert_eq!(w.name().parse(), Ok(w));
+        }
+        assert_ne!(
+            Workload::Random.ops(500, 3),
+            Workload::Random.ops(500, 4)
+        );
+        assert!("bursty".parse::<Workload>().is_err());
+
+        let ops = Workload::Reverse.ops(3, 0);
+        assert_eq!(
+            ops,
+            vec![
+                WorkloadOp::Insert(2),
+                WorkloadOp::Insert(1),
+                WorkloadOp::Insert(0),
+                WorkloadOp::Search(2),
+                WorkloadOp::Search(1),
+                WorkloadOp::Search(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zipf_prefers_low_ranks() {
+        let mut counts = vec![0; 100];
+        for op in Workload::Zipf.ops(100, 5).into_iter().take(100) {
+            let WorkloadOp::Insert(k) = op else {
+                panic!("zipf starts with inserts");
+            };
+            counts[k as usize] += 1;
+        }
+        // 1/rの重みなら順位0は全体の約19%
+        assert!(counts[0] > 10, "{counts:?}");
+        assert!(counts[0] > counts[50..].iter().max().copied().unwrap());
+    }
+}
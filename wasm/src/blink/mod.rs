@@ -0,0 +1,7 @@
+mod race;
+mod tree;
+
+pub use race::{BLinkRace, RaceStep};
+pub use tree::{
+    BLinkNode, BLinkStep, BLinkTree, InsertCursor, PageId, SearchCursor,
+};
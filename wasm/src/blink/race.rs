@@ -0,0 +1,343 @@
+use crate::blink::tree::{
+    BLinkStep, BLinkTree, InsertCursor, SearchCursor,
+};
+use crate::json::JsonValue;
+use wasm_bindgen::prelude::*;
+
+/// 交互実行の1ステップ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RaceStep {
+    pub tick: usize,
+
+    /// 's' (探索) か 'i' (挿入)
+    pub actor: char,
+    pub step: BLinkStep,
+}
+
+/// 探索と挿入を決まった順で交互に1ステップずつ進める
+///
+/// 挿入がノードを分割している間に、探索が分割前の親から古いノードへ
+/// 下りてくる様子を再現する。右リンクを使わない探索と比べれば、
+/// リンクがないと移動したキーを見失うことが分かる
+#[wasm_bindgen]
+pub struct BLinkRace {
+    before: BLinkTree,
+    tree: BLinkTree,
+    search_key: i32,
+    insert_key: i32,
+    steps: Vec<RaceStep>,
+    found: bool,
+}
+
+#[wasm_bindgen]
+impl BLinkRace {
+    /// keysを入れた木で、search_keyの探索とinsert_keyの挿入を競わせる
+    ///
+    /// scheduleの各文字 ('s'か'i') が次に進める側。
+    /// 終わった側の文字は飛ばし、使い切ったら探索、挿入の順に最後まで進める
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        t: usize,
+        keys: Vec<i32>,
+        search_key: i32,
+        insert_key: i32,
+        schedule: &str,
+        follow_links: bool,
+    ) -> Result<BLinkRace, String> {
+        let mut tree = BLinkTree::new(t)?;
+        for k in keys {
+            tree.insert(k);
+        }
+        let before = tree.clone();
+        let mut search =
+            SearchCursor::new(&tree, search_key, follow_links);
+        let mut insert = InsertCursor::new(&tree, insert_key);
+
+        let mut order = Vec::new();
+        for c in schedule.chars().filter(|c| !c.is_whitespace()) {
+            match c {
+                's' | 'i' => order.push(c),
+                _ => return Err(format!("unknown actor: {c}")),
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut order = order.into_iter();
+        loop {
+            let search_done = search.result().is_some();
+            let insert_done = insert.result().is_some();
+            let actor = match order.next() {
+                Some('s') if !search_done => 's',
+                Some('i') if !insert_done => 'i',
+                Some(_) => continue,
+                None if !search_done => 's',
+                None if !insert_done => 'i',
+                None => break,
+            };
+            let step = match actor {
+                's' => search.step(&tree),
+                _ => insert.step(&mut tree),
+            };
+            steps.push(RaceStep {
+                tick: steps.len(),
+                actor,
+                step,
+            });
+        }
+
+        Ok(BLinkRace {
+            before,
+            tree,
+            search_key,
+            insert_key,
+            steps,
+            found: search.result().unwrap(),
+        })
+    }
+
+    /// 探索がキーを見つけたか
+    #[wasm_bindgen]
+    pub fn get_found(&self) -> bool {
+        self.found
+    }
+
+    /// 探索の答えが、挿入の前後どちらかの木の答えと一致するか
+    #[wasm_bindgen]
+    pub fn is_correct(&self) -> bool {
+        let before = self.before.search(self.search_key);
+        let after = before || self.search_key == self.insert_key;
+        self.found == before || self.found == after
+    }
+
+    /// 各ステップ ([{tick, actor, type, page, ...}])
+    #[wasm_bindgen]
+    pub fn get_steps(&self) -> JsValue {
+        JsonValue::Array(
+            self.steps
+                .iter()
+                .map(|s| {
+                    let actor = match s.actor {
+                        's' => "search",
+                        _ => "insert",
+                    };
+                    let JsonValue::Object(mut obj) = s.step.to_json()
+                    else {
+                        unreachable!("steps are objects");
+                    };
+                    obj.insert("tick".to_string(), s.tick.into());
+                    obj.insert("actor".to_string(), actor.into());
+                    JsonValue::Object(obj)
+                })
+                .collect(),
+        )
+        .to_js_value()
+    }
+
+    /// 競争の前の木
+    #[wasm_bindgen]
+    pub fn get_before(&self) -> JsValue {
+        self.before.get_structure()
+    }
+
+    /// 競争の後の木
+    #[wasm_bindgen]
+    pub fn get_after(&self) -> JsValue {
+        self.tree.get_structure()
+    }
+}
+
+impl BLinkRace {
+    pub fn steps(&self) -> &[RaceStep] {
+        &self.steps
+    }
+
+    pub fn tree(&self) -> &BLinkTree {
+        &self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blink::tree::BLinkNode;
+    use crate::rng::SplitMix64;
+    use std::collections::BTreeSet;
+
+    /// 各段が右リンクで左から順につながり、キーが上限キー以下に収まっているか
+    fn check(tree: &BLinkTree) {
+        let t = tree.get_min_degree();
+        let mut first = Some(tree.root());
+        while let Some(start) = first {
+            let mut page = Some(start);
+            let mut lower: Option<i32> = None;
+            while let Some(p) = page {
+                let node: &BLinkNode = tree.node(p);
+                assert!(node.keys.len() < 2 * t);
+                assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+                let in_range = |&k: &i32| {
+                    lower.is_none_or(|l| k > l)
+                        && node.high_key.is_none_or(|h| k <= h)
+                };
+                assert!(node.keys.iter().all(in_range));
+                assert_eq!(node.high_key.is_none(), node.right.is_none());
+                if !node.leaf {
+                    assert_eq!(node.children.len(), node.keys.len() + 1);
+                }
+                lower = node.high_key;
+                page = node.right;
+            }
+            first = tree.node(start).children.first().copied();
+        }
+    }
+
+    #[test]
+    fn test_sequential_operations() {
+        let mut rng = SplitMix64::new(21);
+        let mut tree = BLinkTree::new(2).unwrap();
+        let mut expected = BTreeSet::new();
+        for _ in 0..600 {
+            let k = (rng.next_u64() % 300) as i32;
+            if rng.chance(0.2) {
+                assert_eq!(tree.delete(k), expected.remove(&k));
+            } else {
+                assert_eq!(tree.insert(k), expected.insert(k));
+            }
+        }
+        check(&tree);
+        let leaves: Vec<i32> = tree.levels().last().unwrap().concat();
+        assert_eq!(leaves, expected.iter().copied().collect::<Vec<_>>());
+        for k in 0..300 {
+            assert_eq!(tree.search(k), expected.contains(&k));
+        }
+        assert!(tree.get_height() > 2);
+    }
+
+    #[test]
+    fn test_interleaved_inserts() {
+        // 複数の挿入を1ステップずつ混ぜても、親の分割や根の成長に追いつける
+        let mut rng = SplitMix64::new(4);
+        for _ in 0..200 {
+            let mut tree = BLinkTree::new(2).unwrap();
+            let mut cursors: Vec<InsertCursor> = Vec::new();
+            let mut expected = BTreeSet::new();
+            for _ in 0..60 {
+                if cursors.len() < 4 && rng.chance(0.3) {
+                    let k = (rng.next_u64() % 1000) as i32;
+                    expected.insert(k);
+                    cursors.push(InsertCursor::new(&tree, k));
+                }
+                if !cursors.is_empty() {
+                    let i =
+                        (rng.next_u64() % cursors.len() as u64) as usize;
+                    cursors[i].step(&mut tree);
+                    cursors.retain(|c| c.result().is_none());
+                }
+            }
+            while let Some(cursor) = cursors.last_mut() {
+                cursor.step(&mut tree);
+                cursors.retain(|c| c.result().is_none());
+            }
+            check(&tree);
+            assert!(expected.iter().all(|&k| tree.search(k)));
+        }
+    }
+
+    // 根 [20] の下に葉 [10, 20] と [30, 40, 50]。
+    // 45を入れると右の葉が分割され、50が新しい葉へ移る
+    const KEYS: [i32; 5] = [10, 20, 30, 40, 50];
+
+    #[test]
+    fn test_search_follows_right_link_after_split() {
+        // 探索が根を読んでから、挿入が右の葉を分割し終える
+        let race =
+            BLinkRace::new(2, KEYS.to_vec(), 50, 45, "s iiii", true)
+                .unwrap();
+        let kinds: Vec<(char, &str)> = race
+            .steps()
+            .iter()
+            .map(|s| (s.actor, s.step.kind()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ('s', "visit"),
+                ('i', "visit"),
+                ('i', "visit"),
+                ('i', "split"),
+                ('i', "link"),
+                ('s', "moveRight"),
+                ('s', "search"),
+            ]
+        );
+        assert!(race.get_found());
+        assert!(race.is_correct());
+        assert_eq!(
+            race.tree().levels()[1],
+            vec![vec![10, 20], vec![30, 40], vec![45, 50]]
+        );
+
+        // リンクをたどらなければ50を見失う
+        let race =
+            BLinkRace::new(2, KEYS.to_vec(), 50, 45, "s iiii", false)
+                .unwrap();
+        assert!(!race.get_found());
+        assert!(!race.is_correct());
+        assert!(BLinkRace::new(2, vec![], 1, 2, "sx", true).is_err());
+    }
+
+    #[test]
+    fn test_every_interleaving_is_correct() {
+        // 根の分割も起きる木で、全ての交互実行を試す
+        let keys: Vec<i32> = (1..=11).map(|k| k * 10).collect();
+        for search_key in [10, 60, 110, 115] {
+            for insert_key in [55, 105, 115, 5] {
+                let steps = BLinkRace::new(
+                    2,
+                    keys.clone(),
+                    search_key,
+                    insert_key,
+                    "",
+                    true,
+                )
+                .unwrap()
+                .steps()
+                .iter()
+                .map(|s| s.actor)
+                .collect::<Vec<_>>();
+                let searches = steps.iter().filter(|&&a| a == 's').count();
+                let n = steps.len();
+                let mut missed = false;
+                for mask in 0u32..(1 << n) {
+                    if mask.count_ones() as usize != searches {
+                        continue;
+                    }
+                    let schedule: String = (0..n)
+                        .map(
+                            |i| if mask >> i & 1 == 1 { 's' } else { 'i' },
+                        )
+                        .collect();
+                    for follow_links in [true, false] {
+                        let race = BLinkRace::new(
+                            2,
+                            keys.clone(),
+                            search_key,
+                            insert_key,
+                            &schedule,
+                            follow_links,
+                        )
+                        .unwrap();
+                        if follow_links {
+                            assert!(race.is_correct(), "{schedule}");
+                            check(race.tree());
+                        } else {
+                            missed |= !race.is_correct();
+                        }
+                    }
+                }
+                if (search_key, insert_key) == (110, 105) {
+                    assert!(missed);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use crate::json::JsonValue;
+use wasm_bindgen::prelude::*;
+
+/// ページ番号 (BLinkTree::nodesの添字)
+pub type PageId = usize;
+
+/// B-linkツリーのノード
+///
+/// 全てのキーは葉にあり、内部ノードのキーは子の範囲の上限。
+/// i番目の子は (keys[i-1], keys[i]] を、最後の子は high_key までを受け持つ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BLinkNode {
+    pub keys: Vec<i32>,
+    pub children: Vec<PageId>,
+
+    /// 同じ段の右隣のノード
+    pub right: Option<PageId>,
+
+    /// このノードに入るキーの上限 (Noneは上限なし)
+    pub high_key: Option<i32>,
+
+    /// 葉からの高さ (葉は0)
+    pub level: usize,
+    pub leaf: bool,
+}
+
+impl BLinkNode {
+    fn new(level: usize) -> Self {
+        BLinkNode {
+            keys: Vec::new(),
+            children: Vec::new(),
+            right: None,
+            high_key: None,
+            level,
+            leaf: level == 0,
+        }
+    }
+
+    /// keyがこのノードの範囲を超えていれば右隣へ移る
+    ///
+    /// 読んだ後で他の操作が分割してキーを右へ移していても、
+    /// 右リンクをたどれば必ず追いつける
+    pub fn move_right(&self, key: i32) -> Option<PageId> {
+        match self.high_key {
+            Some(high) if key > high => self.right,
+            _ => None,
+        }
+    }
+
+    /// keyを受け持つ子
+    pub fn child_for(&self, key: i32) -> PageId {
+        self.children[self.keys.partition_point(|&x| x < key)]
+    }
+}
+
+/// Lehman-YaoのB-linkツリー (キーの重複なし)
+///
+/// 各ノードは右隣へのリンクと上限キーを持つ。分割は右に新しいノードを作って
+/// 右リンクでつなぎ、親への区切りの挿入は後から行う。
+/// その間に下りてきた探索は、上限キーを超えたら右リンクをたどればよいので、
+/// 探索は一度に1つのノードしか押さえなくてよい。
+/// Lehman-Yaoと同じく、削除でノードをマージすることはない
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct BLinkTree {
+    t: usize,
+    nodes: Vec<BLinkNode>,
+    root: PageId,
+}
+
+#[wasm_bindgen]
+impl BLinkTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(t: usize) -> Result<BLinkTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(BLinkTree {
+            t,
+            nodes: vec![BLinkNode::new(0)],
+            root: 0,
+        })
+    }
+
+    /// キーkを挿入 (既にあればfalse)
+    pub fn insert(&mut self, k: i32) -> bool {
+        let mut cursor = InsertCursor::new(self, k);
+        while cursor.result().is_none() {
+            cursor.step(self);
+        }
+        cursor.result().unwrap()
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        let mut cursor = SearchCursor::new(self, k, true);
+        while cursor.result().is_none() {
+            cursor.step(self);
+        }
+        cursor.result().unwrap()
+    }
+
+    /// キーkを葉から消す (ノードが少なくなってもマージしない)
+    pub fn delete(&mut self, k: i32) -> bool {
+        let mut page = self.root;
+        loop {
+            let node = &self.nodes[page];
+            page = match node.move_right(k) {
+                Some(right) => right,
+                None if node.leaf => break,
+                None => node.child_for(k),
+            };
+        }
+        let keys = &mut self.nodes[page].keys;
+        match keys.binary_search(&k) {
+            Ok(pos) => {
+                keys.remove(pos);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_height(&self) -> usize {
+        self.nodes[self.root].level + 1
+    }
+
+    #[wasm_bindgen]
+    pub fn get_min_degree(&self) -> usize {
+        self.t
+    }
+
+    /// 木の構造 ({id, keys, children, isLeaf, highKey, right, level})
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        self.to_json().to_js_value()
+    }
+}
+
+impl BLinkTree {
+    pub fn root(&self) -> PageId {
+        self.root
+    }
+
+    pub fn node(&self, page: PageId) -> &BLinkNode {
+        &self.nodes[page]
+    }
+
+    pub fn nodes(&self) -> &[BLinkNode] {
+        &self.nodes
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        self.page_to_json(self.root)
+    }
+
+    fn page_to_json(&self, page: PageId) -> JsonValue {
+        let node = &self.nodes[page];
+        JsonValue::object([
+            ("id", page.into()),
+            ("keys", node.keys.clone().into()),
+            (
+                "children",
+                JsonValue::Array(
+                    node.children
+                        .iter()
+                        .map(|&c| self.page_to_json(c))
+                        .collect(),
+                ),
+            ),
+            ("isLeaf", node.leaf.into()),
+            ("highKey", node.high_key.into()),
+            ("right", node.right.into()),
+            ("level", node.level.into()),
+        ])
+    }
+
+    /// 段ごとのノードのキー (根の段から、右リンクをたどった順)
+    pub fn levels(&self) -> Vec<Vec<Vec<i32>>> {
+        let mut levels = Vec::new();
+        let mut first = Some(self.root);
+        while let Some(start) = first {
+            let mut level = Vec::new();
+            let mut page = Some(start);
+            while let Some(p) = page {
+                level.push(self.nodes[p].keys.clone());
+                page = self.nodes[p].right;
+            }
+            levels.push(level);
+            first = self.nodes[start].children.first().copied();
+        }
+        levels
+    }
+
+    fn overflows(&self, page: PageId) -> bool {
+        self.nodes[page].keys.len() > 2 * self.t - 1
+    }
+
+    /// あふれたノードの右半分を新しい右隣に移し、(右隣, 区切り) を返す
+    ///
+    /// 葉は区切りのキーを左に残し、内部ノードは区切りを親へ上げる
+    fn split(&mut self, page: PageId) -> (PageId, i32) {
+        let t = self.t;
+        let id = self.nodes.len();
+        let node = &mut self.nodes[page];
+        let mut right = BLinkNode::new(node.level);
+        let separator = if node.leaf {
+            right.keys = node.keys.split_off(t);
+            node.keys[t - 1]
+        } else {
+            right.keys = node.keys.split_off(t + 1);
+            right.children = node.children.split_off(t + 1);
+            node.keys.pop().unwrap()
+        };
+        right.right = node.right.replace(id);
+        right.high_key = node.high_key.replace(separator);
+        self.nodes.push(right);
+        (id, separator)
+    }
+
+    /// 根の段の全てのノードを子に持つ新しい根を作る
+    ///
+    /// 根の段では、親へ入れていない分割がいくつも重なっていることがある
+    fn grow(&mut self) {
+        let mut root = BLinkNode::new(self.nodes[self.root].level + 1);
+        let mut page = Some(self.root);
+        while let Some(p) = page {
+            root.children.push(p);
+            root.keys.extend(self.nodes[p].high_key);
+            page = self.nodes[p].right;
+        }
+        self.root = self.nodes.len();
+        self.nodes.push(root);
+    }
+
+    /// keyを受け持つ、段levelのノードを根から探す
+    fn locate(&self, key: i32, level: usize) -> PageId {
+        let mut page = self.root;
+        loop {
+            let node = &self.nodes[page];
+            page = match node.move_right(key) {
+                Some(right) => right,
+                None if node.level == level => return page,
+                None => node.child_for(key),
+            };
+        }
+    }
+}
+
+/// 操作の1ステップ (一度に読み書きするのは1つのノードだけ)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BLinkStep {
+    /// ノードを読んで子へ下りる (葉なら次は葉で操作する)
+    Visit { page: PageId },
+
+    /// 上限キーを超えたので右隣へ移った
+    MoveRight { from: PageId, to: PageId },
+
+    /// 上限キーを超えていたが、右リンクを使わずにそのまま探した
+    MissedRight { page: PageId },
+
+    /// 葉で探索を終えた
+    Search { page: PageId, found: bool },
+
+    /// 葉にキーを入れた
+    Insert { page: PageId, key: i32 },
+
+    /// 既にキーがあったので何もしなかった
+    Duplicate { page: PageId },
+
+    /// pageにkey (葉ならキー、内部ノードなら区切り) を入れてあふれたので、
+    /// 分割して右隣rightを作った
+    Split {
+        page: PageId,
+        key: i32,
+        right: PageId,
+        separator: i32,
+    },
+
+    /// 親pageに区切りと右の子を入れた
+    Link {
+        page: PageId,
+        separator: i32,
+        child: PageId,
+    },
+
+    /// 親pageには、他の操作が作った根として既にchildが入っていた
+    Linked { page: PageId, child: PageId },
+
+    /// 根の段が分割され、新しい根pageができた
+    NewRoot { page: PageId },
+}
+
+impl BLinkStep {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BLinkStep::Visit { .. } => "visit",
+            BLinkStep::MoveRight { .. } => "moveRight",
+            BLinkStep::MissedRight { .. } => "missedRight",
+            BLinkStep::Search { .. } => "search",
+            BLinkStep::Insert { .. } => "insert",
+            BLinkStep::Duplicate { .. } => "duplicate",
+            BLinkStep::Split { .. } => "split",
+            BLinkStep::Link { .. } => "link",
+            BLinkStep::Linked { .. } => "linked",
+            BLinkStep::NewRoot { .. } => "newRoot",
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let JsonValue::Object(mut obj) = (match *self {
+            BLinkStep::Visit { page }
+            | BLinkStep::MissedRight { page }
+            | BLinkStep::Duplicate { page }
+            | BLinkStep::NewRoot { page } => {
+                JsonValue::object([("page", page.into())])
+            }
+            BLinkStep::Linked { page, child } => JsonValue::object([
+                ("page", page.into()),
+                ("child", child.into()),
+            ]),
+            BLinkStep::MoveRight { from, to } => JsonValue::object([
+                ("page", from.into()),
+                ("to", to.into()),
+            ]),
+            BLinkStep::Search { page, found } => JsonValue::object([
+                ("page", page.into()),
+                ("found", found.into()),
+            ]),
+            BLinkStep::Insert { page, key } => JsonValue::object([
+                ("page", page.into()),
+                ("key", key.into()),
+            ]),
+            BLinkStep::Split {
+                page,
+                key,
+                right,
+                separator,
+            } => JsonValue::object([
+                ("page", page.into()),
+                ("key", key.into()),
+                ("right", right.into()),
+                ("separator", separator.into()),
+            ]),
+            BLinkStep::Link {
+                page,
+                separator,
+                child,
+            } => JsonValue::object([
+                ("page", page.into()),
+                ("separator", separator.into()),
+                ("child", child.into()),
+            ]),
+        }) else {
+            unreachable!("steps are objects");
+        };
+        obj.insert("type".to_string(), self.kind().into());
+        JsonValue::Object(obj)
+    }
+}
+
+/// 1ステップずつ進める探索
+#[derive(Clone, Debug)]
+pub struct SearchCursor {
+    key: i32,
+    page: PageId,
+
+    // falseなら右リンクを無視する (リンクのないB-Treeの振る舞い)
+    follow_links: bool,
+    result: Option<bool>,
+}
+
+impl SearchCursor {
+    pub fn new(tree: &BLinkTree, key: i32, follow_links: bool) -> Self {
+        SearchCursor {
+            key,
+            page: tree.root,
+            follow_links,
+            result: None,
+        }
+    }
+
+    /// 終わっていればキーが見つかったか
+    pub fn result(&self) -> Option<bool> {
+        self.result
+    }
+
+    pub fn step(&mut self, tree: &BLinkTree) -> BLinkStep {
+        let page = self.page;
+        let node = &tree.nodes[page];
+        match node.move_right(self.key) {
+            Some(to) if self.follow_links => {
+                self.page = to;
+                return BLinkStep::MoveRight { from: page, to };
+            }
+            Some(_) if node.leaf => {}
+            Some(_) => {
+                self.page = node.child_for(self.key);
+                return BLinkStep::MissedRight { page };
+            }
+            None => {}
+        }
+        if node.leaf {
+            let found = node.keys.binary_search(&self.key).is_ok();
+            self.result = Some(found);
+            BLinkStep::Search { page, found }
+        } else {
+            self.page = node.child_for(self.key);
+            BLinkStep::Visit { page }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum InsertState {
+    Descend(PageId),
+    Leaf(PageId),
+
+    /// 区切りを親へ入れる (Noneなら親をこれから決める)
+    Parent {
+        page: Option<PageId>,
+        left: PageId,
+        right: PageId,
+        separator: i32,
+    },
+    Done(bool),
+}
+
+/// 1ステップずつ進める挿入
+///
+/// 下りるときに通った内部ノードを覚えておき、分割したらそこへ区切りを入れる。
+/// 覚えていた親が分割されていれば、親の段でも右リンクをたどる
+#[derive(Clone, Debug)]
+pub struct InsertCursor {
+    key: i32,
+    state: InsertState,
+    stack: Vec<PageId>,
+}
+
+impl InsertCursor {
+    pub fn new(tree: &BLinkTree, key: i32) -> Self {
+        InsertCursor {
+            key,
+            state: InsertState::Descend(tree.root),
+            stack: Vec::new(),
+        }
+    }
+
+    /// 終わっていればキーを入れたか
+    pub fn result(&self) -> Option<bool> {
+        match self.state {
+            InsertState::Done(inserted) => Some(inserted),
+            _ => None,
+        }
+    }
+
+    pub fn step(&mut self, tree: &mut BLinkTree) -> BLinkStep {
+        let key = self.key;
+        match self.state.clone() {
+            InsertState::Descend(page) => {
+                let node = &tree.nodes[page];
+                if let Some(to) = node.move_right(key) {
+                    self.state = InsertState::Descend(to);
+                    return BLinkStep::MoveRight { from: page, to };
+                }
+                self.state = if node.leaf {
+                    InsertState::Leaf(page)
+                } else {
+                    self.stack.push(page);
+                    InsertState::Descend(node.child_for(key))
+                };
+                BLinkStep::Visit { page }
+            }
+            InsertState::Leaf(page) => {
+                // 読んでから書くまでの間に葉が分割されているかもしれない
+                if let Some(to) = tree.nodes[page].move_right(key) {
+                    self.state = InsertState::Leaf(to);
+                    return BLinkStep::MoveRight { from: page, to };
+                }
+                let keys = &mut tree.nodes[page].keys;
+                match keys.binary_search(&key) {
+                    Ok(_) => {
+                        self.state = InsertState::Done(false);
+                        BLinkStep::Duplicate { page }
+                    }
+                    Err(pos) => {
+                        keys.insert(pos, key);
+                        self.after_write(tree, page, key)
+                            .unwrap_or(BLinkStep::Insert { page, key })
+                    }
+                }
+            }
+            InsertState::Parent {
+                page,
+                left,
+                right,
+                separator,
+            } => {
+                let page = match page.or_else(|| self.stack.pop()) {
+                    Some(page) => page,
+                    None if tree.nodes[tree.root].level
+                        == tree.nodes[left].level =>
+                    {
+                        tree.grow();
+                        self.state = InsertState::Done(true);
+                        return BLinkStep::NewRoot { page: tree.root };
+                    }
+                    // 下りた後で根が高くなった
+                    None => {
+                        tree.locate(separator, tree.nodes[left].level + 1)
+                    }
+                };
+                let node = &mut tree.nodes[page];
+                if let Some(to) = node.move_right(separator) {
+                    self.state = InsertState::Parent {
+                        page: Some(to),
+                        left,
+                        right,
+                        separator,
+                    };
+                    return BLinkStep::MoveRight { from: page, to };
+                }
+                if node.children.contains(&right) {
+                    self.state = InsertState::Done(true);
+                    return BLinkStep::Linked { page, child: right };
+                }
+                // leftがその後さらに分割されていることもあるので、
+                // leftの位置ではなく区切りのキーで入れる場所を決める
+                let pos = node.keys.partition_point(|&x| x < separator);
+                node.keys.insert(pos, separator);
+                node.children.insert(pos + 1, right);
+                self.after_write(tree, page, separator).unwrap_or(
+                    BLinkStep::Link {
+                        page,
+                        separator,
+                        child: right,
+                    },
+                )
+            }
+            InsertState::Done(_) => {
+                unreachable!("finished cursors are not stepped")
+            }
+        }
+    }
+
+    /// pageにkeyを書いた後、あふれていれば同じステップのうちに分割する
+    ///
+    /// 書いてから分割するまでの間に他の操作が同じノードへ書くことはない
+    /// (Lehman-Yaoではノードのロックを持ったまま両方を行う)
+    fn after_write(
+        &mut self,
+        tree: &mut BLinkTree,
+        page: PageId,
+        key: i32,
+    ) -> Option<BLinkStep> {
+        if !tree.overflows(page) {
+            self.state = InsertState::Done(true);
+            return None;
+        }
+        let (right, separator) = tree.split(page);
+        self.state = InsertState::Parent {
+            page: None,
+            left: page,
+            right,
+            separator,
+        };
+        Some(BLinkStep::Split {
+            page,
+            key,
+            right,
+            separator,
+        })
+    }
+}
@@ -0,0 +1,5 @@
+mod node;
+mod operation;
+
+pub use node::BStarNode;
+pub use operation::{BStarTree, FillComparison, compare_fill};
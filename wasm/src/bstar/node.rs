@@ -0,0 +1,210 @@
+use wasm_bindgen::prelude::*;
+
+/// 挿入中に発生した構造変更の回数
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RebalanceCounts {
+    /// 兄弟ノードとの再分配の回数
+    pub redistributions: usize,
+
+    /// 2-to-3 分割の回数
+    pub splits: usize,
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct BStarNode {
+    /// キーの配列
+    keys: Vec<i32>,
+
+    /// 子ノードへのポインタ配列
+    children: Vec<BStarNode>,
+
+    /// 葉ノードかどうか
+    leaf: bool,
+}
+
+impl BStarNode {
+    pub fn new(leaf: bool) -> Self {
+        BStarNode {
+            keys: Vec::new(),
+            children: Vec::new(),
+            leaf,
+        }
+    }
+
+    pub fn keys(&self) -> Vec<i32> {
+        self.keys.clone()
+    }
+
+    pub fn keys_len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn leaf(&self) -> bool {
+        self.leaf
+    }
+
+    /// 子ノードへの参照を取得
+    pub fn child_nodes(&self) -> &[BStarNode] {
+        &self.children
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        let mut node = self;
+        loop {
+            let i = node.keys.partition_point(|&key| key < k);
+            if i < node.keys.len() && node.keys[i] == k {
+                return true;
+            }
+            if node.leaf {
+                return false;
+            }
+            node = &node.children[i];
+        }
+    }
+
+    /// キーkを葉に挿入し、あふれた子ノードを帰りがけに修復する
+    ///
+    /// このノード自身のあふれは親(根の場合は木)が処理する
+    pub fn insert(
+        &mut self,
+        k: i32,
+        max_keys: usize,
+        counts: &mut RebalanceCounts,
+    ) {
+        // k より大きい最初のキーの位置
+        let i = self.keys.partition_point(|&key| key <= k);
+
+        if self.leaf {
+            self.keys.insert(i, k);
+            return;
+        }
+
+        self.children[i].insert(k, max_keys, counts);
+
+        if self.children[i].keys.len() > max_keys {
+            self.fix_overflow(i, max_keys, counts);
+        }
+    }
+
+    /// あふれた子ノードC[i]を兄弟との再分配か 2-to-3 分割で修復
+    fn fix_overflow(
+        &mut self,
+        i: usize,
+        max_keys: usize,
+        counts: &mut RebalanceCounts,
+    ) {
+        // 左の兄弟に空きがあれば再分配
+        if i > 0 && self.children[i - 1].keys.len() < max_keys {
+            self.redistribute(i - 1);
+            counts.redistributions += 1;
+        }
+        // 右の兄弟に空きがあれば再分配
+        else if i + 1 < self.children.len()
+            && self.children[i + 1].keys.len() < max_keys
+        {
+            self.redistribute(i);
+            counts.redistributions += 1;
+        }
+        // 両方とも満杯の場合、満杯の兄弟と合わせて3つに分割
+        else if i + 1 < self.children.len() {
+            self.split_two_to_three(i);
+            counts.splits += 1;
+        } else {
+            self.split_two_to_three(i - 1);
+            counts.splits += 1;
+        }
+    }
+
+    /// C[i]とC[i+1]を取り出し、間の区切りキーと合わせて一列に並べる
+    fn take_pair(&mut self, i: usize) -> (Vec<i32>, Vec<BStarNode>) {
+        let right = self.children.remove(i + 1);
+        let separator = self.keys.remove(i);
+        let left = &mut self.children[i];
+
+        let mut keys = std::mem::take(&mut left.keys);
+        keys.push(separator);
+        keys.extend(right.keys);
+
+        let mut children = std::mem::take(&mut left.children);
+        children.extend(right.children);
+
+        (keys, children)
+    }
+
+    /// 隣り合う子ノードC[i]とC[i+1]のキーを均等に分け直す
+    fn redistribute(&mut self, i: usize) {
+        let leaf = self.children[i].leaf;
+        let (mut keys, mut children) = self.take_pair(i);
+
+        // 区切りキーを除いたキーを左右に振り分ける
+        let left_len = (keys.len() - 1) / 2;
+        let right_keys = keys.split_off(left_len + 1);
+        let separator = keys.pop().unwrap();
+
+        let mut right = BStarNode::new(leaf);
+        right.keys = right_keys;
+        if !leaf {
+            right.children = children.split_off(left_len + 1);
+        }
+
+        let left = &mut self.children[i];
+        left.keys = keys;
+        left.children = children;
+
+        self.keys.insert(i, separator);
+        self.children.insert(i + 1, right);
+    }
+
+    /// 満杯の子ノードC[i]とC[i+1]を3つのノードに分割
+    fn split_two_to_three(&mut self, i: usize) {
+        let leaf = self.children[i].leaf;
+        let (mut keys, mut children) = self.take_pair(i);
+
+        // 2つの区切りキーを除いたキーを3等分する
+        let rest = keys.len() - 2;
+        let first = rest / 3;
+        let second = (rest - first) / 2;
+
+        let third_keys = keys.split_off(first + second + 2);
+        let second_separator = keys.pop().unwrap();
+        let second_keys = keys.split_off(first + 1);
+        let first_separator = keys.pop().unwrap();
+
+        let mut third = BStarNode::new(leaf);
+        third.keys = third_keys;
+        let mut middle = BStarNode::new(leaf);
+        middle.keys = second_keys;
+        if !leaf {
+            third.children = children.split_off(first + second + 2);
+            middle.children = children.split_off(first + 1);
+        }
+
+        let left = &mut self.children[i];
+        left.keys = keys;
+        left.children = children;
+
+        self.keys.insert(i, first_separator);
+        self.keys.insert(i + 1, second_separator);
+        self.children.insert(i + 1, middle);
+        self.children.insert(i + 2, third);
+    }
+
+    /// あふれた根を中央で2つに分割し、新しい根を返す
+    pub fn split_root(mut self) -> BStarNode {
+        let mid = self.keys.len() / 2;
+
+        let mut right = BStarNode::new(self.leaf);
+        right.keys = self.keys.split_off(mid + 1);
+        if !self.leaf {
+            right.children = self.children.split_off(mid + 1);
+        }
+        let median = self.keys.pop().unwrap();
+
+        let mut root = BStarNode::new(false);
+        root.keys.push(median);
+        root.children.push(self);
+        root.children.push(right);
+        root
+    }
+}
@@ -0,0 +1,322 @@
+use crate::bstar::node::{BStarNode, RebalanceCounts};
+use crate::btree::{BTree, FillStats};
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+// B*-Tree
+//
+// あふれたノードはまず兄弟ノードとキーを再分配し、
+// 兄弟も満杯のときだけ2つのノードを3つに分割する(2-to-3 分割)。
+// これにより根以外のノードは常に約2/3以上埋まった状態に保たれる。
+#[wasm_bindgen]
+pub struct BStarTree {
+    // 根
+    root: Option<BStarNode>,
+
+    // 次数 (BTreeと同じく、ノードの最大キー数は 2t-1)
+    t: usize,
+
+    // 再分配と分割の回数
+    counts: RebalanceCounts,
+}
+
+#[wasm_bindgen]
+impl BStarTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(t: usize) -> Result<BStarTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(BStarTree {
+            root: None,
+            t,
+            counts: RebalanceCounts::default(),
+        })
+    }
+
+    /// 根以外のノードの最大キー数
+    pub fn max_keys(&self) -> usize {
+        2 * self.t - 1
+    }
+
+    /// 根以外のノードの最小キー数 (子の数が ⌈(2m-1)/3⌉ 以上, m = 2t)
+    pub fn min_keys(&self) -> usize {
+        (4 * self.t - 1).div_ceil(3) - 1
+    }
+
+    /// 根の最大キー数
+    ///
+    /// 根を分割してできる2つのノードが最小キー数を満たすよう、
+    /// 根は他のノードより多くのキーを持てる
+    pub fn root_max_keys(&self) -> usize {
+        2 * ((4 * self.t - 2) / 3)
+    }
+
+    /// キーkを探索
+    pub fn search(&self, k: i32) -> bool {
+        match &self.root {
+            Some(root) => root.search(k),
+            None => false,
+        }
+    }
+
+    pub fn insert(&mut self, k: i32) {
+        let max_keys = self.max_keys();
+        let mut root =
+            self.root.take().unwrap_or_else(|| BStarNode::new(true));
+
+        root.insert(k, max_keys, &mut self.counts);
+
+        // 根には兄弟がいないので、あふれたら2つに分割して高さを増やす
+        if root.keys_len() > self.root_max_keys() {
+            root = root.split_root();
+            self.counts.splits += 1;
+        }
+        self.root = Some(root);
+    }
+
+    fn node_to_js_value(node: &BStarNode) -> JsValue {
+        let obj = js_sys::Object::new();
+
+        let keys = Array::from_iter(
+            node.keys().iter().map(|key| JsValue::from(*key)),
+        );
+        let _ = js_sys::Reflect::set(&obj, &"keys".into(), &keys.into());
+
+        let children = Array::from_iter(
+            node.child_nodes().iter().map(Self::node_to_js_value),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"children".into(),
+            &children.into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"isLeaf".into(),
+            &JsValue::from(node.leaf()),
+        );
+
+        obj.into()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        match &self.root {
+            Some(root) => Self::node_to_js_value(root),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// キーの総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.fill_stats().keys
+    }
+
+    #[wasm_bindgen]
+    pub fn get_height(&self) -> usize {
+        let mut height = 0;
+        let mut node = self.root.as_ref();
+        while let Some(n) = node {
+            height += 1;
+            node = n.child_nodes().first();
+        }
+        height
+    }
+
+    /// 兄弟ノードとの再分配の回数
+    #[wasm_bindgen]
+    pub fn get_redistribution_count(&self) -> usize {
+        self.counts.redistributions
+    }
+
+    /// 分割の回数 (根の分割を含む)
+    #[wasm_bindgen]
+    pub fn get_split_count(&self) -> usize {
+        self.counts.splits
+    }
+
+    /// ノードの充填率の統計を取得
+    #[wasm_bindgen]
+    pub fn fill_stats(&self) -> FillStats {
+        let mut nodes = Vec::new();
+        if let Some(ref root) = self.root {
+            let mut stack = vec![(root, true)];
+            while let Some((node, is_root)) = stack.pop() {
+                let capacity = if is_root {
+                    self.root_max_keys()
+                } else {
+                    self.max_keys()
+                };
+                nodes.push((node.keys_len(), capacity, is_root));
+                stack
+                    .extend(node.child_nodes().iter().map(|c| (c, false)));
+            }
+        }
+        FillStats::collect(nodes)
+    }
+}
+
+/// 同じキー列を挿入した BTree と BStarTree の充填率の比較
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct FillComparison {
+    pub btree: FillStats,
+    pub bstar: FillStats,
+}
+
+/// 同じ次数tのBTreeとBStarTreeにkeysを順に挿入し、充填率を比較
+#[wasm_bindgen]
+pub fn compare_fill(
+    t: usize,
+    keys: Vec<i32>,
+) -> Result<FillComparison, String> {
+    let mut bstar = BStarTree::new(t)?;
+    let mut btree = BTree::new(t);
+    for &k in &keys {
+        btree.insert(k);
+        bstar.insert(k);
+    }
+
+    Ok(FillComparison {
+        btree: btree.fill_stats(),
+        bstar: bstar.fill_stats(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 木の不変条件を検査し、葉の深さを返す
+    fn check_node(
+        tree: &BStarTree,
+        node: &BStarNode,
+        is_root: bool,
+        lower: Option<i32>,
+        upper: Option<i32>,
+    ) -> usize {
+        let keys = node.keys();
+        let max = if is_root {
+            tree.root_max_keys()
+        } else {
+            tree.max_keys()
+        };
+        assert!(keys.len() <= max, "node {keys:?} overflows");
+        if !is_root {
+            assert!(
+                keys.len() >= tree.min_keys(),
+                "node {keys:?} is less than 2/3 full"
+            );
+        }
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+        assert!(keys.iter().all(|&k| lower.is_none_or(|l| k >= l)));
+        assert!(keys.iter().all(|&k| upper.is_none_or(|u| k <= u)));
+
+        if node.leaf() {
+            return 1;
+        }
+
+        let children = node.child_nodes();
+        assert_eq!(children.len(), keys.len() + 1);
+        let depths: Vec<usize> = children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| {
+                let lo = if i == 0 { lower } else { Some(keys[i - 1]) };
+                let hi = keys.get(i).copied().or(upper);
+                check_node(tree, child, false, lo, hi)
+            })
+            .collect();
+        assert!(depths.windows(2).all(|w| w[0] == w[1]));
+        depths[0] + 1
+    }
+
+    fn check(tree: &BStarTree) {
+        if let Some(ref root) = tree.root {
+            check_node(tree, root, true, None, None);
+        }
+    }
+
+    #[test]
+    fn test_bstar_insertion_and_search() {
+        let mut t = BStarTree::new(3).unwrap();
+
+        for k in [10, 20, 5, 6, 12, 30, 7, 17] {
+            t.insert(k);
+            check(&t);
+        }
+
+        assert!(t.search(6), "6 should be present");
+        assert!(!t.search(15), "15 should not be present");
+        assert_eq!(t.get_total_keys(), 8);
+    }
+
+    #[test]
+    fn test_bstar_sequential_keeps_two_thirds_full() {
+        for t in 2..=5 {
+            let mut tree = BStarTree::new(t).unwrap();
+            for k in 1..=300 {
+                tree.insert(k);
+                check(&tree);
+            }
+            for k in 1..=300 {
+                assert!(tree.search(k), "Key {k} should be present");
+            }
+            assert_eq!(tree.get_total_keys(), 300);
+        }
+    }
+
+    #[test]
+    fn test_bstar_pseudo_random_order() {
+        let mut tree = BStarTree::new(3).unwrap();
+        let mut x: i32 = 1;
+        let mut inserted = Vec::new();
+        for _ in 0..500 {
+            x = (x.wrapping_mul(1103515245).wrapping_add(12345)) & 0x7fff;
+            tree.insert(x);
+            inserted.push(x);
+            check(&tree);
+        }
+        assert!(inserted.iter().all(|&k| tree.search(k)));
+        assert_eq!(tree.get_total_keys(), inserted.len());
+    }
+
+    #[test]
+    fn test_bstar_redistributes_before_split() {
+        let mut t = BStarTree::new(2).unwrap();
+
+        // 根(最大4キー)があふれると2つの葉に分割される
+        for k in 1..=5 {
+            t.insert(k);
+        }
+        assert_eq!(t.get_height(), 2);
+        assert_eq!(t.get_split_count(), 1);
+
+        // 右の葉があふれても、左の葉に空きがあるので再分配される
+        t.insert(6);
+        t.insert(7);
+        assert_eq!(t.get_split_count(), 1);
+        assert!(t.get_redistribution_count() > 0);
+        check(&t);
+    }
+
+    #[test]
+    fn test_compare_fill_favors_bstar() {
+        let keys: Vec<i32> = (1..=1000).collect();
+        let comparison = compare_fill(3, keys).unwrap();
+
+        assert_eq!(comparison.btree.keys, 1000);
+        assert_eq!(comparison.bstar.keys, 1000);
+        assert!(
+            comparison.bstar.average_fill > comparison.btree.average_fill
+        );
+        assert!(comparison.bstar.min_fill >= 2.0 / 3.0 - 0.1);
+        assert!(comparison.bstar.nodes < comparison.btree.nodes);
+
+        assert!(compare_fill(1, vec![1, 2, 3]).is_err());
+        assert!(compare_fill(0, vec![]).is_err());
+        assert!(BStarTree::new(1).is_err());
+    }
+}
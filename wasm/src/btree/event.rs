@@ -0,0 +1,200 @@
+use crate::locale::Message;
+use crate::trace::{NodeId, NodeIds, TraceEvent, Tracer};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// 挿入・削除中にノードの構造が変わったことを表すイベント
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BTreeEvent {
+    /// 満杯のノードkeysを中央のキーmedianで分割
+    Split { keys: Vec<i32>, median: i32 },
+
+    /// 左右の兄弟ノードと親の区切りキーをマージ
+    Merge {
+        left: Vec<i32>,
+        separator: i32,
+        right: Vec<i32>,
+    },
+
+    /// 左の兄弟から借りる
+    /// (親のparent_keyが子へ下り、兄弟のsibling_keyが親へ上がる)
+    BorrowFromPrev { parent_key: i32, sibling_key: i32 },
+
+    /// 右の兄弟から借りる
+    BorrowFromNext { parent_key: i32, sibling_key: i32 },
+
+    /// 内部ノードのキーkeyを前駆predecessorで置き換え
+    ReplaceWithPredecessor { key: i32, predecessor: i32 },
+
+    /// 内部ノードのキーkeyを後継successorで置き換え
+    ReplaceWithSuccessor { key: i32, successor: i32 },
+}
+
+fn keys_to_array(keys: &[i32]) -> Array {
+    Array::from_iter(keys.iter().map(|k| JsValue::from(*k)))
+}
+
+impl BTreeEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BTreeEvent::Split { .. } => "split",
+            BTreeEvent::Merge { .. } => "merge",
+            BTreeEvent::BorrowFromPrev { .. } => "borrowFromPrev",
+            BTreeEvent::BorrowFromNext { .. } => "borrowFromNext",
+            BTreeEvent::ReplaceWithPredecessor { .. } => {
+                "replaceWithPredecessor"
+            }
+            BTreeEvent::ReplaceWithSuccessor { .. } => {
+                "replaceWithSuccessor"
+            }
+        }
+    }
+
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        set("type", self.kind().into());
+        match self {
+            BTreeEvent::Split { keys, median } => {
+                set("keys", keys_to_array(keys).into());
+                set("median", (*median).into());
+            }
+            BTreeEvent::Merge {
+                left,
+                separator,
+                right,
+            } => {
+                set("left", keys_to_array(left).into());
+                set("separator", (*separator).into());
+                set("right", keys_to_array(right).into());
+            }
+            BTreeEvent::BorrowFromPrev {
+                parent_key,
+                sibling_key,
+            }
+            | BTreeEvent::BorrowFromNext {
+                parent_key,
+                sibling_key,
+            } => {
+                set("parentKey", (*parent_key).into());
+                set("siblingKey", (*sibling_key).into());
+            }
+            BTreeEvent::ReplaceWithPredecessor { key, predecessor } => {
+                set("key", (*key).into());
+                set("replacement", (*predecessor).into());
+            }
+            BTreeEvent::ReplaceWithSuccessor { key, successor } => {
+                set("key", (*key).into());
+                set("replacement", (*successor).into());
+            }
+        }
+
+        obj.into()
+    }
+}
+
+impl From<&BTreeEvent> for Message {
+    fn from(event: &BTreeEvent) -> Self {
+        match event.clone() {
+            BTreeEvent::Split { keys, median } => {
+                Message::Split { keys, median }
+            }
+            BTreeEvent::Merge {
+                left,
+                separator,
+                right,
+            } => Message::Merge {
+                left,
+                separator,
+                right,
+            },
+            BTreeEvent::BorrowFromPrev {
+                parent_key,
+                sibling_key,
+            } => Message::BorrowFromPrev {
+                parent_key,
+                sibling_key,
+            },
+            BTreeEvent::BorrowFromNext {
+                parent_key,
+                sibling_key,
+            } => Message::BorrowFromNext {
+                parent_key,
+                sibling_key,
+            },
+            BTreeEvent::ReplaceWithPredecessor { key, predecessor } => {
+                Message::ReplaceWithPredecessor { key, predecessor }
+            }
+            BTreeEvent::ReplaceWithSuccessor { key, successor } => {
+                Message::ReplaceWithSuccessor { key, successor }
+            }
+        }
+    }
+}
+
+/// 操作中のイベントの記録先
+///
+/// 構造変更は`BTreeEvent`として、各ステップは共通のトレースとして記録する
+pub(crate) struct Recorder<'a> {
+    events: &'a mut Vec<BTreeEvent>,
+    ids: &'a mut NodeIds,
+    tracer: &'a mut dyn Tracer,
+}
+
+impl<'a> Recorder<'a> {
+    pub(crate) fn new(
+        events: &'a mut Vec<BTreeEvent>,
+        ids: &'a mut NodeIds,
+        tracer: &'a mut dyn Tracer,
+    ) -> Self {
+        Recorder {
+            events,
+            ids,
+            tracer,
+        }
+    }
+
+    /// 木の中でまだ使われていない識別子
+    pub(crate) fn fresh_id(&mut self) -> NodeId {
+        self.ids.fresh()
+    }
+
+    /// 構造変更を記録し、その説明をトレースにも残す
+    pub(crate) fn event(&mut self, event: BTreeEvent) {
+        self.annotate(|| (&event).into());
+        self.events.push(event);
+    }
+
+    pub(crate) fn annotate(&mut self, message: impl FnOnce() -> Message) {
+        self.trace(|| TraceEvent::Annotate { message: message() });
+    }
+
+    /// トレースが有効なときだけイベントを作って報告する
+    pub(crate) fn trace(&mut self, event: impl FnOnce() -> TraceEvent) {
+        if self.tracer.enabled() {
+            self.tracer.record(event());
+        }
+    }
+
+    /// 子childの親をfromからtoに付け替え、toのindex番目の子にする
+    pub(crate) fn relink(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        child: NodeId,
+        index: usize,
+    ) {
+        self.trace(|| TraceEvent::Unlink {
+            parent: from,
+            child,
+        });
+        self.trace(|| TraceEvent::Link {
+            parent: to,
+            child,
+            index,
+        });
+    }
+}
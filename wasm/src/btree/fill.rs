@@ -0,0 +1,52 @@
+use wasm_bindgen::prelude::*;
+
+/// ノードの充填率に関する統計
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FillStats {
+    /// ノード数
+    pub nodes: usize,
+
+    /// キーの総数
+    pub keys: usize,
+
+    /// 全ノードに格納できるキーの総数
+    pub capacity: usize,
+
+    /// 平均充填率 (keys / capacity)
+    pub average_fill: f64,
+
+    /// 根以外のノードの最小充填率
+    /// 根しかない場合は根の充填率
+    pub min_fill: f64,
+}
+
+impl FillStats {
+    /// (キー数, 最大キー数, 根かどうか) の列から統計を求める
+    pub(crate) fn collect(
+        nodes: impl IntoIterator<Item = (usize, usize, bool)>,
+    ) -> Self {
+        let mut stats = FillStats::default();
+        let mut min_fill: Option<f64> = None;
+        let mut root_fill = 0.0;
+
+        for (keys, capacity, is_root) in nodes {
+            stats.nodes += 1;
+            stats.keys += keys;
+            stats.capacity += capacity;
+
+            let fill = keys as f64 / capacity as f64;
+            if is_root {
+                root_fill = fill;
+            } else {
+                min_fill = Some(min_fill.map_or(fill, |m| m.min(fill)));
+            }
+        }
+
+        if stats.capacity > 0 {
+            stats.average_fill = stats.keys as f64 / stats.capacity as f64;
+        }
+        stats.min_fill = min_fill.unwrap_or(root_fill);
+        stats
+    }
+}
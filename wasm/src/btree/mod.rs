@@ -1,5 +1,15 @@
+mod event;
+mod fill;
 mod node;
 mod operation;
+mod stats;
+mod strategy;
 
+pub use event::BTreeEvent;
+pub use fill::FillStats;
 pub use node::BTreeNode;
-pub use operation::BTree;
+pub use operation::{BTree, MAX_MIN_DEGREE};
+pub use stats::TreeStats;
+pub use strategy::{
+    OperationStats, SplitStrategy, StrategyComparison, compare_strategies,
+};
@@ -1,3 +1,5 @@
+use crate::btree::event::{BTreeEvent, Recorder};
+use crate::trace::{NodeId, TraceEvent};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -7,6 +9,7 @@ pub struct BTreeNode {
     keys: Vec<i32>,
 
     /// 子ノードへのポインタ配列
+    #[allow(clippy::vec_box)]
     children: Vec<Box<BTreeNode>>,
 
     /// 最小次数
@@ -14,15 +17,37 @@ pub struct BTreeNode {
 
     /// 葉ノードかどうか
     leaf: bool,
+
+    /// トレースで使う識別子
+    id: NodeId,
 }
 
 impl BTreeNode {
-    pub fn new(t: usize, leaf: bool) -> Self {
+    pub fn new(t: usize, leaf: bool, id: NodeId) -> Self {
         BTreeNode {
             keys: Vec::with_capacity(2 * t - 1),
             children: Vec::with_capacity(2 * t),
             t,
             leaf,
+            id,
+        }
+    }
+
+    /// キーと子ノードを指定してノードを作成
+    ///
+    /// 子ノードが空の場合は葉ノードになる
+    pub fn with_keys(
+        t: usize,
+        keys: Vec<i32>,
+        children: Vec<Box<BTreeNode>>,
+        id: NodeId,
+    ) -> Self {
+        BTreeNode {
+            leaf: children.is_empty(),
+            keys,
+            children,
+            t,
+            id,
         }
     }
 
@@ -65,7 +90,8 @@ impl BTreeNode {
         }
     }
 
-    pub fn insert_not_full(&mut self, k: i32) {
+    pub(crate) fn insert_not_full(&mut self, k: i32, rec: &mut Recorder) {
+        rec.trace(|| TraceEvent::Visit { node: self.id });
         let mut i = self.keys.len() as i32 - 1;
 
         if self.leaf {
@@ -80,6 +106,11 @@ impl BTreeNode {
 
             // 見つかった位置に新しいキーを挿入
             self.keys[(i + 1) as usize] = k;
+            rec.trace(|| TraceEvent::MoveKey {
+                key: k,
+                from: None,
+                to: Some(self.id),
+            });
         } else {
             // 葉ノードではない場合
             // 新しいキーを持つ子ノードを見つける
@@ -92,7 +123,7 @@ impl BTreeNode {
             // 見つかった子ノードが満杯かチェック
             if self.children[child_idx].keys.len() == 2 * self.t - 1 {
                 // 子ノードが満杯の場合、分割
-                self.split_child(child_idx);
+                self.split_child(child_idx, rec);
 
                 // 分割後、C[i]の中央のキーが上に移動し、
                 // C[i]が二つに分割される。どちらが新しいキーを
@@ -101,16 +132,45 @@ impl BTreeNode {
                     i += 1;
                 }
             }
-            self.children[(i + 1) as usize].insert_not_full(k);
+            self.children[(i + 1) as usize].insert_not_full(k, rec);
         }
     }
 
-    pub fn split_child(&mut self, i: usize) {
+    /// キーkを葉に入れ、あふれた子は戻る途中で分割する (ボトムアップ)
+    ///
+    /// このノードのキーが2t個にあふれたらtrueを返し、親に分割を任せる
+    pub(crate) fn insert_bottom_up(
+        &mut self,
+        k: i32,
+        rec: &mut Recorder,
+    ) -> bool {
+        rec.trace(|| TraceEvent::Visit { node: self.id });
+        // 同じキーの後ろに入れる
+        let i = self.keys.partition_point(|&key| key <= k);
+        if self.leaf {
+            self.keys.insert(i, k);
+            rec.trace(|| TraceEvent::MoveKey {
+                key: k,
+                from: None,
+                to: Some(self.id),
+            });
+        } else if self.children[i].insert_bottom_up(k, rec) {
+            self.split_child(i, rec);
+        }
+        self.keys.len() > self.max_keys()
+    }
+
+    pub(crate) fn split_child(&mut self, i: usize, rec: &mut Recorder) {
         let t = self.t;
+        let parent = self.id;
         let y = &mut self.children[i];
+        rec.event(BTreeEvent::Split {
+            keys: y.keys.clone(),
+            median: y.keys[t - 1],
+        });
 
         // yの(t-1)個のキーを格納する新しいノードを作成
-        let mut z = Box::new(BTreeNode::new(t, y.leaf));
+        let mut z = Box::new(BTreeNode::new(t, y.leaf, rec.fresh_id()));
 
         // yの最後の(t-1)個のキーをzにコピー
         z.keys = y.keys.split_off(t);
@@ -123,6 +183,32 @@ impl BTreeNode {
         // yのキーの数を減らす(中央のキーを取り出す)
         let middle_key = y.keys.pop().unwrap();
 
+        let (y_id, z_id) = (y.id, z.id);
+        rec.trace(|| TraceEvent::CreateNode {
+            node: z_id,
+            keys: Vec::new(),
+        });
+        for &key in &z.keys {
+            rec.trace(|| TraceEvent::MoveKey {
+                key,
+                from: Some(y_id),
+                to: Some(z_id),
+            });
+        }
+        for (index, c) in z.children.iter().enumerate() {
+            rec.relink(y_id, z_id, c.id, index);
+        }
+        rec.trace(|| TraceEvent::MoveKey {
+            key: middle_key,
+            from: Some(y_id),
+            to: Some(parent),
+        });
+        rec.trace(|| TraceEvent::Link {
+            parent,
+            child: z_id,
+            index: i + 1,
+        });
+
         // このノードに新しい子ノードを追加するスペースを作成
         self.children.insert(i + 1, z);
 
@@ -135,6 +221,11 @@ impl BTreeNode {
         self.keys.len() == 2 * self.t - 1
     }
 
+    /// ノードに格納できる最大のキー数
+    pub fn max_keys(&self) -> usize {
+        2 * self.t - 1
+    }
+
     /// キーの数を取得
     pub fn keys_len(&self) -> usize {
         self.keys.len()
@@ -157,6 +248,19 @@ impl BTreeNode {
         self.children.clone()
     }
 
+    /// 子ノードへの参照を取得
+    pub fn child_nodes(&self) -> &[Box<BTreeNode>] {
+        &self.children
+    }
+
+    /// このノードとキー・子の配列が確保しているバイト数 (子ノード自身は含まない)
+    pub fn allocated_bytes(&self) -> usize {
+        std::mem::size_of::<BTreeNode>()
+            + self.keys.capacity() * std::mem::size_of::<i32>()
+            + self.children.capacity()
+                * std::mem::size_of::<Box<BTreeNode>>()
+    }
+
     /// 最初のキーを取得(存在する場合)
     pub fn first_key(&self) -> Option<i32> {
         self.keys.first().copied()
@@ -175,8 +279,15 @@ impl BTreeNode {
         self.children.get_mut(index)
     }
 
+    /// トレースで使う識別子
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
     /// キーkを削除
-    pub fn delete(&mut self, k: i32) -> bool {
+    pub(crate) fn delete(&mut self, k: i32, rec: &mut Recorder) -> bool {
+        rec.trace(|| TraceEvent::Visit { node: self.id });
+
         // キーkが存在するか確認
         let idx = self.find_key_index(k);
 
@@ -185,10 +296,15 @@ impl BTreeNode {
             if self.leaf {
                 // 葉ノードの場合、直接削除
                 self.keys.remove(idx);
+                rec.trace(|| TraceEvent::MoveKey {
+                    key: k,
+                    from: Some(self.id),
+                    to: None,
+                });
                 true
             } else {
                 // 内部ノードの場合
-                self.delete_from_internal_node(idx)
+                self.delete_from_internal_node(idx, rec)
             }
         } else {
             // キーが見つからない
@@ -196,9 +312,50 @@ impl BTreeNode {
                 false
             } else {
                 // 子ノードで削除を試みる
-                self.delete_from_subtree(idx, k)
+                self.delete_from_subtree(idx, k, rec)
+            }
+        }
+    }
+
+    /// キーkを削除し、足りなくなった子は戻る途中で補強する (ボトムアップ)
+    ///
+    /// 内部ノードのキーは前駆で置き換え、前駆を葉から消す。
+    /// キーがt-2個になった子だけを、兄弟から借りるかマージして直す
+    pub(crate) fn delete_bottom_up(
+        &mut self,
+        k: i32,
+        rec: &mut Recorder,
+    ) -> bool {
+        rec.trace(|| TraceEvent::Visit { node: self.id });
+        let idx = self.find_key_index(k);
+        let found = idx < self.keys.len() && self.keys[idx] == k;
+        if self.leaf {
+            if found {
+                self.keys.remove(idx);
+                rec.trace(|| TraceEvent::MoveKey {
+                    key: k,
+                    from: Some(self.id),
+                    to: None,
+                });
             }
+            return found;
         }
+
+        let deleted = if found {
+            let predecessor = self.get_predecessor(idx);
+            rec.event(BTreeEvent::ReplaceWithPredecessor {
+                key: k,
+                predecessor,
+            });
+            self.replace_key(idx, predecessor, rec);
+            self.children[idx].delete_bottom_up(predecessor, rec)
+        } else {
+            self.children[idx].delete_bottom_up(k, rec)
+        };
+        if self.children[idx].keys.len() + 1 < self.t {
+            self.fill_child(idx, rec);
+        }
+        deleted
     }
 
     /// キーのインデックスを見つける
@@ -211,26 +368,57 @@ impl BTreeNode {
     }
 
     /// 内部ノードからキーを削除
-    fn delete_from_internal_node(&mut self, idx: usize) -> bool {
+    fn delete_from_internal_node(
+        &mut self,
+        idx: usize,
+        rec: &mut Recorder,
+    ) -> bool {
         // 左の子が十分なキーを持っている場合、前駆で置き換え
         if self.children[idx].keys.len() >= self.t {
             let predecessor = self.get_predecessor(idx);
-            self.keys[idx] = predecessor;
-            self.children[idx].delete(predecessor)
+            rec.event(BTreeEvent::ReplaceWithPredecessor {
+                key: self.keys[idx],
+                predecessor,
+            });
+            self.replace_key(idx, predecessor, rec);
+            self.children[idx].delete(predecessor, rec)
         }
         // 右の子が十分なキーを持っている場合、後継で置き換え
         else if self.children[idx + 1].keys.len() >= self.t {
             let successor = self.get_successor(idx);
-            self.keys[idx] = successor;
-            self.children[idx + 1].delete(successor)
+            rec.event(BTreeEvent::ReplaceWithSuccessor {
+                key: self.keys[idx],
+                successor,
+            });
+            self.replace_key(idx, successor, rec);
+            self.children[idx + 1].delete(successor, rec)
         }
         // どちらも十分でない場合、マージしてから削除
         else {
-            self.merge_children(idx);
-            self.children[idx].delete(self.keys[idx])
+            // マージで親のキーは子に移るので、先に削除対象を覚えておく
+            let k = self.keys[idx];
+            self.merge_children(idx, rec);
+            self.children[idx].delete(k, rec)
         }
     }
 
+    /// 内部ノードのキーを前駆・後継で置き換える
+    ///
+    /// 前駆・後継はここに複製され、この後で子孫の葉から取り除かれる
+    fn replace_key(&mut self, idx: usize, key: i32, rec: &mut Recorder) {
+        let old = std::mem::replace(&mut self.keys[idx], key);
+        rec.trace(|| TraceEvent::MoveKey {
+            key: old,
+            from: Some(self.id),
+            to: None,
+        });
+        rec.trace(|| TraceEvent::MoveKey {
+            key,
+            from: None,
+            to: Some(self.id),
+        });
+    }
+
     /// 前駆（predecessor）を取得
     fn get_predecessor(&self, idx: usize) -> i32 {
         let mut node = &self.children[idx];
@@ -250,110 +438,157 @@ impl BTreeNode {
     }
 
     /// サブツリーからキーを削除
-    fn delete_from_subtree(&mut self, idx: usize, k: i32) -> bool {
+    fn delete_from_subtree(
+        &mut self,
+        idx: usize,
+        k: i32,
+        rec: &mut Recorder,
+    ) -> bool {
+        // 最後の子の場合、補強で左の兄弟とマージされることがある
+        let is_last = idx == self.keys.len();
+
         // 子ノードが最小キー数未満の場合、補強する
-        let children_len_before = self.children.len();
         if self.children[idx].keys.len() < self.t {
-            self.fill_child(idx);
+            self.fill_child(idx, rec);
         }
 
-        // マージが発生した場合、インデックスを調整
-        // fill_childがマージを実行した場合、children配列のサイズが変わる可能性がある
-        let actual_idx = if self.children.len() < children_len_before {
-            // マージによりchildren配列のサイズが減った場合
-            // idxが範囲外になっている可能性があるので、適切なインデックスを計算
-            if idx >= self.children.len() {
-                // idxが範囲外の場合、最後の子を使用
-                self.children.len() - 1
-            } else if idx > 0 && idx < self.keys.len() {
-                // マージがidx-1で発生した可能性があるので、idx-1を使用
-                idx - 1
-            } else {
-                idx
-            }
-        } else if idx > 0
-            && idx < self.keys.len()
-            && idx < self.children.len()
-            && self.children[idx].keys.len() < self.t
-        {
+        // 最後の子が左の兄弟にマージされた場合、キーは1つ左の子にある
+        let actual_idx = if is_last && idx > self.keys.len() {
             idx - 1
         } else {
-            // idxが範囲外の場合は最後の子を使用
-            if idx >= self.children.len() {
-                self.children.len() - 1
-            } else {
-                idx
-            }
+            idx
         };
 
-        self.children[actual_idx].delete(k)
+        self.children[actual_idx].delete(k, rec)
     }
 
     /// 子ノードを補強する（兄弟から借りるかマージする）
-    fn fill_child(&mut self, idx: usize) {
+    fn fill_child(&mut self, idx: usize, rec: &mut Recorder) {
         // 前の兄弟から借りる
         if idx != 0 && self.children[idx - 1].keys.len() >= self.t {
-            self.borrow_from_prev(idx);
+            self.borrow_from_prev(idx, rec);
         }
         // 次の兄弟から借りる
         else if idx < self.children.len() - 1
             && self.children[idx + 1].keys.len() >= self.t
         {
-            self.borrow_from_next(idx);
+            self.borrow_from_next(idx, rec);
         }
         // どちらも借りられない場合、マージ
-        else {
-            if idx != self.children.len() - 1 {
-                self.merge_children(idx);
-            } else {
-                self.merge_children(idx - 1);
-            }
+        else if idx != self.children.len() - 1 {
+            self.merge_children(idx, rec);
+        } else {
+            self.merge_children(idx - 1, rec);
         }
     }
 
     /// 前の兄弟からキーを借りる
-    fn borrow_from_prev(&mut self, idx: usize) {
+    fn borrow_from_prev(&mut self, idx: usize, rec: &mut Recorder) {
         let (left, right) = self.children.split_at_mut(idx);
         let sibling = &mut left[idx - 1];
         let child = &mut right[0];
 
         // 親のキーを子に移動
         child.keys.insert(0, self.keys[idx - 1]);
+        rec.trace(|| TraceEvent::MoveKey {
+            key: self.keys[idx - 1],
+            from: Some(self.id),
+            to: Some(child.id),
+        });
 
         // 兄弟の最後の子を子の最初に移動
         if !child.leaf {
             let last_child = sibling.children.pop().unwrap();
+            rec.relink(sibling.id, child.id, last_child.id, 0);
             child.children.insert(0, last_child);
         }
 
         // 兄弟の最後のキーを親に移動
-        self.keys[idx - 1] = sibling.keys.pop().unwrap();
+        let sibling_key = sibling.keys.pop().unwrap();
+        rec.event(BTreeEvent::BorrowFromPrev {
+            parent_key: self.keys[idx - 1],
+            sibling_key,
+        });
+        rec.trace(|| TraceEvent::MoveKey {
+            key: sibling_key,
+            from: Some(sibling.id),
+            to: Some(self.id),
+        });
+        self.keys[idx - 1] = sibling_key;
     }
 
     /// 次の兄弟からキーを借りる
-    fn borrow_from_next(&mut self, idx: usize) {
+    fn borrow_from_next(&mut self, idx: usize, rec: &mut Recorder) {
         let (left, right) = self.children.split_at_mut(idx + 1);
         let child = &mut left[idx];
         let sibling = &mut right[0];
 
         // 親のキーを子に移動
         child.keys.push(self.keys[idx]);
+        rec.trace(|| TraceEvent::MoveKey {
+            key: self.keys[idx],
+            from: Some(self.id),
+            to: Some(child.id),
+        });
 
         // 兄弟の最初の子を子の最後に移動
         if !child.leaf {
             let first_child = sibling.children.remove(0);
+            let index = child.children.len();
+            rec.relink(sibling.id, child.id, first_child.id, index);
             child.children.push(first_child);
         }
 
         // 兄弟の最初のキーを親に移動
-        self.keys[idx] = sibling.keys.remove(0);
+        let sibling_key = sibling.keys.remove(0);
+        rec.event(BTreeEvent::BorrowFromNext {
+            parent_key: self.keys[idx],
+            sibling_key,
+        });
+        rec.trace(|| TraceEvent::MoveKey {
+            key: sibling_key,
+            from: Some(sibling.id),
+            to: Some(self.id),
+        });
+        self.keys[idx] = sibling_key;
     }
 
     /// 2つの子ノードをマージ
-    fn merge_children(&mut self, idx: usize) {
+    fn merge_children(&mut self, idx: usize, rec: &mut Recorder) {
         let mut child = self.children.remove(idx);
         let sibling = self.children.remove(idx);
         let key = self.keys.remove(idx);
+        rec.event(BTreeEvent::Merge {
+            left: child.keys.clone(),
+            separator: key,
+            right: sibling.keys.clone(),
+        });
+
+        rec.trace(|| TraceEvent::MoveKey {
+            key,
+            from: Some(self.id),
+            to: Some(child.id),
+        });
+        for &k in &sibling.keys {
+            rec.trace(|| TraceEvent::MoveKey {
+                key: k,
+                from: Some(sibling.id),
+                to: Some(child.id),
+            });
+        }
+        for (j, c) in sibling.children.iter().enumerate() {
+            rec.relink(
+                sibling.id,
+                child.id,
+                c.id,
+                child.children.len() + j,
+            );
+        }
+        rec.trace(|| TraceEvent::Unlink {
+            parent: self.id,
+            child: sibling.id,
+        });
+        rec.trace(|| TraceEvent::DeleteNode { node: sibling.id });
 
         // 親のキーを子に移動
         child.keys.push(key);
@@ -1,9 +1,21 @@
 use std::fmt;
 
+use crate::btree::event::{BTreeEvent, Recorder};
+use crate::btree::fill::FillStats;
 use crate::btree::node::BTreeNode;
+use crate::btree::stats::TreeStats;
+use crate::btree::strategy::{OperationStats, SplitStrategy};
+use crate::locale::{Locale, Message};
+use crate::trace::{NodeIds, TraceEvent, Tracer, trace_to_json};
 use js_sys::Array;
 use wasm_bindgen::prelude::*;
 
+/// 外から受け取る最小次数の上限
+///
+/// ノードは2t-1個分のキーの領域を先に確保するので、大きすぎるtは
+/// 最初の挿入でメモリの確保に失敗する
+pub const MAX_MIN_DEGREE: usize = 1 << 10;
+
 // B-Tree
 #[wasm_bindgen]
 pub struct BTree {
@@ -12,13 +24,39 @@ pub struct BTree {
 
     // 次数
     t: usize,
+
+    // 分割とマージのタイミング
+    strategy: SplitStrategy,
+
+    // 直前の操作で発生した構造変更
+    events: Vec<BTreeEvent>,
+
+    // 直前の操作のトレース
+    trace: Vec<TraceEvent>,
+
+    // 作成してからの分割・マージ・借用の回数
+    totals: OperationStats,
+
+    // ノードの識別子の割り当て
+    ids: NodeIds,
 }
 
 #[wasm_bindgen]
 impl BTree {
     #[wasm_bindgen(constructor)]
     pub fn new(t: usize) -> Self {
-        BTree { root: None, t }
+        BTree::with_split_strategy(t, SplitStrategy::TopDown)
+    }
+
+    /// 分割とマージのタイミング ("topdown" / "bottomup") を指定して木を作成
+    pub fn with_strategy(
+        t: usize,
+        strategy: &str,
+    ) -> Result<BTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(BTree::with_split_strategy(t, strategy.parse()?))
     }
 
     // ツリー全体を走査
@@ -36,48 +74,34 @@ impl BTree {
         }
     }
 
-    pub fn insert(&mut self, k: i32) {
-        match self.root.take() {
-            None => {
-                // ツリーが空の場合
-                let mut new_root = Box::new(BTreeNode::new(self.t, true));
-                new_root.insert_not_full(k);
-                self.root = Some(new_root)
-            }
-            Some(mut root) => {
-                // ルートが満杯の場合、ツリーの高さが増える
-                if root.is_full() {
-                    // 新しいルートを作成
-                    let mut s = Box::new(BTreeNode::new(self.t, false));
-
-                    // 古いルートを新しいルートの子にする
-                    s.add_child(root);
-                    // 古いルートを分割して、1つのキーを新しいルートに移動
-                    s.split_child(0);
+    /// キーkを探索し、たどった節点と比較をトレースに記録
+    pub fn search_traced(&mut self, k: i32) -> bool {
+        // 探索は構造を変えないので、直前の操作のイベントを残さない
+        self.events.clear();
+        let mut trace = std::mem::take(&mut self.trace);
+        trace.clear();
+        let found = self.search_with(k, &mut trace);
+        self.trace = trace;
+        found
+    }
 
-                    // 新しいルートには2つの子がある
-                    // どちらの子が新しいキーを持つか判断
-                    let i = if let Some(first_key) = s.get_key(0) {
-                        if first_key < k { 1 } else { 0 }
-                    } else {
-                        0
-                    };
-                    if let Some(child) = s.get_child_mut(i) {
-                        child.insert_not_full(k);
-                    }
+    pub fn insert(&mut self, k: i32) {
+        let mut trace = std::mem::take(&mut self.trace);
+        trace.clear();
+        self.insert_with(k, &mut trace);
+        self.trace = trace;
+    }
 
-                    // ルートを変更
-                    self.root = Some(s);
-                } else {
-                    // ルートが満杯でない場合
-                    root.insert_not_full(k);
-                    self.root = Some(root)
-                }
-            }
-        }
+    /// 直前の操作のトレース ({version, steps})
+    ///
+    /// 各ステップの説明文はlocale ("ja" / "en") の言語で付く
+    #[wasm_bindgen]
+    pub fn get_trace(&self, locale: &str) -> Result<JsValue, String> {
+        let locale: Locale = locale.parse()?;
+        Ok(trace_to_json(&self.trace, locale).to_js_value())
     }
 
-    fn node_to_js_value(&self, node: &BTreeNode) -> JsValue {
+    fn node_to_js_value(node: &BTreeNode) -> JsValue {
         let obj = js_sys::Object::new();
 
         // keys配列を作成
@@ -91,7 +115,7 @@ impl BTree {
             Array::from_iter(
                 node.children()
                     .iter()
-                    .map(|child| self.node_to_js_value(&child)),
+                    .map(|child| Self::node_to_js_value(child)),
             )
         } else {
             Array::new()
@@ -107,6 +131,11 @@ impl BTree {
             &"isLeaf".into(),
             &JsValue::from(node.leaf()),
         );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"id".into(),
+            &JsValue::from(node.id().0),
+        );
 
         obj.into()
     }
@@ -114,7 +143,7 @@ impl BTree {
     #[wasm_bindgen]
     pub fn get_structure(&self) -> JsValue {
         match &self.root {
-            Some(root) => self.node_to_js_value(&root),
+            Some(root) => Self::node_to_js_value(root),
             None => JsValue::NULL,
         }
     }
@@ -122,17 +151,19 @@ impl BTree {
     /// キーの総数を取得
     #[wasm_bindgen]
     pub fn get_total_keys(&self) -> usize {
-        self.count_keys(&self.root)
+        Self::count_keys(&self.root)
     }
 
-    fn count_keys(&self, node: &Option<Box<BTreeNode>>) -> usize {
+    fn count_keys(node: &Option<Box<BTreeNode>>) -> usize {
         match node {
             Some(n) => {
                 let keys_count = n.keys_len();
                 let children_keys_count = if !n.leaf() {
                     n.children()
                         .iter()
-                        .map(|child| self.count_keys(&Some(child.clone())))
+                        .map(|child| {
+                            Self::count_keys(&Some(child.clone()))
+                        })
                         .sum()
                 } else {
                     0
@@ -144,16 +175,15 @@ impl BTree {
         }
     }
 
-    fn get_node_height(&self, node: &Option<Box<BTreeNode>>) -> usize {
+    fn get_node_height(node: &Option<Box<BTreeNode>>) -> usize {
         match node {
             Some(n) => {
-                if n.leaf() {
-                    1
-                } else if n.children().is_empty() {
+                if n.leaf() || n.children().is_empty() {
                     1
                 } else {
-                    1 + self
-                        .get_node_height(&Some(n.children()[0].clone()))
+                    1 + Self::get_node_height(&Some(
+                        n.children()[0].clone(),
+                    ))
                 }
             }
             None => 0,
@@ -162,28 +192,379 @@ impl BTree {
 
     #[wasm_bindgen]
     pub fn get_height(&self) -> usize {
-        self.get_node_height(&self.root)
+        Self::get_node_height(&self.root)
+    }
+
+    /// 最小次数tを取得
+    #[wasm_bindgen]
+    pub fn get_min_degree(&self) -> usize {
+        self.t
+    }
+
+    /// 分割とマージのタイミング ("topdown" / "bottomup")
+    #[wasm_bindgen]
+    pub fn get_strategy(&self) -> String {
+        self.strategy.name().to_string()
+    }
+
+    /// 直前の操作で訪問・変更したノードの数と構造変更の数
+    #[wasm_bindgen]
+    pub fn get_last_stats(&self) -> OperationStats {
+        OperationStats::collect(&self.trace, &self.events)
+    }
+
+    /// 直前の操作で発生した分割・マージなどのイベントを取得
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(BTreeEvent::to_js_value))
+    }
+
+    /// ノードの充填率の統計を取得
+    #[wasm_bindgen]
+    pub fn fill_stats(&self) -> FillStats {
+        let mut nodes = Vec::new();
+        if let Some(ref root) = self.root {
+            let mut stack = vec![(root.as_ref(), true)];
+            while let Some((node, is_root)) = stack.pop() {
+                nodes.push((node.keys_len(), node.max_keys(), is_root));
+                stack.extend(
+                    node.child_nodes().iter().map(|c| (c.as_ref(), false)),
+                );
+            }
+        }
+        FillStats::collect(nodes)
+    }
+
+    /// 木全体の統計 ({nodesPerLevel, keysPerLevel, internalNodes,
+    /// leafNodes, nodes, keys, averageFill, minFill, splits, merges,
+    /// borrows, memoryBytes})
+    #[wasm_bindgen]
+    pub fn get_stats(&self) -> JsValue {
+        self.stats().to_json().to_js_value()
     }
 
     /// キーkを削除
     #[wasm_bindgen]
     pub fn delete(&mut self, k: i32) -> bool {
+        let mut trace = std::mem::take(&mut self.trace);
+        trace.clear();
+        let deleted = self.delete_with(k, &mut trace);
+        self.trace = trace;
+        deleted
+    }
+}
+
+impl BTree {
+    /// 根を指定して木を作成
+    ///
+    /// 新しいノードにはrootの中の識別子より後の識別子を割り当てる
+    pub fn from_root(t: usize, root: Option<Box<BTreeNode>>) -> Self {
+        let mut ids = Vec::new();
+        let mut stack = Vec::from_iter(root.as_deref());
+        while let Some(node) = stack.pop() {
+            ids.push(node.id());
+            stack.extend(node.child_nodes().iter().map(|c| c.as_ref()));
+        }
+        BTree {
+            ids: NodeIds::after(ids),
+            root,
+            t,
+            strategy: SplitStrategy::TopDown,
+            events: Vec::new(),
+            trace: Vec::new(),
+            totals: OperationStats::default(),
+        }
+    }
+
+    /// 分割とマージのタイミングを指定して空の木を作成
+    pub fn with_split_strategy(t: usize, strategy: SplitStrategy) -> Self {
+        BTree {
+            strategy,
+            ..BTree::from_root(t, None)
+        }
+    }
+
+    pub fn strategy(&self) -> SplitStrategy {
+        self.strategy
+    }
+
+    /// 木全体の統計
+    pub fn stats(&self) -> TreeStats {
+        TreeStats::collect(self.root(), self.fill_stats(), self.totals)
+    }
+
+    /// 直前の操作の構造変更を累計に足す
+    fn count_events(&mut self) {
+        self.totals += OperationStats::collect(&[], &self.events);
+    }
+
+    /// キーkを探索し、各ステップをtracerに報告
+    pub fn search_with(&self, k: i32, tracer: &mut dyn Tracer) -> bool {
+        tracer.record(TraceEvent::Annotate {
+            message: Message::Search { key: k },
+        });
+        let found = self.search_steps(k, tracer);
+        let message = if found {
+            Message::Found { key: k }
+        } else {
+            Message::NotFound { key: k }
+        };
+        tracer.record(TraceEvent::Annotate { message });
+        found
+    }
+
+    fn search_steps(&self, k: i32, tracer: &mut dyn Tracer) -> bool {
+        let mut node = match &self.root {
+            Some(root) => root.as_ref(),
+            None => return false,
+        };
+        loop {
+            let id = node.id();
+            tracer.record(TraceEvent::Visit { node: id });
+
+            // k以上の最初のキーまで比較しながら進む
+            let keys = node.keys();
+            let mut i = 0;
+            while i < keys.len() {
+                tracer.record(TraceEvent::Compare {
+                    node: id,
+                    key: k,
+                    other: keys[i],
+                });
+                if k <= keys[i] {
+                    break;
+                }
+                i += 1;
+            }
+
+            if i < keys.len() && keys[i] == k {
+                tracer.record(TraceEvent::Highlight {
+                    node: id,
+                    key: Some(k),
+                });
+                return true;
+            }
+            if node.leaf() {
+                return false;
+            }
+            node = &node.child_nodes()[i];
+        }
+    }
+
+    /// キーkを挿入し、各ステップをtracerに報告
+    pub fn insert_with(&mut self, k: i32, tracer: &mut dyn Tracer) {
+        self.events.clear();
+        let mut rec =
+            Recorder::new(&mut self.events, &mut self.ids, tracer);
+        rec.annotate(|| Message::Insert { key: k });
         match self.root.take() {
-            None => false,
+            None => {
+                // ツリーが空の場合
+                let mut new_root =
+                    Box::new(BTreeNode::new(self.t, true, rec.fresh_id()));
+                rec.trace(|| TraceEvent::CreateNode {
+                    node: new_root.id(),
+                    keys: Vec::new(),
+                });
+                new_root.insert_not_full(k, &mut rec);
+                self.root = Some(new_root)
+            }
+            Some(mut root) if self.strategy == SplitStrategy::BottomUp => {
+                // 葉に入れてから、あふれたノードを親で分割する
+                self.root = Some(if root.insert_bottom_up(k, &mut rec) {
+                    Self::grow(self.t, root, &mut rec)
+                } else {
+                    root
+                });
+            }
+            Some(mut root) => {
+                // ルートが満杯の場合、ツリーの高さが増える
+                if root.is_full() {
+                    let mut s = Self::grow(self.t, root, &mut rec);
+
+                    // 新しいルートには2つの子がある
+                    // どちらの子が新しいキーを持つか判断
+                    let i = if let Some(first_key) = s.get_key(0) {
+                        if first_key < k { 1 } else { 0 }
+                    } else {
+                        0
+                    };
+                    if let Some(child) = s.get_child_mut(i) {
+                        child.insert_not_full(k, &mut rec);
+                    }
+
+                    // ルートを変更
+                    self.root = Some(s);
+                } else {
+                    // ルートが満杯でない場合
+                    root.insert_not_full(k, &mut rec);
+                    self.root = Some(root)
+                }
+            }
+        }
+        self.count_events();
+    }
+
+    /// 新しいルートの下で古いルートを分割する
+    fn grow(
+        t: usize,
+        root: Box<BTreeNode>,
+        rec: &mut Recorder,
+    ) -> Box<BTreeNode> {
+        rec.annotate(|| Message::GrowRoot);
+
+        // 新しいルートを作成
+        let mut s = Box::new(BTreeNode::new(t, false, rec.fresh_id()));
+        rec.trace(|| TraceEvent::CreateNode {
+            node: s.id(),
+            keys: Vec::new(),
+        });
+
+        // 古いルートを新しいルートの子にする
+        rec.trace(|| TraceEvent::Link {
+            parent: s.id(),
+            child: root.id(),
+            index: 0,
+        });
+        s.add_child(root);
+        // 古いルートを分割して、1つのキーを新しいルートに移動
+        s.split_child(0, rec);
+        s
+    }
+
+    /// キーkを削除し、各ステップをtracerに報告
+    pub fn delete_with(
+        &mut self,
+        k: i32,
+        tracer: &mut dyn Tracer,
+    ) -> bool {
+        self.events.clear();
+        let mut rec =
+            Recorder::new(&mut self.events, &mut self.ids, tracer);
+        rec.annotate(|| Message::Delete { key: k });
+        let deleted = match self.root.take() {
+            None => {
+                rec.annotate(|| Message::NotFound { key: k });
+                false
+            }
             Some(mut root) => {
-                let result = root.delete(k);
+                let result = match self.strategy {
+                    SplitStrategy::TopDown => root.delete(k, &mut rec),
+                    SplitStrategy::BottomUp => {
+                        root.delete_bottom_up(k, &mut rec)
+                    }
+                };
+                if !result {
+                    rec.annotate(|| Message::NotFound { key: k });
+                }
 
                 // ルートが空になった場合、最初の子を新しいルートにする
                 if root.keys().is_empty() && !root.leaf() {
-                    self.root = root.children().pop();
+                    let new_root = root.children().pop();
+                    if let Some(child) = &new_root {
+                        rec.annotate(|| Message::ShrinkRoot);
+                        rec.trace(|| TraceEvent::Unlink {
+                            parent: root.id(),
+                            child: child.id(),
+                        });
+                        rec.trace(|| TraceEvent::DeleteNode {
+                            node: root.id(),
+                        });
+                    }
+                    self.root = new_root;
                 } else {
                     self.root = Some(root);
                 }
 
                 result
             }
+        };
+        self.count_events();
+        deleted
+    }
+
+    /// 直前の操作のトレース
+    pub fn trace(&self) -> &[TraceEvent] {
+        &self.trace
+    }
+
+    /// 根への参照
+    pub fn root(&self) -> Option<&BTreeNode> {
+        self.root.as_deref()
+    }
+
+    /// 直前の操作で発生したイベント
+    pub fn events(&self) -> &[BTreeEvent] {
+        &self.events
+    }
+
+    /// 深さごとの各ノードのキー
+    pub fn levels(&self) -> Vec<Vec<Vec<i32>>> {
+        let mut levels = Vec::new();
+        let mut nodes: Vec<&BTreeNode> = self.root().into_iter().collect();
+        while !nodes.is_empty() {
+            levels.push(nodes.iter().map(|n| n.keys()).collect());
+            nodes = nodes
+                .iter()
+                .flat_map(|n| n.child_nodes().iter().map(|c| c.as_ref()))
+                .collect();
+        }
+        levels
+    }
+
+    /// B-Treeの性質を検査し、キーを昇順に返す
+    ///
+    /// - 各ノードのキー数は2t-1以下、根以外はt-1以上
+    /// - 内部ノードの子の数はキー数+1
+    /// - 全ての葉が同じ深さ
+    /// - 中間順でキーが昇順
+    pub fn check_invariants(&self) -> Result<Vec<i32>, String> {
+        let (mut leaves, mut keys) = (Vec::new(), Vec::new());
+        if let Some(root) = self.root() {
+            check_node(root, self.t, 0, true, &mut leaves, &mut keys)?;
+        }
+        if leaves.windows(2).any(|w| w[0] != w[1]) {
+            return Err("leaves at different depths".to_string());
         }
+        if keys.windows(2).any(|w| w[0] > w[1]) {
+            return Err(format!("keys out of order: {keys:?}"));
+        }
+        Ok(keys)
+    }
+}
+
+fn check_node(
+    node: &BTreeNode,
+    t: usize,
+    depth: usize,
+    is_root: bool,
+    leaves: &mut Vec<usize>,
+    keys: &mut Vec<i32>,
+) -> Result<(), String> {
+    let n = node.keys_len();
+    if n >= 2 * t {
+        return Err(format!("node {:?} overflows", node.keys()));
+    }
+    if !is_root && n + 1 < t {
+        return Err(format!("node {:?} underflows", node.keys()));
     }
+    if node.leaf() {
+        leaves.push(depth);
+        keys.extend(node.keys());
+        return Ok(());
+    }
+    if node.child_nodes().len() != n + 1 {
+        return Err(format!(
+            "node {:?} has {} children",
+            node.keys(),
+            node.child_nodes().len()
+        ));
+    }
+    for (i, child) in node.child_nodes().iter().enumerate() {
+        check_node(child, t, depth + 1, false, leaves, keys)?;
+        keys.extend(node.get_key(i));
+    }
+    Ok(())
 }
 
 impl fmt::Display for BTree {
@@ -199,6 +580,9 @@ impl fmt::Display for BTree {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::SplitMix64;
+    use crate::trace::{NodeId, NoopTracer};
+    use std::collections::HashMap;
 
     #[test]
     fn test_btree_insertion_and_search() {
@@ -344,6 +728,74 @@ mod tests {
         assert!(!t.search(5), "5 should not be present");
     }
 
+    #[test]
+    fn test_btree_delete_internal_key_after_merge() {
+        // 根 [2, 4] の下に [1] [3] [5, 6]。2の両側の子はどちらも
+        // 最小なのでマージしてから消す。マージ後の親の2番目のキー (4)
+        // ではなく、覚えておいた2を子から消さなければならない
+        let mut t = BTree::new(2);
+        for k in 1..=6 {
+            t.insert(k);
+        }
+        assert!(t.delete(2));
+        assert!(!t.search(2));
+        assert!([1, 3, 4, 5, 6].iter().all(|&k| t.search(k)));
+    }
+
+    #[test]
+    fn test_btree_levels_and_invariants() {
+        let mut tree = BTree::new(2);
+        for k in 1..=6 {
+            tree.insert(k);
+        }
+        assert_eq!(
+            tree.levels(),
+            vec![vec![vec![2, 4]], vec![vec![1], vec![3], vec![5, 6]]]
+        );
+        assert_eq!(tree.check_invariants(), Ok((1..=6).collect()));
+
+        // 葉の深さが揃っていない木は検出する
+        let leaf = |k| {
+            Box::new(BTreeNode::with_keys(2, vec![k], vec![], NodeId(0)))
+        };
+        let inner = BTreeNode::with_keys(
+            2,
+            vec![2],
+            vec![leaf(1), leaf(3)],
+            NodeId(0),
+        );
+        let root = BTreeNode::with_keys(
+            2,
+            vec![4],
+            vec![Box::new(inner), leaf(5)],
+            NodeId(0),
+        );
+        let broken = BTree::from_root(2, Some(Box::new(root)));
+        assert!(broken.check_invariants().is_err());
+    }
+
+    #[test]
+    fn test_btree_delete_each_key_from_sequential_trees() {
+        // 補強でマージされた後に下りる子を取り違えないか、全ての位置で確かめる
+        for t in 2..=3 {
+            for n in 1..=20 {
+                for k in 1..=n {
+                    let mut tree = BTree::new(t);
+                    for i in 1..=n {
+                        tree.insert(i);
+                    }
+                    assert!(tree.delete(k), "t={t} n={n} k={k}");
+                    assert!(!tree.search(k));
+                    assert!(
+                        (1..=n)
+                            .filter(|&i| i != k)
+                            .all(|i| tree.search(i))
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_btree_delete_with_merge() {
         let mut t = BTree::new(3);
@@ -419,8 +871,8 @@ mod tests {
 
         // 順序よく削除
         for i in 1..=10 {
-            assert!(t.delete(i), "Key {} should be deleted", i);
-            assert!(!t.search(i), "Key {} should not be present", i);
+            assert!(t.delete(i), "Key {i} should be deleted");
+            assert!(!t.search(i), "Key {i} should not be present");
         }
 
         assert_eq!(
@@ -431,7 +883,7 @@ mod tests {
 
         // 残りのキーが存在することを確認
         for i in 11..=20 {
-            assert!(t.search(i), "Key {} should still be present", i);
+            assert!(t.search(i), "Key {i} should still be present");
         }
     }
 
@@ -446,10 +898,285 @@ mod tests {
 
         // 逆順で削除
         for i in (1..=10).rev() {
-            assert!(t.delete(i), "Key {} should be deleted", i);
-            assert!(!t.search(i), "Key {} should not be present", i);
+            assert!(t.delete(i), "Key {i} should be deleted");
+            assert!(!t.search(i), "Key {i} should not be present");
         }
 
         assert_eq!(t.get_total_keys(), 0, "Should have 0 keys remaining");
     }
+
+    #[test]
+    fn test_btree_delete_every_third_key_t2() {
+        let mut t = BTree::new(2);
+
+        for i in 1..=40 {
+            t.insert(i);
+        }
+
+        // 内部ノードのキーの削除や、最後の子のマージが起こる
+        for i in (1..=40).step_by(3) {
+            assert!(t.delete(i), "Key {i} should be deleted");
+            assert!(!t.search(i), "Key {i} should not be present");
+        }
+        for i in (1..=40).filter(|i| i % 3 != 1) {
+            assert!(t.search(i), "Key {i} should still be present");
+        }
+        assert_eq!(t.get_total_keys(), 26);
+    }
+
+    #[test]
+    fn test_btree_events_record_splits_and_merges() {
+        let mut t = BTree::new(2);
+
+        for i in 1..=3 {
+            t.insert(i);
+        }
+        assert!(t.events().is_empty());
+
+        // 満杯の根 [1|2|3] を分割してから挿入する
+        t.insert(4);
+        assert_eq!(
+            t.events(),
+            &[BTreeEvent::Split {
+                keys: vec![1, 2, 3],
+                median: 2,
+            }]
+        );
+
+        // 両方の子が最小キー数なのでマージが起こる
+        t.delete(4);
+        t.delete(2);
+        assert!(
+            t.events()
+                .iter()
+                .any(|e| matches!(e, BTreeEvent::Merge { .. }))
+        );
+    }
+
+    /// 節点ごとのキー (昇順、重複あり) と子の並び
+    #[derive(Debug, Default, PartialEq)]
+    struct Model {
+        keys: HashMap<NodeId, Vec<i32>>,
+        children: HashMap<NodeId, Vec<NodeId>>,
+    }
+
+    impl Model {
+        fn of(tree: &BTree) -> Self {
+            let mut model = Model::default();
+            let mut stack = Vec::from_iter(tree.root());
+            while let Some(node) = stack.pop() {
+                model.keys.insert(node.id(), node.keys());
+                let children = node.child_nodes();
+                model.children.insert(
+                    node.id(),
+                    children.iter().map(|c| c.id()).collect(),
+                );
+                stack.extend(children.iter().map(|c| c.as_ref()));
+            }
+            model
+        }
+
+        /// トレースのとおりに変更を加える
+        fn apply(&mut self, event: &TraceEvent) {
+            match *event {
+                TraceEvent::CreateNode { node, ref keys } => {
+                    self.keys.insert(node, keys.clone());
+                    self.children.insert(node, Vec::new());
+                }
+                TraceEvent::DeleteNode { node } => {
+                    assert!(self.keys.remove(&node).unwrap().is_empty());
+                    assert!(
+                        self.children.remove(&node).unwrap().is_empty()
+                    );
+                }
+                TraceEvent::MoveKey { key, from, to } => {
+                    if let Some(from) = from {
+                        let keys = self.keys.get_mut(&from).unwrap();
+                        let pos = keys.binary_search(&key).unwrap();
+                        keys.remove(pos);
+                    }
+                    if let Some(to) = to {
+                        let keys = self.keys.get_mut(&to).unwrap();
+                        let pos = keys.partition_point(|&k| k < key);
+                        keys.insert(pos, key);
+                    }
+                }
+                TraceEvent::Link {
+                    parent,
+                    child,
+                    index,
+                } => {
+                    self.children
+                        .get_mut(&parent)
+                        .unwrap()
+                        .insert(index, child);
+                }
+                TraceEvent::Unlink { parent, child } => {
+                    let children = self.children.get_mut(&parent).unwrap();
+                    let pos = children.iter().position(|&c| c == child);
+                    children.remove(pos.unwrap());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_btree_trace_replays_every_operation() {
+        let mut rng = SplitMix64::new(1);
+        let strategies = [SplitStrategy::TopDown, SplitStrategy::BottomUp];
+        for (t, strategy) in
+            (2..=4).flat_map(|t| strategies.map(|s| (t, s)))
+        {
+            let mut tree = BTree::with_split_strategy(t, strategy);
+            for _ in 0..400 {
+                let key = (rng.next_u64() % 60) as i32;
+                let mut model = Model::of(&tree);
+                if rng.chance(0.4) {
+                    tree.delete(key);
+                } else {
+                    tree.insert(key);
+                }
+                for event in tree.trace() {
+                    model.apply(event);
+                }
+
+                // 根から辿れない節点はトレースで消えているはず
+                assert_eq!(
+                    model,
+                    Model::of(&tree),
+                    "t={t} {strategy:?} key={key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_btree_ids_depend_only_on_operations() {
+        let run = || {
+            let mut t = BTree::new(2);
+            for k in 1..=6 {
+                t.insert(k);
+            }
+            t.delete(3);
+            t.search_traced(5);
+            t.trace().to_vec()
+        };
+        let first = run();
+        // 間に別の木を作っても識別子は変わらない
+        BTree::new(2).insert(1);
+        assert_eq!(run(), first);
+
+        // 最初の葉が1、根が2、分割でできた右の葉が3
+        let mut t = BTree::new(2);
+        for k in 1..=4 {
+            t.insert(k);
+        }
+        assert_eq!(t.root().unwrap().id(), NodeId(2));
+        assert_eq!(t.root().unwrap().child_nodes()[1].id(), NodeId(3));
+    }
+
+    #[test]
+    fn test_btree_trace_split_and_search() {
+        let mut t = BTree::new(2);
+        for i in 1..=3 {
+            t.insert(i);
+        }
+        let old_root = t.root().unwrap().id();
+
+        t.insert(4);
+        let root = t.root().unwrap();
+        let right = root.child_nodes()[1].id();
+        let (notes, steps): (Vec<_>, Vec<_>) = t
+            .trace()
+            .iter()
+            .cloned()
+            .partition(|e| matches!(e, TraceEvent::Annotate { .. }));
+        assert_eq!(
+            notes,
+            [
+                Message::Insert { key: 4 },
+                Message::GrowRoot,
+                Message::Split {
+                    keys: vec![1, 2, 3],
+                    median: 2,
+                },
+            ]
+            .map(|message| TraceEvent::Annotate { message })
+        );
+        assert_eq!(
+            steps[..3],
+            [
+                TraceEvent::CreateNode {
+                    node: root.id(),
+                    keys: vec![],
+                },
+                TraceEvent::Link {
+                    parent: root.id(),
+                    child: old_root,
+                    index: 0,
+                },
+                TraceEvent::CreateNode {
+                    node: right,
+                    keys: vec![],
+                },
+            ]
+        );
+        assert_eq!(
+            steps.last(),
+            Some(&TraceEvent::MoveKey {
+                key: 4,
+                from: None,
+                to: Some(right),
+            })
+        );
+
+        // [2] -> [3|4] とたどり、4 との比較で見つかる
+        let root_id = root.id();
+        assert!(t.search_traced(4));
+        assert_eq!(
+            t.trace(),
+            &[
+                TraceEvent::Annotate {
+                    message: Message::Search { key: 4 },
+                },
+                TraceEvent::Visit { node: root_id },
+                TraceEvent::Compare {
+                    node: root_id,
+                    key: 4,
+                    other: 2,
+                },
+                TraceEvent::Visit { node: right },
+                TraceEvent::Compare {
+                    node: right,
+                    key: 4,
+                    other: 3,
+                },
+                TraceEvent::Compare {
+                    node: right,
+                    key: 4,
+                    other: 4,
+                },
+                TraceEvent::Highlight {
+                    node: right,
+                    key: Some(4),
+                },
+                TraceEvent::Annotate {
+                    message: Message::Found { key: 4 },
+                },
+            ]
+        );
+
+        // 存在しないキーの削除は見つからなかったと説明する
+        assert!(!t.delete(9));
+        assert_eq!(
+            t.trace().last().unwrap().describe(Locale::En),
+            "Key 9 was not found"
+        );
+
+        // 報告を捨てるトレーサでも木の操作は同じ
+        t.insert_with(5, &mut NoopTracer);
+        assert!(t.search(5));
+        assert!(!t.search_with(9, &mut NoopTracer));
+    }
 }
@@ -0,0 +1,160 @@
+use crate::btree::fill::FillStats;
+use crate::btree::node::BTreeNode;
+use crate::btree::strategy::OperationStats;
+use crate::json::JsonValue;
+use std::mem::size_of;
+
+/// 木全体の形と、作成してから起きた構造変更の統計
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeStats {
+    /// 深さごとのノード数 (根が0)
+    pub nodes_per_level: Vec<usize>,
+
+    /// 深さごとのキー数
+    pub keys_per_level: Vec<usize>,
+
+    pub internal_nodes: usize,
+    pub leaf_nodes: usize,
+    pub fill: FillStats,
+
+    /// 作成してからの分割・マージ・借用の回数
+    pub splits: usize,
+    pub merges: usize,
+    pub borrows: usize,
+
+    /// ノードとキー・子の配列が確保しているバイト数の見積もり
+    pub memory_bytes: usize,
+}
+
+impl TreeStats {
+    /// 根から幅優先でたどって数える
+    pub(crate) fn collect(
+        root: Option<&BTreeNode>,
+        fill: FillStats,
+        totals: OperationStats,
+    ) -> Self {
+        let mut stats = TreeStats {
+            fill,
+            splits: totals.splits,
+            merges: totals.merges,
+            borrows: totals.borrows,
+            ..TreeStats::default()
+        };
+        let mut level: Vec<&BTreeNode> = root.into_iter().collect();
+        while !level.is_empty() {
+            stats.nodes_per_level.push(level.len());
+            stats
+                .keys_per_level
+                .push(level.iter().map(|n| n.keys_len()).sum());
+            for node in &level {
+                if node.leaf() {
+                    stats.leaf_nodes += 1;
+                } else {
+                    stats.internal_nodes += 1;
+                }
+                stats.memory_bytes += node.allocated_bytes();
+            }
+            level = level
+                .iter()
+                .flat_map(|n| n.child_nodes().iter().map(|c| c.as_ref()))
+                .collect();
+        }
+        if root.is_some() {
+            // 木が根を指すBox
+            stats.memory_bytes += size_of::<Box<BTreeNode>>();
+        }
+        stats
+    }
+
+    /// 統計 ({nodesPerLevel, keysPerLevel, internalNodes, ...})
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("nodesPerLevel", self.nodes_per_level.clone().into()),
+            ("keysPerLevel", self.keys_per_level.clone().into()),
+            ("internalNodes", self.internal_nodes.into()),
+            ("leafNodes", self.leaf_nodes.into()),
+            ("nodes", self.fill.nodes.into()),
+            ("keys", self.fill.keys.into()),
+            ("averageFill", self.fill.average_fill.into()),
+            ("minFill", self.fill.min_fill.into()),
+            ("splits", self.splits.into()),
+            ("merges", self.merges.into()),
+            ("borrows", self.borrows.into()),
+            ("memoryBytes", self.memory_bytes.into()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::btree::operation::BTree;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_stats_of_small_tree() {
+        let mut tree = BTree::new(2);
+        assert_eq!(tree.stats().nodes_per_level, Vec::<usize>::new());
+        assert_eq!(tree.stats().memory_bytes, 0);
+
+        // 根 [2, 4] の下に [1] [3] [5, 6]
+        for k in 1..=6 {
+            tree.insert(k);
+        }
+        let stats = tree.stats();
+        assert_eq!(stats.nodes_per_level, vec![1, 3]);
+        assert_eq!(stats.keys_per_level, vec![2, 4]);
+        assert_eq!((stats.internal_nodes, stats.leaf_nodes), (1, 3));
+        assert_eq!((stats.splits, stats.merges, stats.borrows), (2, 0, 0));
+        assert!(stats.memory_bytes > 4 * size_of::<i32>() * 3);
+
+        // 3を消すと左の2つの葉がマージされる
+        assert!(tree.delete(3));
+        assert!(!tree.delete(3));
+        let stats = tree.stats();
+        assert_eq!(stats.nodes_per_level, vec![1, 2]);
+        assert_eq!((stats.splits, stats.merges), (2, 1));
+    }
+
+    #[test]
+    fn test_stats_accumulate_every_operation() {
+        let mut rng = SplitMix64::new(12);
+        for t in 2..=5 {
+            let mut tree = BTree::new(t);
+            let (mut splits, mut merges, mut borrows) = (0, 0, 0);
+            for _ in 0..800 {
+                let k = (rng.next_u64() % 300) as i32;
+                if rng.chance(0.4) {
+                    tree.delete(k);
+                } else {
+                    tree.insert(k);
+                }
+                let last = tree.get_last_stats();
+                splits += last.splits;
+                merges += last.merges;
+                borrows += last.borrows;
+            }
+            let stats = tree.stats();
+            assert_eq!(
+                (stats.splits, stats.merges, stats.borrows),
+                (splits, merges, borrows)
+            );
+            assert_eq!(stats.nodes_per_level.len(), tree.get_height());
+            assert_eq!(
+                stats.nodes_per_level.iter().sum::<usize>(),
+                stats.internal_nodes + stats.leaf_nodes
+            );
+            assert_eq!(
+                stats.fill.nodes,
+                stats.leaf_nodes + stats.internal_nodes
+            );
+            assert_eq!(
+                stats.nodes_per_level.last(),
+                Some(&stats.leaf_nodes)
+            );
+            assert_eq!(
+                stats.keys_per_level.iter().sum::<usize>(),
+                tree.get_total_keys()
+            );
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::btree::event::BTreeEvent;
+use crate::btree::fill::FillStats;
+use crate::btree::operation::BTree;
+use crate::trace::TraceEvent;
+use std::collections::HashSet;
+use std::ops::AddAssign;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// 分割とマージを行うタイミング
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SplitStrategy {
+    /// 下りながら満杯のノードを先に分割し、キーが最小のノードを先に補強する (CLRS)
+    #[default]
+    TopDown,
+
+    /// 葉で挿入・削除してから、あふれたノードや足りなくなったノードだけを
+    /// 親に戻る途中で直す
+    BottomUp,
+}
+
+impl SplitStrategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            SplitStrategy::TopDown => "topdown",
+            SplitStrategy::BottomUp => "bottomup",
+        }
+    }
+}
+
+impl FromStr for SplitStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "topdown" => SplitStrategy::TopDown,
+            "bottomup" => SplitStrategy::BottomUp,
+            _ => return Err(format!("unknown split strategy: {s}")),
+        })
+    }
+}
+
+/// 操作で起きた構造変更と、読み書きしたノードの数
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    /// 訪問したノード
+    pub visited: usize,
+
+    /// キーか子を書き換えたノード (作ったノードと消したノードを含む)
+    pub touched: usize,
+
+    pub splits: usize,
+    pub merges: usize,
+    pub borrows: usize,
+}
+
+impl OperationStats {
+    /// 1つの操作のトレースとイベントから数える
+    pub fn collect(trace: &[TraceEvent], events: &[BTreeEvent]) -> Self {
+        let mut visited = HashSet::new();
+        let mut touched = HashSet::new();
+        for event in trace {
+            match event {
+                TraceEvent::Visit { node } => {
+                    visited.insert(*node);
+                }
+                TraceEvent::CreateNode { node, .. }
+                | TraceEvent::DeleteNode { node } => {
+                    touched.insert(*node);
+                }
+                TraceEvent::MoveKey { from, to, .. } => {
+                    touched.extend(from);
+                    touched.extend(to);
+                }
+                TraceEvent::Link { parent, .. }
+                | TraceEvent::Unlink { parent, .. } => {
+                    touched.insert(*parent);
+                }
+                TraceEvent::Compare { .. }
+                | TraceEvent::Highlight { .. }
+                | TraceEvent::Annotate { .. } => {}
+            }
+        }
+
+        let mut stats = OperationStats {
+            visited: visited.len(),
+            touched: touched.len(),
+            ..OperationStats::default()
+        };
+        for event in events {
+            match event {
+                BTreeEvent::Split { .. } => stats.splits += 1,
+                BTreeEvent::Merge { .. } => stats.merges += 1,
+                BTreeEvent::BorrowFromPrev { .. }
+                | BTreeEvent::BorrowFromNext { .. } => stats.borrows += 1,
+                BTreeEvent::ReplaceWithPredecessor { .. }
+                | BTreeEvent::ReplaceWithSuccessor { .. } => {}
+            }
+        }
+        stats
+    }
+}
+
+impl AddAssign for OperationStats {
+    fn add_assign(&mut self, other: Self) {
+        self.visited += other.visited;
+        self.touched += other.touched;
+        self.splits += other.splits;
+        self.merges += other.merges;
+        self.borrows += other.borrows;
+    }
+}
+
+/// 2つの戦略で同じ操作列を行った結果
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrategyComparison {
+    /// 全ての操作の合計
+    pub top_down: OperationStats,
+    pub bottom_up: OperationStats,
+
+    /// 操作を終えた木の充填率
+    pub top_down_fill: FillStats,
+    pub bottom_up_fill: FillStats,
+}
+
+/// 同じ次数tの2つの戦略の木に、insertsを順に挿入してからdeletesを順に削除して比べる
+#[wasm_bindgen]
+pub fn compare_strategies(
+    t: usize,
+    inserts: Vec<i32>,
+    deletes: Vec<i32>,
+) -> Result<StrategyComparison, String> {
+    if t < 2 {
+        return Err("min degree must be at least 2".into());
+    }
+    let run = |strategy| {
+        let mut tree = BTree::with_split_strategy(t, strategy);
+        let mut total = OperationStats::default();
+        for &k in &inserts {
+            tree.insert(k);
+            total += tree.get_last_stats();
+        }
+        for &k in &deletes {
+            tree.delete(k);
+            total += tree.get_last_stats();
+        }
+        (total, tree.fill_stats())
+    };
+    let (top_down, top_down_fill) = run(SplitStrategy::TopDown);
+    let (bottom_up, bottom_up_fill) = run(SplitStrategy::BottomUp);
+    Ok(StrategyComparison {
+        top_down,
+        bottom_up,
+        top_down_fill,
+        bottom_up_fill,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_bottom_up_keeps_same_keys() {
+        let mut rng = SplitMix64::new(8);
+        for t in 2..=4 {
+            let mut top_down = BTree::new(t);
+            let mut bottom_up =
+                BTree::with_strategy(t, "bottomup").unwrap();
+            for _ in 0..600 {
+                let k = (rng.next_u64() % 80) as i32;
+                if rng.chance(0.45) {
+                    assert_eq!(bottom_up.delete(k), top_down.delete(k));
+                } else {
+                    top_down.insert(k);
+                    bottom_up.insert(k);
+                }
+                assert_eq!(
+                    bottom_up.check_invariants().unwrap(),
+                    top_down.check_invariants().unwrap()
+                );
+            }
+        }
+        assert_eq!(
+            BTree::with_strategy(2, "bottomup").unwrap().get_strategy(),
+            "bottomup"
+        );
+        assert!(BTree::with_strategy(2, "lazy").is_err());
+        assert!(BTree::with_strategy(1, "bottomup").is_err());
+        assert!(BTree::with_strategy(0, "topdown").is_err());
+    }
+
+    #[test]
+    fn test_bottom_up_splits_only_when_needed() {
+        // 根 [2] の下に [1] と [3|4|5]。6は右の葉に入らないので分割が要る
+        let mut top_down = BTree::new(2);
+        let mut bottom_up =
+            BTree::with_split_strategy(2, SplitStrategy::BottomUp);
+        for k in 1..=5 {
+            top_down.insert(k);
+            bottom_up.insert(k);
+        }
+
+        // 満杯の葉に入るキーは、トップダウンでは入る前に分割する
+        top_down.insert(0);
+        bottom_up.insert(0);
+        assert_eq!(top_down.get_last_stats().splits, 0);
+        assert_eq!(bottom_up.get_last_stats().splits, 0);
+        top_down.insert(6);
+        bottom_up.insert(6);
+        let (td, bu) =
+            (top_down.get_last_stats(), bottom_up.get_last_stats());
+        assert_eq!((td.splits, bu.splits), (1, 1));
+        assert_eq!(td.visited, 2);
+        assert_eq!(bu.visited, 2);
+
+        // ボトムアップは葉が4個目のキーを受け取ってから分割する
+        assert_eq!(
+            bottom_up.events(),
+            &[BTreeEvent::Split {
+                keys: vec![3, 4, 5, 6],
+                median: 4,
+            }]
+        );
+        assert_eq!(
+            top_down.events(),
+            &[BTreeEvent::Split {
+                keys: vec![3, 4, 5],
+                median: 4,
+            }]
+        );
+
+        // 探索は直前の挿入の分割を引き継がない
+        top_down.search_traced(1);
+        assert_eq!(top_down.get_last_stats().splits, 0);
+        assert!(top_down.events().is_empty());
+    }
+
+    #[test]
+    fn test_compare_strategies() {
+        let mut rng = SplitMix64::new(3);
+        let inserts: Vec<i32> =
+            (0..2000).map(|_| (rng.next_u64() % 5000) as i32).collect();
+        let deletes: Vec<i32> =
+            inserts.iter().step_by(2).copied().collect();
+        let comparison = compare_strategies(3, inserts, deletes).unwrap();
+        let (td, bu) = (comparison.top_down, comparison.bottom_up);
+
+        // 先回りの分割と補強は、結果的に要らなかったものも含む
+        assert!(td.splits >= bu.splits, "{td:?} {bu:?}");
+        assert!(td.merges + td.borrows > bu.merges + bu.borrows);
+        assert!(td.touched > bu.touched);
+        assert_eq!(
+            comparison.top_down_fill.keys,
+            comparison.bottom_up_fill.keys
+        );
+        assert!(compare_strategies(1, vec![1, 2], vec![1]).is_err());
+        assert!(compare_strategies(0, vec![], vec![]).is_err());
+    }
+}
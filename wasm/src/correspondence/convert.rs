@@ -0,0 +1,121 @@
+use crate::btree::{BTree, BTreeNode};
+use crate::rbtree::{Color, RbShape, RedBlackTree};
+use crate::trace::NodeIds;
+use wasm_bindgen::prelude::*;
+
+// 2-3-4木 (t=2 の BTree) と赤黒木の対応
+//
+// 2-3-4木の1つのノードは、黒ノードとその赤い子をまとめたものに対応する。
+//   [b]       → b(黒)
+//   [a|b]     → b(黒) の左に a(赤)
+//   [a|b|c]   → b(黒) の左右に a(赤), c(赤)
+
+/// t=2 の BTree を同じキー集合の赤黒木に変換
+#[wasm_bindgen]
+pub fn btree_to_rbtree(tree: &BTree) -> Result<RedBlackTree, String> {
+    if tree.get_min_degree() != 2 {
+        return Err(format!(
+            "only a 2-3-4 tree (t = 2) can be converted, got t = {}",
+            tree.get_min_degree()
+        ));
+    }
+    // 全て削除した後は空の葉が根に残るので、空の木として扱う
+    let shape = tree
+        .root()
+        .filter(|root| root.keys_len() > 0)
+        .map(node_to_shape);
+    Ok(RedBlackTree::from_shape(shape.as_ref()))
+}
+
+fn node_to_shape(node: &BTreeNode) -> RbShape {
+    let keys = node.keys();
+    let mut children = node
+        .child_nodes()
+        .iter()
+        .map(|child| Box::new(node_to_shape(child)));
+    let mut next = || children.next();
+
+    let red = |key: i32, left, right| {
+        Some(Box::new(RbShape {
+            key,
+            color: Color::Red,
+            left,
+            right,
+        }))
+    };
+
+    match keys.len() {
+        1 => RbShape {
+            key: keys[0],
+            color: Color::Black,
+            left: next(),
+            right: next(),
+        },
+        2 => RbShape {
+            key: keys[1],
+            color: Color::Black,
+            left: red(keys[0], next(), next()),
+            right: next(),
+        },
+        3 => RbShape {
+            key: keys[1],
+            color: Color::Black,
+            left: red(keys[0], next(), next()),
+            right: red(keys[2], next(), next()),
+        },
+        n => panic!("a 2-3-4 node must have 1 to 3 keys, got {n}"),
+    }
+}
+
+/// 赤黒木を同じキー集合の 2-3-4木 (t=2 の BTree) に変換
+#[wasm_bindgen]
+pub fn rbtree_to_btree(tree: &RedBlackTree) -> BTree {
+    let mut ids = NodeIds::new();
+    let root = tree
+        .shape()
+        .map(|shape| Box::new(shape_to_node(&shape, &mut ids)));
+    BTree::from_root(2, root)
+}
+
+/// 黒ノードと赤い子をまとめて1つの 2-3-4 ノードにする
+fn shape_to_node(black: &RbShape, ids: &mut NodeIds) -> BTreeNode {
+    let id = ids.fresh();
+    let mut keys = Vec::with_capacity(3);
+    let mut children = Vec::with_capacity(4);
+
+    if let Some(key) = absorb(&black.left, &mut children, ids) {
+        keys.push(key);
+    }
+    keys.push(black.key);
+    if let Some(key) = absorb(&black.right, &mut children, ids) {
+        keys.push(key);
+    }
+
+    BTreeNode::with_keys(2, keys, children, id)
+}
+
+/// 赤い子はキーを同じノードに取り込み、その子を子ノードにする。
+/// 黒い子はそのまま子ノードにする。
+#[allow(clippy::vec_box)]
+fn absorb(
+    side: &Option<Box<RbShape>>,
+    children: &mut Vec<Box<BTreeNode>>,
+    ids: &mut NodeIds,
+) -> Option<i32> {
+    match side.as_deref() {
+        Some(red) if red.color == Color::Red => {
+            children.extend(
+                [&red.left, &red.right]
+                    .into_iter()
+                    .flatten()
+                    .map(|g| Box::new(shape_to_node(g, ids))),
+            );
+            Some(red.key)
+        }
+        Some(black) => {
+            children.push(Box::new(shape_to_node(black, ids)));
+            None
+        }
+        None => None,
+    }
+}
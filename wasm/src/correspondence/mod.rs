@@ -0,0 +1,5 @@
+mod convert;
+mod operation;
+
+pub use convert::{btree_to_rbtree, rbtree_to_btree};
+pub use operation::{AlignedStep, Correspondence, EventPair, Operation};
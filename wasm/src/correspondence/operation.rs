@@ -0,0 +1,495 @@
+use crate::btree::{BTree, BTreeEvent, BTreeNode};
+use crate::correspondence::convert::rbtree_to_btree;
+use crate::rbtree::{RbEvent, RedBlackTree};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// 両方の木に適用する操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Insert(i32),
+    Delete(i32),
+}
+
+/// 2-3-4木のイベントと、それに対応する赤黒木のイベント列
+///
+/// btreeがNoneの場合、2-3-4木の構造変更を伴わない赤黒木側の処理
+/// (葉への追加など) を表す
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EventPair {
+    pub btree: Option<BTreeEvent>,
+    pub rbtree: Vec<RbEvent>,
+}
+
+/// 1つの操作について両方の木のイベントを並べたもの
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlignedStep {
+    /// 適用した操作
+    pub operation: Operation,
+
+    /// 木が変化したか (重複した挿入と、ないキーの削除はfalse)
+    pub applied: bool,
+
+    /// 対応づけたイベント
+    pub pairs: Vec<EventPair>,
+
+    /// 操作後の赤黒木を2-3-4木に変換したものがBTreeと一致するか
+    pub structures_match: bool,
+}
+
+impl AlignedStep {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let (kind, key) = match self.operation {
+            Operation::Insert(k) => ("insert", k),
+            Operation::Delete(k) => ("delete", k),
+        };
+        let _ =
+            js_sys::Reflect::set(&obj, &"operation".into(), &kind.into());
+        let _ = js_sys::Reflect::set(&obj, &"key".into(), &key.into());
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"applied".into(),
+            &self.applied.into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"structuresMatch".into(),
+            &self.structures_match.into(),
+        );
+
+        let pairs = Array::from_iter(self.pairs.iter().map(|pair| {
+            let p = Object::new();
+            let btree = match &pair.btree {
+                Some(event) => event.to_js_value(),
+                None => JsValue::NULL,
+            };
+            let rbtree = Array::from_iter(
+                pair.rbtree.iter().map(RbEvent::to_js_value),
+            );
+            let _ = js_sys::Reflect::set(&p, &"btree".into(), &btree);
+            let _ =
+                js_sys::Reflect::set(&p, &"rbtree".into(), &rbtree.into());
+            JsValue::from(p)
+        }));
+        let _ = js_sys::Reflect::set(&obj, &"pairs".into(), &pairs.into());
+
+        obj.into()
+    }
+}
+
+/// 同じ操作列を 2-3-4木 (t=2 の BTree) と赤黒木に適用し、
+/// 各操作のイベントを対応づける
+///
+/// 赤黒木への挿入と削除はトップダウン版を使うので、
+/// 2-3-4木の分割はそれぞれ赤黒木の色の反転 (と続く回転) に、
+/// マージは色の逆反転に、兄弟からの借用は2回の回転に対応する
+#[wasm_bindgen]
+pub struct Correspondence {
+    btree: BTree,
+    rbtree: RedBlackTree,
+}
+
+impl Default for Correspondence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl Correspondence {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Correspondence {
+            btree: BTree::new(2),
+            rbtree: RedBlackTree::new(),
+        }
+    }
+
+    /// キーkを両方の木に挿入し、対応づけたイベントを返す
+    pub fn insert(&mut self, k: i32) -> JsValue {
+        self.apply_insert(k).to_js_value()
+    }
+
+    /// キーkを両方の木から削除し、対応づけたイベントを返す
+    pub fn delete(&mut self, k: i32) -> JsValue {
+        self.apply_delete(k).to_js_value()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_btree_structure(&self) -> JsValue {
+        self.btree.get_structure()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_rbtree_structure(&self) -> JsValue {
+        self.rbtree.get_structure()
+    }
+}
+
+impl Correspondence {
+    pub fn btree(&self) -> &BTree {
+        &self.btree
+    }
+
+    pub fn rbtree(&self) -> &RedBlackTree {
+        &self.rbtree
+    }
+
+    /// 操作列を順に適用
+    pub fn run(&mut self, operations: &[Operation]) -> Vec<AlignedStep> {
+        operations.iter().map(|&op| self.apply(op)).collect()
+    }
+
+    /// 1つの操作を両方の木に適用
+    pub fn apply(&mut self, operation: Operation) -> AlignedStep {
+        match operation {
+            Operation::Insert(k) => self.apply_insert(k),
+            Operation::Delete(k) => self.apply_delete(k),
+        }
+    }
+
+    fn apply_insert(&mut self, k: i32) -> AlignedStep {
+        // 赤黒木は重複を持てないので、両方とも挿入しない
+        let (applied, pairs) = if self.rbtree.search(k) {
+            (false, Vec::new())
+        } else {
+            self.rbtree.insert_top_down(k);
+            self.btree.insert(k);
+            (true, self.align_insert())
+        };
+
+        AlignedStep {
+            operation: Operation::Insert(k),
+            applied,
+            pairs,
+            structures_match: self.structures_match(),
+        }
+    }
+
+    fn apply_delete(&mut self, k: i32) -> AlignedStep {
+        // ないキーの削除でも途中の補強で形が変わるので、両方とも削除しない
+        let (applied, pairs) = if self.rbtree.search(k) {
+            self.rbtree.delete_top_down(k);
+            self.btree.delete(k);
+            (true, self.align_delete())
+        } else {
+            (false, Vec::new())
+        };
+
+        AlignedStep {
+            operation: Operation::Delete(k),
+            applied,
+            pairs,
+            structures_match: self.structures_match(),
+        }
+    }
+
+    /// 操作後の赤黒木を2-3-4木に変換したものがBTreeと一致するか
+    fn structures_match(&self) -> bool {
+        // 全て削除したBTreeはキーのない根を残す
+        let root = self.btree.root().filter(|root| root.keys_len() > 0);
+        same_structure(root, rbtree_to_btree(&self.rbtree).root())
+    }
+
+    /// 分割i番目と、色の反転i番目から始まるイベント列を対応づける
+    fn align_insert(&self) -> Vec<EventPair> {
+        let mut groups: Vec<Vec<RbEvent>> = Vec::new();
+        for &event in self.rbtree.events() {
+            match event {
+                RbEvent::ColorFlip { .. } | RbEvent::Insert { .. } => {
+                    groups.push(vec![event])
+                }
+                _ => match groups.last_mut() {
+                    Some(group) => group.push(event),
+                    None => groups.push(vec![event]),
+                },
+            }
+        }
+
+        let mut splits = self.btree.events().iter().cloned();
+        let mut pairs: Vec<EventPair> = groups
+            .into_iter()
+            .map(|group| {
+                let btree = match group[0] {
+                    RbEvent::ColorFlip { .. } => splits.next(),
+                    _ => None,
+                };
+                EventPair {
+                    btree,
+                    rbtree: group,
+                }
+            })
+            .collect();
+
+        // 対応する色の反転がない分割 (本来は起こらない) も残しておく
+        pairs.extend(splits.map(|split| EventPair {
+            btree: Some(split),
+            rbtree: Vec::new(),
+        }));
+        pairs
+    }
+
+    /// マージ・借用・置き換えのi番目と、赤黒木の削除のi番目の手を対応づける
+    ///
+    /// 最後の葉からのキーの削除は、2-3-4木の構造変更を伴わない
+    fn align_delete(&self) -> Vec<EventPair> {
+        let mut events = self.btree.events().iter().cloned();
+        let mut pairs: Vec<EventPair> = self
+            .rbtree
+            .steps()
+            .into_iter()
+            .map(|step| EventPair {
+                btree: match step[0] {
+                    RbEvent::Remove { .. } => None,
+                    _ => events.next(),
+                },
+                rbtree: step.to_vec(),
+            })
+            .collect();
+
+        // 対応する手がない構造変更 (本来は起こらない) も残しておく
+        pairs.extend(events.map(|event| EventPair {
+            btree: Some(event),
+            rbtree: Vec::new(),
+        }));
+        pairs
+    }
+}
+
+/// 2つの木のノードごとのキーと形が一致するか
+fn same_structure(a: Option<&BTreeNode>, b: Option<&BTreeNode>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            a.keys() == b.keys()
+                && a.child_nodes().len() == b.child_nodes().len()
+                && a.child_nodes()
+                    .iter()
+                    .zip(b.child_nodes())
+                    .all(|(x, y)| same_structure(Some(x), Some(y)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correspondence::convert::btree_to_rbtree;
+
+    fn pseudo_random_keys(n: usize) -> Vec<i32> {
+        let mut x: u32 = 42;
+        let mut keys = Vec::new();
+        while keys.len() < n {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+            let k = ((x >> 8) % 5_000) as i32;
+            if !keys.contains(&k) {
+                keys.push(k);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn test_btree_to_rbtree_round_trip() {
+        let mut btree = BTree::new(2);
+        for k in pseudo_random_keys(200) {
+            btree.insert(k);
+        }
+
+        let rbtree = btree_to_rbtree(&btree).unwrap();
+        assert!(rbtree.is_valid(), "{:?}", rbtree.check_invariants());
+        assert_eq!(rbtree.get_total_keys(), btree.get_total_keys());
+
+        let back = rbtree_to_btree(&rbtree);
+        assert!(same_structure(back.root(), btree.root()));
+        assert_eq!(back.get_height(), btree.get_height());
+    }
+
+    #[test]
+    fn test_rbtree_to_btree_keeps_keys() {
+        let mut rbtree = RedBlackTree::new();
+        let keys = pseudo_random_keys(200);
+        for &k in &keys {
+            rbtree.insert(k);
+        }
+
+        let btree = rbtree_to_btree(&rbtree);
+        assert_eq!(btree.get_total_keys(), keys.len());
+        assert!(keys.iter().all(|&k| btree.search(k)));
+        // 黒高さが 2-3-4木の高さになる
+        assert_eq!(Ok(btree.get_height() + 1), rbtree.check_invariants());
+    }
+
+    #[test]
+    fn test_btree_to_rbtree_rejects_other_degrees() {
+        let mut btree = BTree::new(3);
+        btree.insert(1);
+        assert!(btree_to_rbtree(&btree).is_err());
+    }
+
+    #[test]
+    fn test_correspondence_splits_match_color_flips() {
+        let mut c = Correspondence::new();
+        let ops: Vec<Operation> = pseudo_random_keys(300)
+            .into_iter()
+            .map(Operation::Insert)
+            .collect();
+
+        let mut splits = 0;
+        for step in c.run(&ops) {
+            assert!(step.applied);
+            assert!(step.structures_match, "{:?}", step.operation);
+
+            for pair in &step.pairs {
+                match (&pair.btree, pair.rbtree.first()) {
+                    (
+                        Some(BTreeEvent::Split { median, .. }),
+                        Some(RbEvent::ColorFlip { key }),
+                    ) => {
+                        assert_eq!(median, key);
+                        splits += 1;
+                    }
+                    (None, Some(RbEvent::Insert { .. })) => {}
+                    other => panic!("unexpected pair {other:?}"),
+                }
+            }
+        }
+        assert!(splits > 0);
+        assert!(c.rbtree().is_valid());
+    }
+
+    #[test]
+    fn test_correspondence_duplicate_and_delete() {
+        let mut c = Correspondence::new();
+        for k in 1..=20 {
+            c.apply(Operation::Insert(k));
+        }
+
+        let dup = c.apply(Operation::Insert(5));
+        assert!(!dup.applied);
+        assert!(dup.pairs.is_empty());
+        assert_eq!(c.btree().get_total_keys(), 20);
+
+        // ないキーの削除はどちらの木も変えない
+        let before = c.rbtree().shape();
+        let missing = c.apply(Operation::Delete(99));
+        assert!(!missing.applied);
+        assert!(missing.pairs.is_empty());
+        assert_eq!(c.rbtree().shape(), before);
+
+        let step = c.apply(Operation::Delete(5));
+        assert!(step.applied);
+        assert!(step.structures_match);
+        assert!(!c.btree().search(5));
+        assert!(!c.rbtree().search(5));
+        assert_eq!(
+            step.pairs.last().map(|pair| (&pair.btree, pair.rbtree[0])),
+            Some((&None, RbEvent::Remove { key: 5 }))
+        );
+    }
+
+    #[test]
+    fn test_structures_match_across_mixed_operations() {
+        let mut c = Correspondence::new();
+        let mut x: u32 = 7;
+        let (mut merges, mut borrows) = (0, 0);
+        for _ in 0..2000 {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+            let k = ((x >> 8) % 200) as i32;
+            let op = if (x >> 20) % 3 == 0 {
+                Operation::Insert(k)
+            } else {
+                Operation::Delete(k)
+            };
+            let step = c.apply(op);
+            assert!(step.structures_match, "{op:?}");
+            assert!(c.rbtree().is_valid(), "{op:?}");
+
+            // マージは色の逆反転で終わり、借用は区切りキーでの回転を含む
+            for pair in &step.pairs {
+                let last = pair.rbtree.last().copied();
+                match &pair.btree {
+                    Some(BTreeEvent::Merge { separator, .. }) => {
+                        merges += 1;
+                        assert_eq!(
+                            last,
+                            Some(RbEvent::ReverseFlip { key: *separator })
+                        );
+                    }
+                    Some(BTreeEvent::BorrowFromPrev {
+                        parent_key,
+                        ..
+                    }) => {
+                        borrows += 1;
+                        assert!(pair.rbtree.contains(
+                            &RbEvent::RotateRight { key: *parent_key }
+                        ));
+                    }
+                    Some(BTreeEvent::BorrowFromNext {
+                        parent_key,
+                        ..
+                    }) => {
+                        borrows += 1;
+                        assert!(pair.rbtree.contains(
+                            &RbEvent::RotateLeft { key: *parent_key }
+                        ));
+                    }
+                    Some(BTreeEvent::ReplaceWithPredecessor {
+                        key,
+                        predecessor: with,
+                    })
+                    | Some(BTreeEvent::ReplaceWithSuccessor {
+                        key,
+                        successor: with,
+                    }) => {
+                        assert_eq!(
+                            pair.rbtree,
+                            vec![RbEvent::Replace {
+                                key: *key,
+                                with: *with
+                            }]
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+        assert!(merges > 0 && borrows > 0, "{merges} {borrows}");
+        assert_eq!(
+            c.btree().get_total_keys(),
+            c.rbtree().get_total_keys()
+        );
+    }
+
+    #[test]
+    fn test_delete_every_key_in_both_trees() {
+        let keys = pseudo_random_keys(300);
+        let mut c = Correspondence::new();
+        c.run(
+            &keys
+                .iter()
+                .copied()
+                .map(Operation::Insert)
+                .collect::<Vec<_>>(),
+        );
+        for (i, &k) in keys.iter().enumerate().rev() {
+            let step = c.apply(Operation::Delete(k));
+            assert!(step.applied && step.structures_match, "{k}");
+            assert_eq!(c.rbtree().get_total_keys(), i);
+        }
+        assert!(c.btree().root().is_none_or(|root| root.keys_len() == 0));
+        assert_eq!(c.rbtree().get_height(), 0);
+    }
+
+    #[test]
+    fn test_btree_to_rbtree_after_deleting_everything() {
+        let mut btree = BTree::new(2);
+        btree.insert(1);
+        btree.delete(1);
+        let rbtree = btree_to_rbtree(&btree).unwrap();
+        assert_eq!(rbtree.get_total_keys(), 0);
+        assert!(rbtree.is_valid());
+    }
+}
@@ -0,0 +1,8 @@
+//! ファイルに置いたB-Tree (wasm以外のターゲット用)
+
+mod operation;
+mod page;
+mod pager;
+
+pub use operation::DiskBTree;
+pub use page::{DiskNode, FORMAT_VERSION, FileHeader};
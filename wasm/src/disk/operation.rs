@@ -0,0 +1,635 @@
+use crate::disk::page::DiskNode;
+use crate::disk::pager::Pager;
+use std::io;
+use std::path::Path;
+
+/// ファイルに置いたB-Tree (ネイティブ用)
+///
+/// 各ノードは固定長のページに置き、操作の度に必要なページだけを読み書きする。
+/// 挿入・削除の手順は`BTree`と同じなので、同じ操作列から同じ形の木になる
+pub struct DiskBTree {
+    pager: Pager,
+}
+
+impl DiskBTree {
+    /// 最小次数tの空の木をpathに作る (既存のファイルは上書き)
+    pub fn create(path: impl AsRef<Path>, t: usize) -> io::Result<Self> {
+        Ok(DiskBTree {
+            pager: Pager::create(path.as_ref(), t)?,
+        })
+    }
+
+    /// 以前に作ったファイルを開き、見出しから根を見つける
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(DiskBTree {
+            pager: Pager::open(path.as_ref())?,
+        })
+    }
+
+    pub fn min_degree(&self) -> usize {
+        self.pager.header.t
+    }
+
+    fn max_keys(&self) -> usize {
+        2 * self.min_degree() - 1
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.pager.page_size()
+    }
+
+    /// 見出しを含むページ数
+    pub fn page_count(&self) -> usize {
+        self.pager.header.page_count as usize
+    }
+
+    /// 空きリストにあるページ数
+    pub fn free_page_count(&mut self) -> io::Result<usize> {
+        self.pager.free_page_count()
+    }
+
+    /// 根から数えてdepth段目のノードを読めるか
+    ///
+    /// 子のページが自身や祖先を指す壊れたファイルでも止まるように、
+    /// ページ数以上の深さは不正とする
+    fn check_depth(&self, depth: usize) -> io::Result<()> {
+        if depth >= self.page_count() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("tree is deeper than {} pages", self.page_count()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 書き込みをディスクに反映する
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.pager.sync()
+    }
+
+    /// キーkを探索
+    pub fn search(&mut self, k: i32) -> io::Result<bool> {
+        let mut page = self.pager.header.root;
+        let mut depth = 0;
+        while page != 0 {
+            self.check_depth(depth)?;
+            depth += 1;
+            let node = self.pager.read_node(page)?;
+            let i = node.keys.partition_point(|&key| key < k);
+            if i < node.keys.len() && node.keys[i] == k {
+                return Ok(true);
+            }
+            if node.leaf {
+                break;
+            }
+            page = node.children[i];
+        }
+        Ok(false)
+    }
+
+    /// キーkを挿入
+    pub fn insert(&mut self, k: i32) -> io::Result<()> {
+        let root = self.pager.header.root;
+        if root == 0 {
+            // ツリーが空の場合
+            let page = self.pager.allocate()?;
+            let node = DiskNode {
+                keys: vec![k],
+                children: Vec::new(),
+                leaf: true,
+            };
+            self.pager.write_node(page, &node)?;
+            self.pager.header.root = page;
+        } else {
+            let node = self.pager.read_node(root)?;
+            if node.keys.len() == self.max_keys() {
+                // ルートが満杯の場合、新しいルートの下で分割
+                let page = self.pager.allocate()?;
+                let mut s = DiskNode {
+                    keys: Vec::new(),
+                    children: vec![root],
+                    leaf: false,
+                };
+                let (y, z) = self.split_child(page, &mut s, 0, node)?;
+                let (child, node) = if s.keys[0] < k {
+                    (s.children[1], z)
+                } else {
+                    (s.children[0], y)
+                };
+                self.insert_not_full(child, node, k)?;
+                self.pager.header.root = page;
+            } else {
+                self.insert_not_full(root, node, k)?;
+            }
+        }
+        self.pager.write_header()
+    }
+
+    fn insert_not_full(
+        &mut self,
+        mut page: u32,
+        mut node: DiskNode,
+        k: i32,
+    ) -> io::Result<()> {
+        let mut depth = 0;
+        loop {
+            self.check_depth(depth)?;
+            depth += 1;
+            // 同じキーの後ろに入れる
+            let mut i = node.keys.partition_point(|&key| key <= k);
+            if node.leaf {
+                node.keys.insert(i, k);
+                return self.pager.write_node(page, &node);
+            }
+
+            let child = self.pager.read_node(node.children[i])?;
+            let next = if child.keys.len() == self.max_keys() {
+                let (y, z) =
+                    self.split_child(page, &mut node, i, child)?;
+                if node.keys[i] < k {
+                    i += 1;
+                    z
+                } else {
+                    y
+                }
+            } else {
+                child
+            };
+            page = node.children[i];
+            node = next;
+        }
+    }
+
+    /// parentのi番目の子yを分割し、分割後の2つのノードを返す
+    ///
+    /// parent、y、新しいノードは全て書き込む
+    fn split_child(
+        &mut self,
+        parent_page: u32,
+        parent: &mut DiskNode,
+        i: usize,
+        mut y: DiskNode,
+    ) -> io::Result<(DiskNode, DiskNode)> {
+        let t = self.min_degree();
+        let mut z = DiskNode {
+            keys: y.keys.split_off(t),
+            children: Vec::new(),
+            leaf: y.leaf,
+        };
+        if !y.leaf {
+            z.children = y.children.split_off(t);
+        }
+        let middle_key = y.keys.pop().unwrap();
+
+        let z_page = self.pager.allocate()?;
+        parent.keys.insert(i, middle_key);
+        parent.children.insert(i + 1, z_page);
+        self.pager.write_node(parent.children[i], &y)?;
+        self.pager.write_node(z_page, &z)?;
+        self.pager.write_node(parent_page, parent)?;
+        Ok((y, z))
+    }
+
+    /// キーkを1つ削除し、あったかどうかを返す
+    pub fn delete(&mut self, k: i32) -> io::Result<bool> {
+        let root = self.pager.header.root;
+        if root == 0 {
+            return Ok(false);
+        }
+        let node = self.pager.read_node(root)?;
+        let deleted = self.delete_from(root, node, k, 0)?;
+        self.pager.write_header()?;
+        Ok(deleted)
+    }
+
+    fn delete_from(
+        &mut self,
+        page: u32,
+        mut node: DiskNode,
+        k: i32,
+        depth: usize,
+    ) -> io::Result<bool> {
+        self.check_depth(depth)?;
+        let idx = node.keys.partition_point(|&key| key < k);
+        if idx < node.keys.len() && node.keys[idx] == k {
+            if node.leaf {
+                node.keys.remove(idx);
+                self.pager.write_node(page, &node)?;
+                Ok(true)
+            } else {
+                self.delete_from_internal_node(page, node, idx, depth)
+            }
+        } else if node.leaf {
+            Ok(false)
+        } else {
+            self.delete_from_subtree(page, node, idx, k, depth)
+        }
+    }
+
+    fn delete_from_internal_node(
+        &mut self,
+        page: u32,
+        mut node: DiskNode,
+        idx: usize,
+        depth: usize,
+    ) -> io::Result<bool> {
+        let t = self.min_degree();
+        let left = self.pager.read_node(node.children[idx])?;
+        if left.keys.len() >= t {
+            // 前駆で置き換え
+            let predecessor = self.edge_key(&left, true)?;
+            node.keys[idx] = predecessor;
+            self.pager.write_node(page, &node)?;
+            return self.delete_from(
+                node.children[idx],
+                left,
+                predecessor,
+                depth + 1,
+            );
+        }
+        let right = self.pager.read_node(node.children[idx + 1])?;
+        if right.keys.len() >= t {
+            // 後継で置き換え
+            let successor = self.edge_key(&right, false)?;
+            node.keys[idx] = successor;
+            self.pager.write_node(page, &node)?;
+            return self.delete_from(
+                node.children[idx + 1],
+                right,
+                successor,
+                depth + 1,
+            );
+        }
+
+        // どちらも十分でない場合、マージしてから削除
+        let k = node.keys[idx];
+        let merged =
+            self.merge_children(page, &mut node, idx, left, right)?;
+        self.delete_from(node.children[idx], merged, k, depth + 1)
+    }
+
+    /// 部分木の最大のキー (lastがfalseなら最小のキー)
+    fn edge_key(
+        &mut self,
+        node: &DiskNode,
+        last: bool,
+    ) -> io::Result<i32> {
+        let mut node = node.clone();
+        let mut depth = 0;
+        while !node.leaf {
+            self.check_depth(depth)?;
+            depth += 1;
+            let child = if last {
+                node.children[node.children.len() - 1]
+            } else {
+                node.children[0]
+            };
+            node = self.pager.read_node(child)?;
+        }
+        Ok(if last {
+            node.keys[node.keys.len() - 1]
+        } else {
+            node.keys[0]
+        })
+    }
+
+    fn delete_from_subtree(
+        &mut self,
+        page: u32,
+        mut node: DiskNode,
+        idx: usize,
+        k: i32,
+        depth: usize,
+    ) -> io::Result<bool> {
+        let is_last = idx == node.keys.len();
+        let child = self.pager.read_node(node.children[idx])?;
+        if child.keys.len() < self.min_degree() {
+            self.fill_child(page, &mut node, idx, child)?;
+        }
+
+        // 最後の子が左の兄弟にマージされた場合、キーは1つ左の子にある
+        let idx = if is_last && idx > node.keys.len() {
+            idx - 1
+        } else {
+            idx
+        };
+        let child = self.pager.read_node(node.children[idx])?;
+        self.delete_from(node.children[idx], child, k, depth + 1)
+    }
+
+    /// 子を兄弟から借りるかマージして補強する
+    fn fill_child(
+        &mut self,
+        page: u32,
+        node: &mut DiskNode,
+        idx: usize,
+        mut child: DiskNode,
+    ) -> io::Result<()> {
+        let t = self.min_degree();
+        if idx != 0 {
+            let mut sibling =
+                self.pager.read_node(node.children[idx - 1])?;
+            if sibling.keys.len() >= t {
+                // 前の兄弟から借りる
+                child.keys.insert(0, node.keys[idx - 1]);
+                if !child.leaf {
+                    child
+                        .children
+                        .insert(0, sibling.children.pop().unwrap());
+                }
+                node.keys[idx - 1] = sibling.keys.pop().unwrap();
+                self.pager.write_node(node.children[idx - 1], &sibling)?;
+                self.pager.write_node(node.children[idx], &child)?;
+                return self.pager.write_node(page, node);
+            }
+        }
+        if idx < node.children.len() - 1 {
+            let mut sibling =
+                self.pager.read_node(node.children[idx + 1])?;
+            if sibling.keys.len() >= t {
+                // 次の兄弟から借りる
+                child.keys.push(node.keys[idx]);
+                if !child.leaf {
+                    child.children.push(sibling.children.remove(0));
+                }
+                node.keys[idx] = sibling.keys.remove(0);
+                self.pager.write_node(node.children[idx + 1], &sibling)?;
+                self.pager.write_node(node.children[idx], &child)?;
+                return self.pager.write_node(page, node);
+            }
+        }
+
+        // どちらも借りられない場合、マージ
+        if idx != node.children.len() - 1 {
+            let sibling = self.pager.read_node(node.children[idx + 1])?;
+            self.merge_children(page, node, idx, child, sibling)?;
+        } else {
+            let sibling = self.pager.read_node(node.children[idx - 1])?;
+            self.merge_children(page, node, idx - 1, sibling, child)?;
+        }
+        Ok(())
+    }
+
+    /// idx番目とidx+1番目の子を区切りのキーとともにマージする
+    ///
+    /// 右の子のページは空きリストに戻す。根が空になった場合は
+    /// マージしたノードを新しい根にし、根のページも空きリストに戻す
+    fn merge_children(
+        &mut self,
+        page: u32,
+        node: &mut DiskNode,
+        idx: usize,
+        mut child: DiskNode,
+        sibling: DiskNode,
+    ) -> io::Result<DiskNode> {
+        let sibling_page = node.children.remove(idx + 1);
+        child.keys.push(node.keys.remove(idx));
+        child.keys.extend(sibling.keys);
+        child.children.extend(sibling.children);
+
+        self.pager.write_node(node.children[idx], &child)?;
+        self.pager.free(sibling_page)?;
+        if node.keys.is_empty() {
+            // キーのない内部ノードはページに書かない
+            self.pager.header.root = node.children[0];
+            self.pager.free(page)?;
+        } else {
+            self.pager.write_node(page, node)?;
+        }
+        Ok(child)
+    }
+
+    /// 全てのキーを昇順に
+    pub fn keys(&mut self) -> io::Result<Vec<i32>> {
+        let mut keys = Vec::new();
+        let root = self.pager.header.root;
+        if root != 0 {
+            self.collect(root, 0, &mut keys)?;
+        }
+        Ok(keys)
+    }
+
+    fn collect(
+        &mut self,
+        page: u32,
+        depth: usize,
+        keys: &mut Vec<i32>,
+    ) -> io::Result<()> {
+        self.check_depth(depth)?;
+        let node = self.pager.read_node(page)?;
+        for (i, &key) in node.keys.iter().enumerate() {
+            if let Some(&child) = node.children.get(i) {
+                self.collect(child, depth + 1, keys)?;
+            }
+            keys.push(key);
+        }
+        if let Some(&child) = node.children.get(node.keys.len()) {
+            self.collect(child, depth + 1, keys)?;
+        }
+        Ok(())
+    }
+
+    /// 深さごとのノードのキー (根から順に、各段は左から)
+    pub fn levels(&mut self) -> io::Result<Vec<Vec<Vec<i32>>>> {
+        let mut levels = Vec::new();
+        let mut pages = Vec::from_iter(
+            Some(self.pager.header.root).filter(|&p| p != 0),
+        );
+        while !pages.is_empty() {
+            self.check_depth(levels.len())?;
+            let mut level = Vec::new();
+            let mut next = Vec::new();
+            for page in pages {
+                let node = self.pager.read_node(page)?;
+                next.extend(&node.children);
+                level.push(node.keys);
+            }
+            levels.push(level);
+            pages = next;
+        }
+        Ok(levels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::BTree;
+    use crate::paged::{PAGE_HEADER_SIZE, page_size};
+    use crate::rng::SplitMix64;
+    use std::path::PathBuf;
+
+    /// テストが終わると消える一時ファイル
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("btree-{}-{name}.db", std::process::id()));
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// 操作 (true なら挿入) をDiskBTreeとBTreeに同じように適用して比べる
+    fn check_against_btree(name: &str, t: usize, ops: &[(bool, i32)]) {
+        let file = TempFile::new(name);
+        let mut disk = DiskBTree::create(&file.0, t).unwrap();
+        let mut memory = BTree::new(t);
+        for &(insert, k) in ops {
+            if insert {
+                disk.insert(k).unwrap();
+                memory.insert(k);
+            } else {
+                assert_eq!(disk.delete(k).unwrap(), memory.delete(k));
+            }
+            assert_eq!(disk.levels().unwrap(), memory.levels(), "{k}");
+        }
+        for k in -1..=ops.iter().map(|op| op.1).max().unwrap_or(0) + 1 {
+            assert_eq!(disk.search(k).unwrap(), memory.search(k));
+        }
+    }
+
+    #[test]
+    fn test_same_shape_as_btree() {
+        let inserts =
+            |keys: &[i32]| keys.iter().map(|&k| (true, k)).collect();
+        let mut ops: Vec<(bool, i32)> =
+            inserts(&[10, 20, 5, 6, 12, 30, 7, 17]);
+        ops.extend([(false, 6), (false, 13), (false, 7), (false, 4)]);
+        check_against_btree("basic", 3, &ops);
+
+        // 順に挿入して、逆順と3つおきに削除
+        let mut ops: Vec<(bool, i32)> =
+            inserts(&(1..=50).collect::<Vec<_>>());
+        ops.extend((1..=50).rev().step_by(3).map(|k| (false, k)));
+        ops.extend((1..=50).map(|k| (false, k)));
+        check_against_btree("sequential", 2, &ops);
+
+        let mut rng = SplitMix64::new(3);
+        for t in 2..=4 {
+            let ops: Vec<(bool, i32)> = (0..600)
+                .map(|_| (!rng.chance(0.4), (rng.next_u64() % 80) as i32))
+                .collect();
+            check_against_btree(&format!("random-{t}"), t, &ops);
+        }
+    }
+
+    #[test]
+    fn test_reopen_and_reuse_free_pages() {
+        let file = TempFile::new("reopen");
+        let mut tree = DiskBTree::create(&file.0, 2).unwrap();
+        for k in 0..100 {
+            tree.insert(k).unwrap();
+        }
+        let pages = tree.page_count();
+        for k in (0..100).filter(|k| k % 4 != 0) {
+            assert!(tree.delete(k).unwrap());
+        }
+        let free = tree.free_page_count().unwrap();
+        assert!(free > 0);
+        tree.sync().unwrap();
+        drop(tree);
+
+        let mut tree = DiskBTree::open(&file.0).unwrap();
+        assert_eq!(tree.min_degree(), 2);
+        assert_eq!(tree.page_size(), crate::paged::page_size(2));
+        assert_eq!(
+            tree.keys().unwrap(),
+            (0..100).step_by(4).collect::<Vec<_>>()
+        );
+        assert_eq!(tree.free_page_count().unwrap(), free);
+
+        // 空きページを使い切るまでファイルは伸びない
+        let mut k = 1;
+        while tree.free_page_count().unwrap() > 0 {
+            tree.insert(k).unwrap();
+            k += 4;
+        }
+        assert_eq!(tree.page_count(), pages);
+        assert!(tree.search(1).unwrap());
+    }
+
+    #[test]
+    fn test_open_rejects_other_files() {
+        let file = TempFile::new("garbage");
+        std::fs::write(&file.0, b"definitely not a b-tree file").unwrap();
+        let error = DiskBTree::open(&file.0).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(DiskBTree::create(&file.0, 1).is_err());
+    }
+
+    #[test]
+    fn test_open_rejects_zero_page_count() {
+        let file = TempFile::new("zero-pages");
+        DiskBTree::create(&file.0, 2).unwrap();
+        let mut bytes = std::fs::read(&file.0).unwrap();
+        bytes[20..24].copy_from_slice(&0u32.to_le_bytes());
+        std::fs::write(&file.0, bytes).unwrap();
+        let error = DiskBTree::open(&file.0).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_corrupted_pages_return_invalid_data() {
+        let t = 2;
+        let file = TempFile::new("corrupted");
+        let mut tree = DiskBTree::create(&file.0, t).unwrap();
+        for k in 1..=10 {
+            tree.insert(k).unwrap();
+        }
+        drop(tree);
+        let original = std::fs::read(&file.0).unwrap();
+        let root =
+            u32::from_le_bytes(original[12..16].try_into().unwrap());
+        let root_offset = root as usize * page_size(t);
+        let children = root_offset + PAGE_HEADER_SIZE + (2 * t - 1) * 4;
+        let first_child = u32::from_le_bytes(
+            original[children..children + 4].try_into().unwrap(),
+        );
+
+        let check = |bytes: &[u8]| {
+            std::fs::write(&file.0, bytes).unwrap();
+            let mut tree = DiskBTree::open(&file.0).unwrap();
+            let kind = |e: io::Error| e.kind();
+            assert_eq!(
+                tree.search(0).map_err(kind),
+                Err(io::ErrorKind::InvalidData)
+            );
+            assert_eq!(
+                tree.keys().map_err(kind),
+                Err(io::ErrorKind::InvalidData)
+            );
+            assert_eq!(
+                tree.levels().map_err(kind),
+                Err(io::ErrorKind::InvalidData)
+            );
+            assert_eq!(
+                tree.insert(0).map_err(kind),
+                Err(io::ErrorKind::InvalidData)
+            );
+            let mut tree = DiskBTree::open(&file.0).unwrap();
+            assert_eq!(
+                tree.delete(1).map_err(kind),
+                Err(io::ErrorKind::InvalidData)
+            );
+        };
+
+        // 根の最初の子が根自身を指す
+        let mut bytes = original.clone();
+        bytes[children..children + 4].copy_from_slice(&root.to_le_bytes());
+        check(&bytes);
+
+        // 根でない葉にキーがない
+        let mut bytes = original.clone();
+        let leaf_offset = first_child as usize * page_size(t);
+        bytes[leaf_offset..leaf_offset + 4]
+            .copy_from_slice(&0u32.to_le_bytes());
+        check(&bytes);
+    }
+}
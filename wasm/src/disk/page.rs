@@ -0,0 +1,170 @@
+//! ページの配置
+//!
+//! ページ0はファイルの見出し、それ以降は1ページに1ノードを置く。
+//! 整数は全てリトルエンディアン
+//!
+//! ```text
+//! 見出し  0..4 "BTPG" | 4..8 形式の版 | 8..12 最小次数t
+//!         12..16 根のページ | 16..20 空きリストの先頭 | 20..24 ページ数
+//! ノード  0..4 キー数 | 4 種類 (1 内部, 2 葉) | 5..8 予備
+//!         8.. キー (i32) 2t-1個 | 子のページ番号 (u32) 2t個
+//! 空き    0..4 0 | 4 種類 (0) | 5..8 予備 | 8..12 次の空きページ
+//! ```
+//!
+//! ページ番号0は「なし」を表す (根のない木、空きリストの終わり)
+
+use crate::paged::{PAGE_HEADER_SIZE, page_size};
+use std::io;
+
+pub const MAGIC: [u8; 4] = *b"BTPG";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// 見出しに必要な大きさ
+pub const FILE_HEADER_SIZE: usize = 24;
+
+const KIND_FREE: u8 = 0;
+const KIND_INTERNAL: u8 = 1;
+const KIND_LEAF: u8 = 2;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u32(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// ファイルの見出し (ページ0)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileHeader {
+    pub t: usize,
+    pub root: u32,
+    pub free_head: u32,
+    pub page_count: u32,
+}
+
+impl FileHeader {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![0; page_size(self.t)];
+        buf[0..4].copy_from_slice(&MAGIC);
+        write_u32(&mut buf, 4, FORMAT_VERSION);
+        write_u32(&mut buf, 8, self.t as u32);
+        write_u32(&mut buf, 12, self.root);
+        write_u32(&mut buf, 16, self.free_head);
+        write_u32(&mut buf, 20, self.page_count);
+        buf
+    }
+
+    pub fn decode(buf: &[u8; FILE_HEADER_SIZE]) -> io::Result<Self> {
+        if buf[0..4] != MAGIC {
+            return Err(invalid("not a B-tree page file".into()));
+        }
+        let version = read_u32(buf, 4);
+        if version != FORMAT_VERSION {
+            return Err(invalid(format!(
+                "unsupported format version {version}"
+            )));
+        }
+        let t = read_u32(buf, 8) as usize;
+        if t < 2 {
+            return Err(invalid(format!("invalid min degree {t}")));
+        }
+        // ページ0はヘッダ自身なので、ページ数は少なくとも1
+        let page_count = read_u32(buf, 20);
+        if page_count == 0 {
+            return Err(invalid("page count is zero".into()));
+        }
+        Ok(FileHeader {
+            t,
+            root: read_u32(buf, 12),
+            free_head: read_u32(buf, 16),
+            page_count,
+        })
+    }
+}
+
+/// ページから読んだノード
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiskNode {
+    pub keys: Vec<i32>,
+
+    /// 子のページ番号 (葉なら空)
+    pub children: Vec<u32>,
+    pub leaf: bool,
+}
+
+impl DiskNode {
+    pub fn encode(&self, t: usize) -> Vec<u8> {
+        let mut buf = vec![0; page_size(t)];
+        write_u32(&mut buf, 0, self.keys.len() as u32);
+        buf[4] = if self.leaf { KIND_LEAF } else { KIND_INTERNAL };
+        for (i, &key) in self.keys.iter().enumerate() {
+            let offset = PAGE_HEADER_SIZE + i * 4;
+            buf[offset..offset + 4].copy_from_slice(&key.to_le_bytes());
+        }
+        let base = PAGE_HEADER_SIZE + (2 * t - 1) * 4;
+        for (i, &child) in self.children.iter().enumerate() {
+            write_u32(&mut buf, base + i * 4, child);
+        }
+        buf
+    }
+
+    /// キーのないノードは根の葉 (空の木) だけ許す
+    pub fn decode(
+        buf: &[u8],
+        t: usize,
+        page: u32,
+        root: bool,
+    ) -> io::Result<Self> {
+        let leaf = match buf[4] {
+            KIND_LEAF => true,
+            KIND_INTERNAL => false,
+            KIND_FREE => {
+                return Err(invalid(format!("page {page} is free")));
+            }
+            kind => {
+                return Err(invalid(format!(
+                    "page {page} has unknown kind {kind}"
+                )));
+            }
+        };
+        let len = read_u32(buf, 0) as usize;
+        if len > 2 * t - 1 {
+            return Err(invalid(format!("page {page} overflows")));
+        }
+        if len == 0 && !(leaf && root) {
+            return Err(invalid(format!("page {page} has no keys")));
+        }
+        let keys = (0..len)
+            .map(|i| read_u32(buf, PAGE_HEADER_SIZE + i * 4) as i32)
+            .collect();
+        let base = PAGE_HEADER_SIZE + (2 * t - 1) * 4;
+        let children = if leaf {
+            Vec::new()
+        } else {
+            (0..=len).map(|i| read_u32(buf, base + i * 4)).collect()
+        };
+        Ok(DiskNode {
+            keys,
+            children,
+            leaf,
+        })
+    }
+}
+
+/// 空きページ (次の空きページnextを指す)
+pub fn encode_free(t: usize, next: u32) -> Vec<u8> {
+    let mut buf = vec![0; page_size(t)];
+    buf[4] = KIND_FREE;
+    write_u32(&mut buf, PAGE_HEADER_SIZE, next);
+    buf
+}
+
+/// 空きページなら次の空きページ
+pub fn decode_free(buf: &[u8]) -> Option<u32> {
+    (buf[4] == KIND_FREE).then(|| read_u32(buf, PAGE_HEADER_SIZE))
+}
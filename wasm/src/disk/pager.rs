@@ -0,0 +1,157 @@
+use crate::disk::page::{
+    DiskNode, FILE_HEADER_SIZE, FileHeader, decode_free, encode_free,
+};
+use crate::paged::page_size;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// ページ単位でファイルを読み書きする
+pub struct Pager {
+    file: File,
+    pub header: FileHeader,
+}
+
+impl Pager {
+    /// 空の木のファイルを作る (既存のファイルは上書き)
+    pub fn create(path: &Path, t: usize) -> io::Result<Self> {
+        if t < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "min degree must be at least 2",
+            ));
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        let mut pager = Pager {
+            file,
+            header: FileHeader {
+                t,
+                root: 0,
+                free_head: 0,
+                page_count: 1,
+            },
+        };
+        pager.write_header()?;
+        Ok(pager)
+    }
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file =
+            OpenOptions::new().read(true).write(true).open(path)?;
+        let mut buf = [0; FILE_HEADER_SIZE];
+        file.read_exact(&mut buf)?;
+        let header = FileHeader::decode(&buf)?;
+        let expected =
+            header.page_count as u64 * page_size(header.t) as u64;
+        if file.metadata()?.len() < expected {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file is shorter than its page count",
+            ));
+        }
+        Ok(Pager { file, header })
+    }
+
+    pub fn page_size(&self) -> usize {
+        page_size(self.header.t)
+    }
+
+    fn read_page(&mut self, page: u32) -> io::Result<Vec<u8>> {
+        if page == 0 || page >= self.header.page_count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("page {page} is out of range"),
+            ));
+        }
+        let mut buf = vec![0; self.page_size()];
+        self.file.seek(SeekFrom::Start(
+            page as u64 * self.page_size() as u64,
+        ))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn write_page(&mut self, page: u32, buf: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(
+            page as u64 * self.page_size() as u64,
+        ))?;
+        self.file.write_all(buf)
+    }
+
+    pub fn write_header(&mut self) -> io::Result<()> {
+        let buf = self.header.encode();
+        self.write_page(0, &buf)
+    }
+
+    pub fn read_node(&mut self, page: u32) -> io::Result<DiskNode> {
+        let buf = self.read_page(page)?;
+        let root = page == self.header.root;
+        DiskNode::decode(&buf, self.header.t, page, root)
+    }
+
+    pub fn write_node(
+        &mut self,
+        page: u32,
+        node: &DiskNode,
+    ) -> io::Result<()> {
+        let buf = node.encode(self.header.t);
+        self.write_page(page, &buf)
+    }
+
+    /// ページを1つ確保する (空きリストにあればそれを使う)
+    pub fn allocate(&mut self) -> io::Result<u32> {
+        let page = self.header.free_head;
+        if page == 0 {
+            self.header.page_count += 1;
+            return Ok(self.header.page_count - 1);
+        }
+        let buf = self.read_page(page)?;
+        self.header.free_head = decode_free(&buf).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("page {page} in the free list is in use"),
+            )
+        })?;
+        Ok(page)
+    }
+
+    /// ページを空きリストの先頭に戻す
+    pub fn free(&mut self, page: u32) -> io::Result<()> {
+        let buf = encode_free(self.header.t, self.header.free_head);
+        self.write_page(page, &buf)?;
+        self.header.free_head = page;
+        Ok(())
+    }
+
+    /// 空きリストの長さ
+    pub fn free_page_count(&mut self) -> io::Result<usize> {
+        let mut count = 0;
+        let mut page = self.header.free_head;
+        while page != 0 {
+            count += 1;
+            if count >= self.header.page_count as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "free list has a cycle",
+                ));
+            }
+            page =
+                decode_free(&self.read_page(page)?).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("page {page} in the free list is in use"),
+                    )
+                })?;
+        }
+        Ok(count)
+    }
+
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+}
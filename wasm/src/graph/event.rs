@@ -0,0 +1,157 @@
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// グラフアルゴリズムの実行中に発生したイベント
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphEvent {
+    /// 頂点vertexを訪問 (キューやスタック、ヒープから取り出した)
+    Visit { vertex: usize },
+
+    /// 辺 from → to を調べた
+    ///
+    /// distanceはfromを経由したときのtoまでの距離、
+    /// improvedはそれでtoの距離(または親)が更新されたか
+    Relax {
+        from: usize,
+        to: usize,
+        weight: i32,
+        distance: i64,
+        improved: bool,
+    },
+
+    /// 頂点vertexの処理が終わり、距離や順序が確定した
+    Finalize { vertex: usize },
+}
+
+impl GraphEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        match *self {
+            GraphEvent::Visit { vertex } => {
+                set("type", "visit".into());
+                set("vertex", vertex.into());
+            }
+            GraphEvent::Relax {
+                from,
+                to,
+                weight,
+                distance,
+                improved,
+            } => {
+                set("type", "relax".into());
+                set("from", from.into());
+                set("to", to.into());
+                set("weight", weight.into());
+                set("distance", (distance as f64).into());
+                set("improved", improved.into());
+            }
+            GraphEvent::Finalize { vertex } => {
+                set("type", "finalize".into());
+                set("vertex", vertex.into());
+            }
+        }
+
+        obj.into()
+    }
+}
+
+/// グラフアルゴリズムの実行結果
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct GraphRun {
+    /// 発生したイベント
+    pub(crate) events: Vec<GraphEvent>,
+
+    /// 始点からの距離 (到達できない場合はNone)
+    pub(crate) distances: Vec<Option<i64>>,
+
+    /// 探索木・最短路木での親
+    pub(crate) parents: Vec<Option<usize>>,
+
+    /// 訪問順 (トポロジカルソートではソート結果)
+    pub(crate) order: Vec<usize>,
+
+    /// 各頂点の連結成分の番号 (連結成分分解のみ)
+    pub(crate) components: Vec<usize>,
+
+    /// 閉路 (Bellman-Fordでは負閉路) が見つかったか
+    pub(crate) cycle: bool,
+}
+
+fn optional_array<T: Copy + Into<JsValue>>(values: &[Option<T>]) -> Array {
+    Array::from_iter(
+        values.iter().map(|v| v.map_or(JsValue::NULL, Into::into)),
+    )
+}
+
+#[wasm_bindgen]
+impl GraphRun {
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(GraphEvent::to_js_value))
+    }
+
+    /// 距離の配列 (到達できない頂点はnull)
+    #[wasm_bindgen]
+    pub fn get_distances(&self) -> Array {
+        let distances: Vec<Option<f64>> =
+            self.distances.iter().map(|d| d.map(|d| d as f64)).collect();
+        optional_array(&distances)
+    }
+
+    /// 親の配列 (根や到達できない頂点はnull)
+    #[wasm_bindgen]
+    pub fn get_parents(&self) -> Array {
+        optional_array(&self.parents)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_order(&self) -> Vec<usize> {
+        self.order.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_components(&self) -> Vec<usize> {
+        self.components.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn has_cycle(&self) -> bool {
+        self.cycle
+    }
+}
+
+impl GraphRun {
+    /// n頂点ぶんの空の結果
+    pub(crate) fn new(n: usize) -> Self {
+        GraphRun {
+            distances: vec![None; n],
+            parents: vec![None; n],
+            ..Default::default()
+        }
+    }
+
+    pub fn events(&self) -> &[GraphEvent] {
+        &self.events
+    }
+
+    pub fn distances(&self) -> &[Option<i64>] {
+        &self.distances
+    }
+
+    pub fn parents(&self) -> &[Option<usize>] {
+        &self.parents
+    }
+
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    pub fn components(&self) -> &[usize] {
+        &self.components
+    }
+}
@@ -0,0 +1,12 @@
+mod event;
+mod mst;
+mod operation;
+mod parse;
+mod shortest_path;
+mod traversal;
+mod union_find;
+
+pub use event::{GraphEvent, GraphRun};
+pub use mst::{MstEvent, MstRun};
+pub use operation::{Edge, Graph};
+pub use union_find::{UnionFind, UnionFindEvent};
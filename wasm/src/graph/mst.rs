@@ -0,0 +1,208 @@
+use crate::graph::operation::{Edge, Graph};
+use crate::graph::union_find::{UnionFind, UnionFindEvent};
+use crate::heap::{DaryHeap, HeapOrder};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// 最小全域木アルゴリズムの実行中に発生したイベント
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MstEvent {
+    /// 辺を調べ始めた (Kruskalでは重み順、Primではヒープから取り出した辺)
+    Consider(Edge),
+
+    /// 辺を全域木に加えた
+    Accept(Edge),
+
+    /// 辺が閉路を作るので捨てた
+    Reject(Edge),
+
+    /// 頂点vertexを木に加えた (Primのみ)
+    Visit { vertex: usize },
+
+    /// Union-Findの操作 (Kruskalのみ)
+    UnionFind(UnionFindEvent),
+
+    /// その時点のヒープの配列 (Primのみ)
+    Heap(Vec<Edge>),
+}
+
+impl MstEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let with_type = |kind: &str, value: JsValue| {
+            let _ =
+                js_sys::Reflect::set(&value, &"type".into(), &kind.into());
+            value
+        };
+
+        match self {
+            MstEvent::Consider(edge) => {
+                with_type("consider", edge.to_js_value())
+            }
+            MstEvent::Accept(edge) => {
+                with_type("accept", edge.to_js_value())
+            }
+            MstEvent::Reject(edge) => {
+                with_type("reject", edge.to_js_value())
+            }
+            MstEvent::Visit { vertex } => {
+                let obj = Object::new();
+                let _ = js_sys::Reflect::set(
+                    &obj,
+                    &"vertex".into(),
+                    &(*vertex).into(),
+                );
+                with_type("visit", obj.into())
+            }
+            MstEvent::UnionFind(event) => event.to_js_value(),
+            MstEvent::Heap(entries) => {
+                let obj = Object::new();
+                let _ = js_sys::Reflect::set(
+                    &obj,
+                    &"entries".into(),
+                    &Array::from_iter(
+                        entries.iter().map(Edge::to_js_value),
+                    )
+                    .into(),
+                );
+                with_type("heap", obj.into())
+            }
+        }
+    }
+}
+
+/// 最小全域木 (非連結なら全域森) の計算結果
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct MstRun {
+    events: Vec<MstEvent>,
+
+    // 採用した辺 (採用した順)
+    edges: Vec<Edge>,
+}
+
+#[wasm_bindgen]
+impl MstRun {
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(MstEvent::to_js_value))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_edges(&self) -> Array {
+        Array::from_iter(self.edges.iter().map(Edge::to_js_value))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_total_weight(&self) -> f64 {
+        self.total_weight() as f64
+    }
+}
+
+impl MstRun {
+    pub fn events(&self) -> &[MstEvent] {
+        &self.events
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn total_weight(&self) -> i64 {
+        self.edges.iter().map(|e| e.weight as i64).sum()
+    }
+
+    fn accept(&mut self, edge: Edge) {
+        self.events.push(MstEvent::Accept(edge));
+        self.edges.push(edge);
+    }
+}
+
+fn require_undirected(graph: &Graph) -> Result<(), String> {
+    if graph.is_directed() {
+        Err("minimum spanning trees require an undirected graph".into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Kruskal法
+///
+/// 辺を重みの軽い順 (同じ重みなら追加順) に見て、
+/// 別々の木を結ぶ辺だけを採用する
+pub fn kruskal(graph: &Graph) -> Result<MstRun, String> {
+    require_undirected(graph)?;
+
+    let n = graph.vertex_count();
+    let mut run = MstRun::default();
+    let mut sets = UnionFind::new(n);
+    let mut edges = graph.edges().to_vec();
+    edges.sort_by_key(|e| e.weight);
+
+    for edge in edges {
+        if run.edges.len() + 1 >= n {
+            break;
+        }
+        run.events.push(MstEvent::Consider(edge));
+        let joined = sets.union(edge.from, edge.to);
+        run.events.extend(
+            sets.take_events().into_iter().map(MstEvent::UnionFind),
+        );
+        if joined {
+            run.accept(edge);
+        } else {
+            run.events.push(MstEvent::Reject(edge));
+        }
+    }
+    Ok(run)
+}
+
+/// Prim法 (rootを含む連結成分の最小全域木)
+///
+/// ヒープには (重み, 行き先, 元) を入れ、ヒープが変わるたびに中身を記録する
+pub fn prim(graph: &Graph, root: usize) -> Result<MstRun, String> {
+    require_undirected(graph)?;
+
+    let mut run = MstRun::default();
+    let mut in_tree = vec![false; graph.vertex_count()];
+    let mut heap = DaryHeap::new(2, HeapOrder::Min)?;
+    grow(graph, root, &mut in_tree, &mut heap, &mut run);
+
+    while let Some((weight, to, from)) = heap.pop() {
+        let edge = Edge { from, to, weight };
+        run.events.push(MstEvent::Consider(edge));
+        if in_tree[to] {
+            // 両端が既に木に含まれている
+            run.events.push(MstEvent::Reject(edge));
+            run.events.push(MstEvent::Heap(snapshot(&heap)));
+        } else {
+            run.accept(edge);
+            grow(graph, to, &mut in_tree, &mut heap, &mut run);
+        }
+    }
+    Ok(run)
+}
+
+/// 頂点vを木に加え、木の外へ出る辺をヒープに積む
+fn grow(
+    graph: &Graph,
+    v: usize,
+    in_tree: &mut [bool],
+    heap: &mut DaryHeap<(i32, usize, usize)>,
+    run: &mut MstRun,
+) {
+    in_tree[v] = true;
+    run.events.push(MstEvent::Visit { vertex: v });
+    for &(to, weight) in graph.neighbors(v) {
+        if !in_tree[to] {
+            heap.push((weight, to, v));
+        }
+    }
+    run.events.push(MstEvent::Heap(snapshot(heap)));
+}
+
+fn snapshot(heap: &DaryHeap<(i32, usize, usize)>) -> Vec<Edge> {
+    heap.as_slice()
+        .iter()
+        .map(|&(weight, to, from)| Edge { from, to, weight })
+        .collect()
+}
@@ -0,0 +1,523 @@
+use crate::graph::event::GraphRun;
+use crate::graph::mst::MstRun;
+use crate::graph::{mst, parse, shortest_path, traversal};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// 重み付きの辺
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub weight: i32,
+}
+
+impl Edge {
+    /// {from, to, weight} のオブジェクトに変換
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+        set("from", self.from.into());
+        set("to", self.to.into());
+        set("weight", self.weight.into());
+        obj.into()
+    }
+}
+
+// Graph (有向・無向、重み付き)
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Graph {
+    // 辺 (入力された順)
+    edges: Vec<Edge>,
+
+    // 隣接リスト (隣接頂点, 重み)。無向グラフでは両方向に持つ
+    adjacency: Vec<Vec<(usize, i32)>>,
+
+    // 有向グラフかどうか
+    directed: bool,
+}
+
+#[wasm_bindgen]
+impl Graph {
+    /// 頂点0..verticesで辺のないグラフを作成
+    #[wasm_bindgen(constructor)]
+    pub fn new(vertices: usize, directed: bool) -> Result<Graph, String> {
+        parse::check_vertex_count(vertices)?;
+        Ok(Graph {
+            edges: Vec::new(),
+            adjacency: vec![Vec::new(); vertices],
+            directed,
+        })
+    }
+
+    /// 辺の一覧 [from, to, weight, from, to, weight, ...] からグラフを作成
+    pub fn from_edge_list(
+        vertices: usize,
+        directed: bool,
+        edges: &[i32],
+    ) -> Result<Graph, String> {
+        if edges.len() % 3 != 0 {
+            return Err(
+                "edge list must consist of (from, to, weight)".into()
+            );
+        }
+        let mut graph = Graph::new(vertices, directed)?;
+        for triple in edges.chunks(3) {
+            let vertex = |v: i32| {
+                usize::try_from(v)
+                    .map_err(|_| format!("invalid vertex {v}"))
+            };
+            graph.add_edge(
+                vertex(triple[0])?,
+                vertex(triple[1])?,
+                triple[2],
+            )?;
+        }
+        Ok(graph)
+    }
+
+    /// JSON形式のテキストからグラフを作成
+    pub fn from_json(text: &str) -> Result<Graph, String> {
+        Self::from_parsed(parse::from_json(text)?)
+    }
+
+    /// DIMACS形式のテキストからグラフを作成
+    pub fn from_dimacs(text: &str) -> Result<Graph, String> {
+        Self::from_parsed(parse::from_dimacs(text)?)
+    }
+
+    /// 辺 from → to を追加 (無向グラフでは両方向)
+    pub fn add_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        weight: i32,
+    ) -> Result<(), String> {
+        let n = self.vertex_count();
+        if from >= n || to >= n {
+            return Err(format!(
+                "edge {from} -> {to} is out of range for {n} vertices"
+            ));
+        }
+        self.edges.push(Edge { from, to, weight });
+        self.adjacency[from].push((to, weight));
+        if !self.directed && from != to {
+            self.adjacency[to].push((from, weight));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_vertex_count(&self) -> usize {
+        self.vertex_count()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// 辺の一覧 ({from, to, weight} の配列)
+    #[wasm_bindgen]
+    pub fn get_edges(&self) -> Array {
+        Array::from_iter(self.edges.iter().map(Edge::to_js_value))
+    }
+
+    /// 幅優先探索
+    pub fn bfs(&self, source: usize) -> Result<GraphRun, String> {
+        self.check_vertex(source)?;
+        Ok(traversal::bfs(self, source))
+    }
+
+    /// 深さ優先探索
+    pub fn dfs(&self, source: usize) -> Result<GraphRun, String> {
+        self.check_vertex(source)?;
+        Ok(traversal::dfs(self, source))
+    }
+
+    /// Dijkstra法による単一始点最短路
+    pub fn dijkstra(&self, source: usize) -> Result<GraphRun, String> {
+        self.check_vertex(source)?;
+        shortest_path::dijkstra(self, source)
+    }
+
+    /// Bellman-Ford法による単一始点最短路
+    pub fn bellman_ford(&self, source: usize) -> Result<GraphRun, String> {
+        self.check_vertex(source)?;
+        Ok(shortest_path::bellman_ford(self, source))
+    }
+
+    /// トポロジカルソート (有向グラフのみ)
+    pub fn topological_sort(&self) -> Result<GraphRun, String> {
+        traversal::topological_sort(self)
+    }
+
+    /// 連結成分分解
+    pub fn connected_components(&self) -> GraphRun {
+        traversal::connected_components(self)
+    }
+
+    /// Kruskal法による最小全域木 (無向グラフのみ)
+    pub fn kruskal(&self) -> Result<MstRun, String> {
+        mst::kruskal(self)
+    }
+
+    /// Prim法による最小全域木 (無向グラフのみ)
+    pub fn prim(&self, root: usize) -> Result<MstRun, String> {
+        self.check_vertex(root)?;
+        mst::prim(self, root)
+    }
+}
+
+impl Graph {
+    fn from_parsed(
+        (vertices, directed, edges): parse::ParsedGraph,
+    ) -> Result<Graph, String> {
+        let mut graph = Graph::new(vertices, directed)?;
+        for e in edges {
+            graph.add_edge(e.from, e.to, e.weight)?;
+        }
+        Ok(graph)
+    }
+
+    fn check_vertex(&self, v: usize) -> Result<(), String> {
+        if v < self.vertex_count() {
+            Ok(())
+        } else {
+            Err(format!("vertex {v} does not exist"))
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// 頂点vから出る辺 (隣接頂点, 重み)
+    pub fn neighbors(&self, v: usize) -> &[(usize, i32)] {
+        &self.adjacency[v]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::event::GraphEvent;
+    use crate::graph::mst::MstEvent;
+
+    /// CLRSの Dijkstra 法の例 (s, t, x, y, z = 0..5)
+    fn clrs_graph() -> Graph {
+        Graph::from_edge_list(
+            5,
+            true,
+            &[
+                0, 1, 10, 0, 3, 5, 1, 2, 1, 1, 3, 2, 2, 4, 4, 3, 1, 3, 3,
+                2, 9, 3, 4, 2, 4, 0, 7, 4, 2, 6,
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_bfs_distances_and_parents() {
+        let mut g = Graph::new(6, false).unwrap();
+        for (u, v) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)] {
+            g.add_edge(u, v, 1).unwrap();
+        }
+
+        let run = g.bfs(0).unwrap();
+        assert_eq!(
+            run.distances(),
+            &[Some(0), Some(1), Some(1), Some(2), Some(3), None]
+        );
+        assert_eq!(run.parents()[3], Some(1));
+        assert_eq!(run.order(), &[0, 1, 2, 3, 4]);
+        assert!(run.events().iter().any(|e| matches!(
+            e,
+            GraphEvent::Relax {
+                from: 2,
+                to: 3,
+                improved: false,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_dfs_order_and_finalize() {
+        let mut g = Graph::new(4, true).unwrap();
+        for (u, v) in [(0, 1), (1, 2), (0, 3)] {
+            g.add_edge(u, v, 1).unwrap();
+        }
+
+        let run = g.dfs(0).unwrap();
+        assert_eq!(run.order(), &[0, 1, 2, 3]);
+        let finalized: Vec<usize> = run
+            .events()
+            .iter()
+            .filter_map(|e| match e {
+                GraphEvent::Finalize { vertex } => Some(*vertex),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(finalized, vec![2, 1, 3, 0]);
+    }
+
+    #[test]
+    fn test_dijkstra_clrs_example() {
+        let run = clrs_graph().dijkstra(0).unwrap();
+        assert_eq!(
+            run.distances(),
+            &[Some(0), Some(8), Some(9), Some(5), Some(7)]
+        );
+        assert_eq!(
+            run.parents(),
+            &[None, Some(3), Some(1), Some(0), Some(3)]
+        );
+        // 確定する順は距離の小さい順
+        assert_eq!(run.order(), &[0, 3, 4, 1, 2]);
+    }
+
+    #[test]
+    fn test_dijkstra_rejects_negative_weights() {
+        let g = Graph::from_edge_list(2, true, &[0, 1, -1]).unwrap();
+        assert!(g.dijkstra(0).is_err());
+        assert!(g.dijkstra(5).is_err());
+    }
+
+    #[test]
+    fn test_bellman_ford_matches_dijkstra_and_detects_cycles() {
+        let g = clrs_graph();
+        let bf = g.bellman_ford(0).unwrap();
+        assert_eq!(bf.distances(), g.dijkstra(0).unwrap().distances());
+        assert!(!bf.has_cycle());
+
+        let mut negative = Graph::new(3, true).unwrap();
+        negative.add_edge(0, 1, 4).unwrap();
+        negative.add_edge(1, 2, -3).unwrap();
+        let run = negative.bellman_ford(0).unwrap();
+        assert_eq!(run.distances(), &[Some(0), Some(4), Some(1)]);
+        assert!(!run.has_cycle());
+
+        negative.add_edge(2, 1, 1).unwrap();
+        assert!(negative.bellman_ford(0).unwrap().has_cycle());
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        // 服を着る順番 (CLRSの例を簡略化)
+        let mut g = Graph::new(6, true).unwrap();
+        for (u, v) in [(0, 1), (0, 2), (1, 3), (2, 3), (4, 5), (5, 3)] {
+            g.add_edge(u, v, 1).unwrap();
+        }
+
+        let run = g.topological_sort().unwrap();
+        let order = run.order();
+        assert_eq!(order.len(), 6);
+        let position = |v: usize| order.iter().position(|&x| x == v);
+        for e in g.edges() {
+            assert!(position(e.from) < position(e.to), "{e:?}");
+        }
+        assert!(!run.has_cycle());
+
+        g.add_edge(3, 0, 1).unwrap();
+        let run = g.topological_sort().unwrap();
+        assert!(run.has_cycle());
+        assert!(run.order().is_empty());
+
+        assert!(Graph::new(2, false).unwrap().topological_sort().is_err());
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let mut g = Graph::new(6, true).unwrap();
+        for (u, v) in [(0, 1), (2, 1), (3, 4)] {
+            g.add_edge(u, v, 1).unwrap();
+        }
+
+        let run = g.connected_components();
+        assert_eq!(run.components(), &[0, 0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let g = Graph::from_json(
+            r#"{"directed": true, "edges": [[0, 1, 5], [1, 2],
+                {"from": 2, "to": 3, "weight": -1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(g.vertex_count(), 4);
+        assert!(g.is_directed());
+        assert_eq!(
+            g.edges()[1],
+            Edge {
+                from: 1,
+                to: 2,
+                weight: 1
+            }
+        );
+        assert_eq!(g.edges()[2].weight, -1);
+
+        assert!(Graph::from_json(r#"{"edges": [[0]]}"#).is_err());
+        assert!(
+            Graph::from_json(r#"{"vertices": 1, "edges": [[0, 1]]}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_dimacs() {
+        let text = "c sample\np sp 3 2\na 1 2 7\na 2 3 1\n";
+        let g = Graph::from_dimacs(text).unwrap();
+        assert!(g.is_directed());
+        assert_eq!(
+            g.edges()[0],
+            Edge {
+                from: 0,
+                to: 1,
+                weight: 7
+            }
+        );
+        assert_eq!(g.dijkstra(0).unwrap().distances()[2], Some(8));
+
+        let g = Graph::from_dimacs("p edge 2 1\ne 1 2\n").unwrap();
+        assert!(!g.is_directed());
+        assert_eq!(g.neighbors(1), &[(0, 1)]);
+
+        assert!(Graph::from_dimacs("a 1 2 3\n").is_err());
+        assert!(Graph::from_dimacs("p sp 2 1\na 1 3 1\n").is_err());
+        assert!(Graph::from_dimacs("p sp 2 1\nx\n").is_err());
+    }
+
+    #[test]
+    fn test_rejects_huge_vertex_counts() {
+        // 隣接リストを確保する前に断る
+        assert!(
+            Graph::from_json(r#"{"vertices": 4000000000, "edges": []}"#)
+                .is_err()
+        );
+        assert!(
+            Graph::from_json(r#"{"edges": [[0, 4000000000]]}"#).is_err()
+        );
+        assert!(Graph::from_dimacs("p sp 4000000000 0\n").is_err());
+        assert!(Graph::from_edge_list(usize::MAX, true, &[]).is_err());
+        assert!(Graph::new(1 << 40, false).is_err());
+        assert!(Graph::from_json(&"[".repeat(1_000_000)).is_err());
+
+        let n = parse::MAX_VERTICES;
+        let text = format!(r#"{{"edges": [[0, {}]]}}"#, n - 1);
+        assert_eq!(Graph::from_json(&text).unwrap().vertex_count(), n);
+    }
+
+    /// CLRSの最小全域木の例 (a..i = 0..9)。最小全域木の重みは37
+    fn clrs_mst_graph() -> Graph {
+        Graph::from_edge_list(
+            9,
+            false,
+            &[
+                0, 1, 4, 0, 7, 8, 1, 2, 8, 1, 7, 11, 2, 3, 7, 2, 5, 4, 2,
+                8, 2, 3, 4, 9, 3, 5, 14, 4, 5, 10, 5, 6, 2, 6, 7, 1, 6, 8,
+                6, 7, 8, 7,
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_kruskal_clrs_example() {
+        let run = clrs_mst_graph().kruskal().unwrap();
+        assert_eq!(run.total_weight(), 37);
+        assert_eq!(run.edges().len(), 8);
+        // 最も軽い辺 (g, h) から採用される
+        assert_eq!(
+            run.edges()[0],
+            Edge {
+                from: 6,
+                to: 7,
+                weight: 1
+            }
+        );
+
+        // (g, i) は c-i, c-f, f-g を経由して既につながっている
+        let rejected: Vec<(usize, usize)> = run
+            .events()
+            .iter()
+            .filter_map(|e| match e {
+                MstEvent::Reject(edge) => Some((edge.from, edge.to)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rejected[0], (6, 8));
+        assert!(
+            run.events()
+                .iter()
+                .any(|e| matches!(e, MstEvent::UnionFind(_)))
+        );
+    }
+
+    #[test]
+    fn test_prim_clrs_example() {
+        let g = clrs_mst_graph();
+        let run = g.prim(0).unwrap();
+        assert_eq!(run.total_weight(), 37);
+        assert_eq!(run.edges().len(), 8);
+        assert_eq!(
+            run.edges()[0],
+            Edge {
+                from: 0,
+                to: 1,
+                weight: 4
+            }
+        );
+
+        // 根を加えた直後のヒープには根から出る辺だけがある
+        assert_eq!(run.events()[0], MstEvent::Visit { vertex: 0 });
+        let MstEvent::Heap(entries) = &run.events()[1] else {
+            panic!("expected a heap snapshot");
+        };
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].weight, 4);
+
+        // 木を広げた後や辺を捨てた後は必ずヒープの中身が記録される
+        for pair in run.events().windows(2) {
+            if matches!(pair[0], MstEvent::Reject(_)) {
+                assert!(matches!(pair[1], MstEvent::Heap(_)));
+            }
+        }
+        assert_eq!(
+            g.kruskal().unwrap().total_weight(),
+            run.total_weight()
+        );
+    }
+
+    #[test]
+    fn test_mst_forest_and_errors() {
+        let mut g = Graph::new(4, false).unwrap();
+        g.add_edge(0, 1, 3).unwrap();
+        g.add_edge(2, 3, 5).unwrap();
+        g.add_edge(1, 1, 1).unwrap();
+
+        // 非連結なら Kruskal は全域森、Prim は根の連結成分だけ
+        assert_eq!(g.kruskal().unwrap().total_weight(), 8);
+        assert_eq!(g.prim(2).unwrap().total_weight(), 5);
+
+        assert!(g.prim(4).is_err());
+        assert!(Graph::new(2, true).unwrap().kruskal().is_err());
+        assert!(Graph::new(2, true).unwrap().prim(0).is_err());
+    }
+}
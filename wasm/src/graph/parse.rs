@@ -0,0 +1,169 @@
+use crate::graph::operation::Edge;
+use crate::json::{self, JsonValue};
+
+/// パースしたグラフ (頂点数, 有向か, 辺)
+pub type ParsedGraph = (usize, bool, Vec<Edge>);
+
+/// 読み込める頂点数の上限 (隣接リストを頂点数だけ確保するため)
+pub const MAX_VERTICES: usize = 100_000;
+
+/// 頂点数が上限以下か
+pub fn check_vertex_count(n: usize) -> Result<usize, String> {
+    if n > MAX_VERTICES {
+        return Err(format!(
+            "too many vertices: {n} (at most {MAX_VERTICES})"
+        ));
+    }
+    Ok(n)
+}
+
+/// JSON形式のグラフを読む
+///
+/// ```text
+/// {"directed": true, "vertices": 4,
+///  "edges": [[0, 1, 5], [1, 2], {"from": 2, "to": 3, "weight": 1}]}
+/// ```
+///
+/// 重みを省略した辺は重み1、verticesを省略した場合は辺の端点から決める
+pub fn from_json(text: &str) -> Result<ParsedGraph, String> {
+    let root = json::parse(text)?;
+    let directed = match root.get("directed") {
+        None => false,
+        Some(v) => v.as_bool().ok_or("\"directed\" must be a boolean")?,
+    };
+
+    let items = root
+        .get("edges")
+        .and_then(JsonValue::as_array)
+        .ok_or("\"edges\" must be an array")?;
+    let edges = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            json_edge(item).ok_or(format!("invalid edge #{i}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let vertices = match root.get("vertices") {
+        Some(v) => v
+            .as_i64()
+            .filter(|&n| n >= 0)
+            .ok_or("\"vertices\" must be a non-negative integer")?
+            as usize,
+        None => edges
+            .iter()
+            .map(|e| e.from.max(e.to) + 1)
+            .max()
+            .unwrap_or(0),
+    };
+    Ok((check_vertex_count(vertices)?, directed, edges))
+}
+
+fn json_edge(item: &JsonValue) -> Option<Edge> {
+    let (from, to, weight) = match item {
+        JsonValue::Array(parts)
+            if parts.len() == 2 || parts.len() == 3 =>
+        {
+            (&parts[0], &parts[1], parts.get(2))
+        }
+        JsonValue::Object(_) => {
+            (item.get("from")?, item.get("to")?, item.get("weight"))
+        }
+        _ => return None,
+    };
+    let vertex = |v: &JsonValue| v.as_i64().filter(|&v| v >= 0);
+    let weight = match weight {
+        Some(w) => i32::try_from(w.as_i64()?).ok()?,
+        None => 1,
+    };
+    Some(Edge {
+        from: vertex(from)? as usize,
+        to: vertex(to)? as usize,
+        weight,
+    })
+}
+
+/// DIMACS形式のグラフを読む (頂点番号は1始まり)
+///
+/// ```text
+/// c コメント
+/// p sp 4 3      (sp は有向グラフ、edge は無向グラフ)
+/// a 1 2 5       (有向辺 1 → 2, 重み5)
+/// e 2 3         (無向辺 2 - 3, 重みは省略すると1)
+/// ```
+pub fn from_dimacs(text: &str) -> Result<ParsedGraph, String> {
+    let mut header: Option<(usize, bool)> = None;
+    let mut edges = Vec::new();
+
+    for (line_no, line) in text.lines().enumerate() {
+        let line_no = line_no + 1;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some((&kind, args)) = fields.split_first() else {
+            continue;
+        };
+        let number = |s: &str| {
+            s.parse::<i64>().map_err(|_| {
+                format!("line {line_no}: invalid number '{s}'")
+            })
+        };
+
+        match kind {
+            "c" => {}
+            "p" => {
+                if args.len() < 2 {
+                    return Err(format!(
+                        "line {line_no}: malformed header"
+                    ));
+                }
+                let directed = args[0] != "edge" && args[0] != "col";
+                let n = number(args[1])?;
+                if n < 0 {
+                    return Err(format!("line {line_no}: negative size"));
+                }
+                let n = check_vertex_count(n as usize)
+                    .map_err(|e| format!("line {line_no}: {e}"))?;
+                header = Some((n, directed));
+            }
+            "a" | "e" => {
+                let (n, _) = header.ok_or(format!(
+                    "line {line_no}: edge before the 'p' header"
+                ))?;
+                if args.len() < 2 {
+                    return Err(format!("line {line_no}: malformed edge"));
+                }
+                let vertex = |s: &str| -> Result<usize, String> {
+                    let v = number(s)?;
+                    if v < 1 || v as usize > n {
+                        return Err(format!(
+                            "line {line_no}: vertex {v} is out of range"
+                        ));
+                    }
+                    Ok(v as usize - 1)
+                };
+                let weight = match args.get(2) {
+                    Some(w) => {
+                        i32::try_from(number(w)?).map_err(|_| {
+                            format!(
+                                "line {line_no}: weight is out of range"
+                            )
+                        })?
+                    }
+                    None => 1,
+                };
+                edges.push(Edge {
+                    from: vertex(args[0])?,
+                    to: vertex(args[1])?,
+                    weight,
+                });
+            }
+            _ => {
+                return Err(format!(
+                    "line {line_no}: unknown line type '{kind}'"
+                ));
+            }
+        }
+    }
+
+    let (n, directed) = header.ok_or("missing 'p' header")?;
+    Ok((n, directed, edges))
+}
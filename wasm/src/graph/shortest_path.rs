@@ -0,0 +1,110 @@
+use crate::graph::event::{GraphEvent, GraphRun};
+use crate::graph::operation::Graph;
+use crate::heap::{DaryHeap, HeapOrder};
+
+/// Dijkstra法 (重みは非負であること)
+pub fn dijkstra(graph: &Graph, source: usize) -> Result<GraphRun, String> {
+    if graph.edges().iter().any(|e| e.weight < 0) {
+        return Err(
+            "Dijkstra's algorithm requires non-negative weights".into()
+        );
+    }
+
+    let n = graph.vertex_count();
+    let mut run = GraphRun::new(n);
+    let mut done = vec![false; n];
+    let mut heap = DaryHeap::new(2, HeapOrder::Min)?;
+
+    run.distances[source] = Some(0);
+    heap.push((0i64, source));
+
+    while let Some((d, v)) = heap.pop() {
+        // 既に確定した頂点の古いエントリは読み飛ばす
+        if done[v] {
+            continue;
+        }
+        done[v] = true;
+        run.events.push(GraphEvent::Visit { vertex: v });
+        run.order.push(v);
+
+        for &(to, weight) in graph.neighbors(v) {
+            let distance = d + weight as i64;
+            let improved = !done[to]
+                && run.distances[to].is_none_or(|old| distance < old);
+            run.events.push(GraphEvent::Relax {
+                from: v,
+                to,
+                weight,
+                distance,
+                improved,
+            });
+            if improved {
+                run.distances[to] = Some(distance);
+                run.parents[to] = Some(v);
+                heap.push((distance, to));
+            }
+        }
+
+        run.events.push(GraphEvent::Finalize { vertex: v });
+    }
+    Ok(run)
+}
+
+/// Bellman-Ford法 (負の重みも扱える)
+///
+/// 始点から到達できる負閉路があれば閉路ありとする
+pub fn bellman_ford(graph: &Graph, source: usize) -> GraphRun {
+    let n = graph.vertex_count();
+    let mut run = GraphRun::new(n);
+    run.distances[source] = Some(0);
+
+    // 全辺の緩和を最大 n-1 回繰り返す
+    for _ in 1..n.max(2) {
+        let mut changed = false;
+        for v in 0..n {
+            let Some(d) = run.distances[v] else {
+                continue;
+            };
+            run.events.push(GraphEvent::Visit { vertex: v });
+
+            for &(to, weight) in graph.neighbors(v) {
+                let distance = d + weight as i64;
+                let improved =
+                    run.distances[to].is_none_or(|old| distance < old);
+                run.events.push(GraphEvent::Relax {
+                    from: v,
+                    to,
+                    weight,
+                    distance,
+                    improved,
+                });
+                if improved {
+                    run.distances[to] = Some(distance);
+                    run.parents[to] = Some(v);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // まだ緩和できる辺があれば負閉路がある
+    run.cycle = (0..n).any(|v| {
+        run.distances[v].is_some_and(|d| {
+            graph.neighbors(v).iter().any(|&(to, weight)| {
+                run.distances[to]
+                    .is_none_or(|old| d + (weight as i64) < old)
+            })
+        })
+    });
+
+    for v in 0..n {
+        if run.distances[v].is_some() {
+            run.events.push(GraphEvent::Finalize { vertex: v });
+            run.order.push(v);
+        }
+    }
+    run
+}
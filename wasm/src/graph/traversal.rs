@@ -0,0 +1,186 @@
+use crate::graph::event::{GraphEvent, GraphRun};
+use crate::graph::operation::Graph;
+use std::collections::VecDeque;
+
+/// 幅優先探索 (距離は辺の本数)
+pub fn bfs(graph: &Graph, source: usize) -> GraphRun {
+    let mut run = GraphRun::new(graph.vertex_count());
+    run.distances[source] = Some(0);
+    let mut queue = VecDeque::from([source]);
+
+    while let Some(v) = queue.pop_front() {
+        run.events.push(GraphEvent::Visit { vertex: v });
+        run.order.push(v);
+        let distance = run.distances[v].unwrap() + 1;
+
+        for &(to, weight) in graph.neighbors(v) {
+            let improved = run.distances[to].is_none();
+            run.events.push(GraphEvent::Relax {
+                from: v,
+                to,
+                weight,
+                distance,
+                improved,
+            });
+            if improved {
+                run.distances[to] = Some(distance);
+                run.parents[to] = Some(v);
+                queue.push_back(to);
+            }
+        }
+
+        run.events.push(GraphEvent::Finalize { vertex: v });
+    }
+    run
+}
+
+/// 頂点の状態 (未訪問・探索中・完了)
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    White,
+    Gray,
+    Black,
+}
+
+/// sourceから深さ優先探索し、完了した順に頂点をfinishedへ追加する
+///
+/// 有向グラフで探索中の頂点へ戻る辺 (後退辺) があればrun.cycleを立てる
+fn dfs_visit(
+    graph: &Graph,
+    source: usize,
+    run: &mut GraphRun,
+    marks: &mut [Mark],
+    finished: &mut Vec<usize>,
+) {
+    marks[source] = Mark::Gray;
+    run.distances[source] = Some(0);
+    run.events.push(GraphEvent::Visit { vertex: source });
+    run.order.push(source);
+
+    // (頂点, 次に調べる隣接辺の位置)
+    let mut stack = vec![(source, 0)];
+    while let Some((v, i)) = stack.last_mut() {
+        let v = *v;
+        let Some(&(to, weight)) = graph.neighbors(v).get(*i) else {
+            marks[v] = Mark::Black;
+            run.events.push(GraphEvent::Finalize { vertex: v });
+            finished.push(v);
+            stack.pop();
+            continue;
+        };
+        *i += 1;
+
+        let distance = run.distances[v].unwrap() + 1;
+        let improved = marks[to] == Mark::White;
+        run.events.push(GraphEvent::Relax {
+            from: v,
+            to,
+            weight,
+            distance,
+            improved,
+        });
+
+        match marks[to] {
+            Mark::White => {
+                marks[to] = Mark::Gray;
+                run.distances[to] = Some(distance);
+                run.parents[to] = Some(v);
+                run.events.push(GraphEvent::Visit { vertex: to });
+                run.order.push(to);
+                stack.push((to, 0));
+            }
+            Mark::Gray if graph.is_directed() => run.cycle = true,
+            _ => {}
+        }
+    }
+}
+
+/// 深さ優先探索 (距離は探索木での深さ)
+pub fn dfs(graph: &Graph, source: usize) -> GraphRun {
+    let mut run = GraphRun::new(graph.vertex_count());
+    let mut marks = vec![Mark::White; graph.vertex_count()];
+    dfs_visit(graph, source, &mut run, &mut marks, &mut Vec::new());
+    // 閉路の検出はトポロジカルソートでのみ使う
+    run.cycle = false;
+    run
+}
+
+/// 深さ優先探索の完了順の逆順によるトポロジカルソート
+///
+/// 閉路がある場合は順序を空にし、閉路ありとする
+pub fn topological_sort(graph: &Graph) -> Result<GraphRun, String> {
+    if !graph.is_directed() {
+        return Err("topological sort requires a directed graph".into());
+    }
+
+    let n = graph.vertex_count();
+    let mut run = GraphRun::new(n);
+    let mut marks = vec![Mark::White; n];
+    let mut finished = Vec::with_capacity(n);
+    for v in 0..n {
+        if marks[v] == Mark::White {
+            dfs_visit(graph, v, &mut run, &mut marks, &mut finished);
+        }
+    }
+
+    finished.reverse();
+    run.order = if run.cycle { Vec::new() } else { finished };
+    Ok(run)
+}
+
+/// 連結成分分解 (有向グラフでは辺の向きを無視した弱連結成分)
+pub fn connected_components(graph: &Graph) -> GraphRun {
+    let n = graph.vertex_count();
+    let mut run = GraphRun::new(n);
+
+    // 辺の向きを無視するため、逆向きの隣接も用意する
+    let mut undirected: Vec<Vec<(usize, i32)>> =
+        (0..n).map(|v| graph.neighbors(v).to_vec()).collect();
+    if graph.is_directed() {
+        for v in 0..n {
+            for &(to, weight) in graph.neighbors(v) {
+                undirected[to].push((v, weight));
+            }
+        }
+    }
+
+    let mut component = vec![usize::MAX; n];
+    let mut next_id = 0;
+    for start in 0..n {
+        if component[start] != usize::MAX {
+            continue;
+        }
+        component[start] = next_id;
+        run.distances[start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(v) = queue.pop_front() {
+            run.events.push(GraphEvent::Visit { vertex: v });
+            run.order.push(v);
+            let distance = run.distances[v].unwrap() + 1;
+
+            for &(to, weight) in &undirected[v] {
+                let improved = component[to] == usize::MAX;
+                run.events.push(GraphEvent::Relax {
+                    from: v,
+                    to,
+                    weight,
+                    distance,
+                    improved,
+                });
+                if improved {
+                    component[to] = next_id;
+                    run.distances[to] = Some(distance);
+                    run.parents[to] = Some(v);
+                    queue.push_back(to);
+                }
+            }
+
+            run.events.push(GraphEvent::Finalize { vertex: v });
+        }
+        next_id += 1;
+    }
+
+    run.components = component;
+    run
+}
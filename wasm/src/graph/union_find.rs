@@ -0,0 +1,206 @@
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+/// Union-Findの操作中に発生したイベント
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnionFindEvent {
+    /// vertexの根を探した (pathはvertexから根までにたどった要素)
+    Find {
+        vertex: usize,
+        root: usize,
+        path: Vec<usize>,
+    },
+
+    /// 経路圧縮でvertexの親をfromからrootに付け替えた
+    Compress {
+        vertex: usize,
+        from: usize,
+        root: usize,
+    },
+
+    /// childの木をrootの下につないだ (rankは併合後のrootのランク)
+    Union {
+        root: usize,
+        child: usize,
+        rank: usize,
+    },
+}
+
+impl UnionFindEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        match self {
+            UnionFindEvent::Find { vertex, root, path } => {
+                set("type", "find".into());
+                set("vertex", (*vertex).into());
+                set("root", (*root).into());
+                set(
+                    "path",
+                    Array::from_iter(
+                        path.iter().map(|&v| JsValue::from(v)),
+                    )
+                    .into(),
+                );
+            }
+            UnionFindEvent::Compress { vertex, from, root } => {
+                set("type", "compress".into());
+                set("vertex", (*vertex).into());
+                set("from", (*from).into());
+                set("root", (*root).into());
+            }
+            UnionFindEvent::Union { root, child, rank } => {
+                set("type", "union".into());
+                set("root", (*root).into());
+                set("child", (*child).into());
+                set("rank", (*rank).into());
+            }
+        }
+
+        obj.into()
+    }
+}
+
+/// 経路圧縮とランクによる併合を行うUnion-Find
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<usize>,
+    events: Vec<UnionFindEvent>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            events: Vec::new(),
+        }
+    }
+
+    /// vertexの根を返し、たどった要素を根に直接つなぎ直す
+    pub fn find(&mut self, vertex: usize) -> usize {
+        let mut path = vec![vertex];
+        let mut root = vertex;
+        while self.parent[root] != root {
+            root = self.parent[root];
+            path.push(root);
+        }
+        self.events.push(UnionFindEvent::Find {
+            vertex,
+            root,
+            path: path.clone(),
+        });
+
+        // 根の直接の子は付け替えても変わらないので飛ばす
+        for &v in path.iter().rev().skip(2) {
+            let from = self.parent[v];
+            self.parent[v] = root;
+            self.events.push(UnionFindEvent::Compress {
+                vertex: v,
+                from,
+                root,
+            });
+        }
+        root
+    }
+
+    /// aとbの集合を併合する。既に同じ集合ならfalse
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra == rb {
+            return false;
+        }
+
+        // ランクの低い木を高い木の下につなぐ
+        let (root, child) = if self.rank[ra] < self.rank[rb] {
+            (rb, ra)
+        } else {
+            (ra, rb)
+        };
+        self.parent[child] = root;
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+        self.events.push(UnionFindEvent::Union {
+            root,
+            child,
+            rank: self.rank[root],
+        });
+        true
+    }
+
+    pub fn parents(&self) -> &[usize] {
+        &self.parent
+    }
+
+    pub fn ranks(&self) -> &[usize] {
+        &self.rank
+    }
+
+    pub fn events(&self) -> &[UnionFindEvent] {
+        &self.events
+    }
+
+    /// 記録したイベントを取り出す
+    pub fn take_events(&mut self) -> Vec<UnionFindEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_by_rank() {
+        let mut uf = UnionFind::new(4);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(2, 3));
+        assert!(uf.union(3, 1));
+        assert!(!uf.union(0, 2));
+
+        // 同じランク同士は第1引数側の根が残る
+        assert_eq!(uf.parents(), &[2, 0, 2, 2]);
+        assert_eq!(uf.ranks(), &[1, 0, 2, 0]);
+        assert_eq!(
+            uf.events().last(),
+            Some(&UnionFindEvent::Find {
+                vertex: 2,
+                root: 2,
+                path: vec![2],
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_compresses_path() {
+        let mut uf = UnionFind::new(4);
+        uf.union(0, 1);
+        uf.union(2, 3);
+        uf.union(0, 2);
+        assert_eq!(uf.parents(), &[0, 0, 0, 2]);
+        uf.take_events();
+
+        assert_eq!(uf.find(3), 0);
+        assert_eq!(uf.parents(), &[0, 0, 0, 0]);
+        assert_eq!(
+            uf.events(),
+            &[
+                UnionFindEvent::Find {
+                    vertex: 3,
+                    root: 0,
+                    path: vec![3, 2, 0],
+                },
+                UnionFindEvent::Compress {
+                    vertex: 3,
+                    from: 2,
+                    root: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+/// ハッシュテーブルの操作中に発生したイベント
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashEvent {
+    /// keyのハッシュ値と、最初に調べる位置
+    Hash { key: i32, hash: u32, index: usize },
+
+    /// attempt回目にindexを調べた (チェインではチェインの中の位置)
+    Probe {
+        key: i32,
+        index: usize,
+        attempt: usize,
+    },
+
+    /// 挿入しようとした位置が別のキーoccupantで埋まっていた
+    Collision {
+        key: i32,
+        index: usize,
+        occupant: i32,
+    },
+
+    /// keyをindexに格納した
+    Insert { key: i32, index: usize },
+
+    /// keyがindexに見つかった
+    Found { key: i32, index: usize },
+
+    /// keyは見つからなかった
+    NotFound { key: i32 },
+
+    /// indexからkeyを取り除いた
+    Remove { key: i32, index: usize },
+
+    /// keyを削除し、indexを墓標 (削除済み) にした
+    Tombstone { key: i32, index: usize },
+
+    /// keyをindexに置き、そこにあったdisplacedを追い出した
+    /// (Robin Hoodの入れ替え、カッコーハッシュの追い出し)
+    Displace {
+        key: i32,
+        displaced: i32,
+        index: usize,
+    },
+
+    /// 削除後の後方シフトでkeyをfromからtoに移した (Robin Hood)
+    Shift { key: i32, from: usize, to: usize },
+
+    /// テーブルを作り直した
+    Resize {
+        old_capacity: usize,
+        new_capacity: usize,
+    },
+
+    /// 作り直したテーブルでkeyがindexに入った
+    Rehash { key: i32, index: usize },
+}
+
+impl HashEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        match *self {
+            HashEvent::Hash { key, hash, index } => {
+                set("type", "hash".into());
+                set("key", key.into());
+                set("hash", hash.into());
+                set("index", index.into());
+            }
+            HashEvent::Probe {
+                key,
+                index,
+                attempt,
+            } => {
+                set("type", "probe".into());
+                set("key", key.into());
+                set("index", index.into());
+                set("attempt", attempt.into());
+            }
+            HashEvent::Collision {
+                key,
+                index,
+                occupant,
+            } => {
+                set("type", "collision".into());
+                set("key", key.into());
+                set("index", index.into());
+                set("occupant", occupant.into());
+            }
+            HashEvent::Insert { key, index } => {
+                set("type", "insert".into());
+                set("key", key.into());
+                set("index", index.into());
+            }
+            HashEvent::Found { key, index } => {
+                set("type", "found".into());
+                set("key", key.into());
+                set("index", index.into());
+            }
+            HashEvent::NotFound { key } => {
+                set("type", "notFound".into());
+                set("key", key.into());
+            }
+            HashEvent::Remove { key, index } => {
+                set("type", "remove".into());
+                set("key", key.into());
+                set("index", index.into());
+            }
+            HashEvent::Tombstone { key, index } => {
+                set("type", "tombstone".into());
+                set("key", key.into());
+                set("index", index.into());
+            }
+            HashEvent::Displace {
+                key,
+                displaced,
+                index,
+            } => {
+                set("type", "displace".into());
+                set("key", key.into());
+                set("displaced", displaced.into());
+                set("index", index.into());
+            }
+            HashEvent::Shift { key, from, to } => {
+                set("type", "shift".into());
+                set("key", key.into());
+                set("from", from.into());
+                set("to", to.into());
+            }
+            HashEvent::Resize {
+                old_capacity,
+                new_capacity,
+            } => {
+                set("type", "resize".into());
+                set("oldCapacity", old_capacity.into());
+                set("newCapacity", new_capacity.into());
+            }
+            HashEvent::Rehash { key, index } => {
+                set("type", "rehash".into());
+                set("key", key.into());
+                set("index", index.into());
+            }
+        }
+
+        obj.into()
+    }
+}
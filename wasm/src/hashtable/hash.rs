@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+/// キーから添字を求めるハッシュ関数 (どれも決定的)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashFunction {
+    /// k mod m (教科書の除算法)
+    #[default]
+    Modulo,
+
+    /// Knuthの乗算法 (積の上位ビットを使う)
+    Multiplicative,
+
+    /// 32bit FNV-1a (キーのリトルエンディアンの4バイト)
+    Fnv1a,
+
+    /// MurmurHash3 の最終ミックス関数
+    Murmur,
+}
+
+impl FromStr for HashFunction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "modulo" => HashFunction::Modulo,
+            "multiplicative" => HashFunction::Multiplicative,
+            "fnv1a" => HashFunction::Fnv1a,
+            "murmur" => HashFunction::Murmur,
+            _ => return Err(format!("unknown hash function: {s}")),
+        })
+    }
+}
+
+const KNUTH: u32 = 2_654_435_761;
+
+impl HashFunction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashFunction::Modulo => "modulo",
+            HashFunction::Multiplicative => "multiplicative",
+            HashFunction::Fnv1a => "fnv1a",
+            HashFunction::Murmur => "murmur",
+        }
+    }
+
+    /// キーのハッシュ値
+    pub fn hash(&self, key: i32) -> u32 {
+        let k = key as u32;
+        match self {
+            HashFunction::Modulo => k,
+            HashFunction::Multiplicative => k.wrapping_mul(KNUTH),
+            HashFunction::Fnv1a => {
+                k.to_le_bytes().iter().fold(0x811c_9dc5, |h, &b| {
+                    (h ^ b as u32).wrapping_mul(0x0100_0193)
+                })
+            }
+            HashFunction::Murmur => fmix32(k),
+        }
+    }
+
+    /// ハッシュ値を容量 (2の冪) の範囲の添字にする
+    pub fn index(&self, hash: u32, capacity: usize) -> usize {
+        match self {
+            // 乗算法は下位ビットの質が悪いので上位ビットを使う
+            HashFunction::Multiplicative if capacity > 1 => {
+                (hash >> (32 - capacity.trailing_zeros())) as usize
+            }
+            _ => hash as usize & (capacity - 1),
+        }
+    }
+}
+
+/// ダブルハッシュの刻み幅やカッコーハッシュの2つ目の位置に使うハッシュ値
+///
+/// 1つ目のハッシュ関数と相関しないよう、キーを混ぜてからミックスする
+pub fn secondary_hash(key: i32) -> u32 {
+    fmix32(key as u32 ^ 0x9e37_79b9)
+}
+
+fn fmix32(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
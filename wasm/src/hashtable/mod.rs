@@ -0,0 +1,7 @@
+mod event;
+mod hash;
+mod operation;
+
+pub use event::HashEvent;
+pub use hash::HashFunction;
+pub use operation::{HashTable, MAX_CAPACITY, Slot, Strategy};
@@ -0,0 +1,895 @@
+use crate::hashtable::event::HashEvent;
+use crate::hashtable::hash::{HashFunction, secondary_hash};
+use js_sys::{Array, Object};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// 最小の容量 (容量は常に2の冪)
+const MIN_CAPACITY: usize = 4;
+
+/// 指定できる初期容量の上限
+pub const MAX_CAPACITY: usize = 1 << 20;
+
+/// 衝突の解決方法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// 同じ位置のキーを連結リスト (チェイン) に並べる
+    #[default]
+    Chaining,
+
+    /// 線形探査
+    Linear,
+
+    /// 二次探査 (三角数 i(i+1)/2 ずつ進むので全スロットを巡る)
+    Quadratic,
+
+    /// ダブルハッシュ (刻み幅を2つ目のハッシュ関数で決める)
+    DoubleHashing,
+
+    /// Robin Hoodハッシュ (線形探査 + 後方シフト削除)
+    RobinHood,
+
+    /// カッコーハッシュ (各キーの候補位置は2か所)
+    Cuckoo,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 6] = [
+        Strategy::Chaining,
+        Strategy::Linear,
+        Strategy::Quadratic,
+        Strategy::DoubleHashing,
+        Strategy::RobinHood,
+        Strategy::Cuckoo,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::Chaining => "chaining",
+            Strategy::Linear => "linear",
+            Strategy::Quadratic => "quadratic",
+            Strategy::DoubleHashing => "double",
+            Strategy::RobinHood => "robinhood",
+            Strategy::Cuckoo => "cuckoo",
+        }
+    }
+
+    /// これを超えたら容量を倍にする負荷率
+    fn max_load_factor(&self) -> f64 {
+        match self {
+            Strategy::Chaining => 1.0,
+            Strategy::Cuckoo => 0.5,
+            _ => 0.75,
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "chaining" => Strategy::Chaining,
+            "linear" => Strategy::Linear,
+            "quadratic" => Strategy::Quadratic,
+            "double" => Strategy::DoubleHashing,
+            "robinhood" => Strategy::RobinHood,
+            "cuckoo" => Strategy::Cuckoo,
+            _ => return Err(format!("unknown hashing strategy: {s}")),
+        })
+    }
+}
+
+/// オープンアドレス法のスロット
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Empty,
+    Occupied(i32),
+    /// 削除済み (探索は先へ進み、挿入では再利用する)
+    Tombstone,
+}
+
+// HashTable (キーの集合)
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct HashTable {
+    strategy: Strategy,
+    hash: HashFunction,
+
+    // チェイン法のバケット (オープンアドレス法では空)
+    chains: Vec<Vec<i32>>,
+
+    // オープンアドレス法のスロット (チェイン法では空)
+    slots: Vec<Slot>,
+
+    // 格納しているキーの数
+    len: usize,
+
+    // 墓標の数
+    tombstones: usize,
+
+    // 直前の操作で発生したイベント
+    events: Vec<HashEvent>,
+}
+
+#[wasm_bindgen]
+impl HashTable {
+    /// strategyとhashは名前で指定する (例: "linear", "murmur")
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        strategy: &str,
+        hash: &str,
+        capacity: usize,
+    ) -> Result<HashTable, String> {
+        HashTable::with_options(strategy.parse()?, hash.parse()?, capacity)
+    }
+
+    /// キーを挿入する。既にあればfalse
+    pub fn insert(&mut self, key: i32) -> bool {
+        self.events.clear();
+        let inserted = match self.strategy {
+            Strategy::Chaining => self.chaining_insert(key),
+            Strategy::Linear
+            | Strategy::Quadratic
+            | Strategy::DoubleHashing => self.probing_insert(key),
+            Strategy::RobinHood => self.robin_hood_insert(key),
+            Strategy::Cuckoo => match self.cuckoo_insert(key) {
+                Ok(inserted) => inserted,
+                Err(homeless) => {
+                    // 追い出しが循環したので、容量を倍にして入れ直す
+                    self.rebuild(self.capacity() * 2, Some(homeless));
+                    true
+                }
+            },
+        };
+
+        if inserted {
+            let limit =
+                self.strategy.max_load_factor() * self.capacity() as f64;
+            if self.len as f64 > limit {
+                self.rebuild(self.capacity() * 2, None);
+            } else if (self.len + self.tombstones) as f64 > limit {
+                // 墓標が溜まったら同じ容量で作り直して掃除する
+                self.rebuild(self.capacity(), None);
+            }
+        }
+        inserted
+    }
+
+    /// キーを削除する。なければfalse
+    pub fn delete(&mut self, key: i32) -> bool {
+        self.events.clear();
+        let Some(index) = self.find(key) else {
+            return false;
+        };
+
+        match self.strategy {
+            Strategy::Chaining => {
+                self.chains[index].retain(|&k| k != key);
+                self.events.push(HashEvent::Remove { key, index });
+            }
+            Strategy::Linear
+            | Strategy::Quadratic
+            | Strategy::DoubleHashing => {
+                self.slots[index] = Slot::Tombstone;
+                self.tombstones += 1;
+                self.events.push(HashEvent::Tombstone { key, index });
+            }
+            Strategy::RobinHood => self.backward_shift(key, index),
+            Strategy::Cuckoo => {
+                self.slots[index] = Slot::Empty;
+                self.events.push(HashEvent::Remove { key, index });
+            }
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// キーを検索する
+    pub fn search(&mut self, key: i32) -> bool {
+        self.events.clear();
+        self.find(key).is_some()
+    }
+
+    /// バケットの配列 ({index, keys, tombstone} の配列)
+    ///
+    /// オープンアドレス法ではkeysの要素は高々1つ
+    #[wasm_bindgen]
+    pub fn get_buckets(&self) -> Array {
+        let bucket = |index: usize, keys: &[i32], tombstone: bool| {
+            let obj = Object::new();
+            let set = |name: &str, value: JsValue| {
+                let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+            };
+            set("index", index.into());
+            set(
+                "keys",
+                Array::from_iter(keys.iter().map(|&k| JsValue::from(k)))
+                    .into(),
+            );
+            set("tombstone", tombstone.into());
+            JsValue::from(obj)
+        };
+
+        if self.strategy == Strategy::Chaining {
+            Array::from_iter(
+                self.chains
+                    .iter()
+                    .enumerate()
+                    .map(|(i, chain)| bucket(i, chain, false)),
+            )
+        } else {
+            Array::from_iter(self.slots.iter().enumerate().map(
+                |(i, slot)| match *slot {
+                    Slot::Empty => bucket(i, &[], false),
+                    Slot::Occupied(key) => bucket(i, &[key], false),
+                    Slot::Tombstone => bucket(i, &[], true),
+                },
+            ))
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(HashEvent::to_js_value))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    #[wasm_bindgen]
+    pub fn get_load_factor(&self) -> f64 {
+        self.len as f64 / self.capacity() as f64
+    }
+
+    #[wasm_bindgen]
+    pub fn get_strategy(&self) -> String {
+        self.strategy.as_str().to_string()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_hash_function(&self) -> String {
+        self.hash.as_str().to_string()
+    }
+}
+
+impl HashTable {
+    /// 容量がMAX_CAPACITYを超えるとErr
+    pub fn with_options(
+        strategy: Strategy,
+        hash: HashFunction,
+        capacity: usize,
+    ) -> Result<Self, String> {
+        if capacity > MAX_CAPACITY {
+            return Err(format!(
+                "capacity must be at most {MAX_CAPACITY}"
+            ));
+        }
+        let mut table = HashTable {
+            strategy,
+            hash,
+            chains: Vec::new(),
+            slots: Vec::new(),
+            len: 0,
+            tombstones: 0,
+            events: Vec::new(),
+        };
+        table.reset(capacity.max(MIN_CAPACITY).next_power_of_two());
+        Ok(table)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.chains.len().max(self.slots.len())
+    }
+
+    pub fn events(&self) -> &[HashEvent] {
+        &self.events
+    }
+
+    /// チェイン法のバケット
+    pub fn chains(&self) -> &[Vec<i32>] {
+        &self.chains
+    }
+
+    /// オープンアドレス法のスロット
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    /// 格納しているキー (バケットの順)
+    pub fn keys(&self) -> Vec<i32> {
+        self.placements().into_iter().map(|(key, _)| key).collect()
+    }
+
+    fn placements(&self) -> Vec<(i32, usize)> {
+        let chained =
+            self.chains.iter().enumerate().flat_map(|(i, chain)| {
+                chain.iter().map(move |&key| (key, i))
+            });
+        let open =
+            self.slots.iter().enumerate().filter_map(|(i, slot)| {
+                match *slot {
+                    Slot::Occupied(key) => Some((key, i)),
+                    _ => None,
+                }
+            });
+        chained.chain(open).collect()
+    }
+
+    fn reset(&mut self, capacity: usize) {
+        if self.strategy == Strategy::Chaining {
+            self.chains = vec![Vec::new(); capacity];
+        } else {
+            self.slots = vec![Slot::Empty; capacity];
+        }
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    fn mask(&self) -> usize {
+        self.capacity() - 1
+    }
+
+    /// keyの最初の位置 (イベントは記録しない)
+    fn home(&self, key: i32) -> usize {
+        self.hash.index(self.hash.hash(key), self.capacity())
+    }
+
+    /// keyのハッシュ値を記録して最初の位置を返す
+    fn trace_hash(&mut self, key: i32) -> usize {
+        let hash = self.hash.hash(key);
+        let index = self.hash.index(hash, self.capacity());
+        self.events.push(HashEvent::Hash { key, hash, index });
+        index
+    }
+
+    fn probe(&mut self, key: i32, index: usize, attempt: usize) {
+        self.events.push(HashEvent::Probe {
+            key,
+            index,
+            attempt,
+        });
+    }
+
+    /// 探査列のattempt番目の位置
+    fn probe_index(&self, key: i32, home: usize, attempt: usize) -> usize {
+        let offset = match self.strategy {
+            Strategy::Quadratic => attempt * (attempt + 1) / 2,
+            // 容量は2の冪なので、奇数の刻み幅なら全スロットを巡る
+            Strategy::DoubleHashing => {
+                attempt
+                    * ((secondary_hash(key) as usize & self.mask()) | 1)
+            }
+            _ => attempt,
+        };
+        (home + offset) & self.mask()
+    }
+
+    /// Robin Hoodでindexにあるkeyの、本来の位置からの距離
+    fn distance(&self, key: i32, index: usize) -> usize {
+        index.wrapping_sub(self.home(key)) & self.mask()
+    }
+
+    /// カッコーハッシュのkeyの2つ目の候補位置
+    fn alternate(&self, key: i32) -> usize {
+        secondary_hash(key) as usize & self.mask()
+    }
+
+    /// keyの位置 (チェイン法ではバケットの番号) を探す
+    fn find(&mut self, key: i32) -> Option<usize> {
+        let home = self.trace_hash(key);
+        let found = match self.strategy {
+            Strategy::Chaining => {
+                let mut found = None;
+                for attempt in 0..self.chains[home].len() {
+                    self.probe(key, home, attempt);
+                    if self.chains[home][attempt] == key {
+                        found = Some(home);
+                        break;
+                    }
+                }
+                found
+            }
+            Strategy::Cuckoo => [home, self.alternate(key)]
+                .into_iter()
+                .enumerate()
+                .find(|&(attempt, index)| {
+                    self.probe(key, index, attempt);
+                    self.slots[index] == Slot::Occupied(key)
+                })
+                .map(|(_, index)| index),
+            _ => {
+                let mut found = None;
+                for attempt in 0..self.capacity() {
+                    let index = self.probe_index(key, home, attempt);
+                    self.probe(key, index, attempt);
+                    match self.slots[index] {
+                        Slot::Empty => break,
+                        Slot::Occupied(k) if k == key => {
+                            found = Some(index);
+                            break;
+                        }
+                        // Robin Hoodでは自分より近いキーがあればそこで打ち切る
+                        Slot::Occupied(k)
+                            if self.strategy == Strategy::RobinHood
+                                && self.distance(k, index) < attempt =>
+                        {
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                found
+            }
+        };
+
+        self.events.push(match found {
+            Some(index) => HashEvent::Found { key, index },
+            None => HashEvent::NotFound { key },
+        });
+        found
+    }
+
+    fn chaining_insert(&mut self, key: i32) -> bool {
+        let index = self.trace_hash(key);
+        for attempt in 0..self.chains[index].len() {
+            self.probe(key, index, attempt);
+            if self.chains[index][attempt] == key {
+                self.events.push(HashEvent::Found { key, index });
+                return false;
+            }
+        }
+
+        if let Some(&occupant) = self.chains[index].first() {
+            self.events.push(HashEvent::Collision {
+                key,
+                index,
+                occupant,
+            });
+        }
+        self.chains[index].push(key);
+        self.len += 1;
+        self.events.push(HashEvent::Insert { key, index });
+        true
+    }
+
+    /// 線形探査・二次探査・ダブルハッシュの挿入
+    fn probing_insert(&mut self, key: i32) -> bool {
+        let home = self.trace_hash(key);
+        let mut free = None;
+        for attempt in 0..self.capacity() {
+            let index = self.probe_index(key, home, attempt);
+            self.probe(key, index, attempt);
+            match self.slots[index] {
+                Slot::Empty => {
+                    free.get_or_insert(index);
+                    break;
+                }
+                // 墓標は再利用できるが、先に同じキーがあるかもしれない
+                Slot::Tombstone => {
+                    free.get_or_insert(index);
+                }
+                Slot::Occupied(k) if k == key => {
+                    self.events.push(HashEvent::Found { key, index });
+                    return false;
+                }
+                Slot::Occupied(occupant) => {
+                    self.events.push(HashEvent::Collision {
+                        key,
+                        index,
+                        occupant,
+                    });
+                }
+            }
+        }
+
+        // 負荷率を1未満に保っているので必ず空きがある
+        let index = free.expect("open addressing table has no free slot");
+        if self.slots[index] == Slot::Tombstone {
+            self.tombstones -= 1;
+        }
+        self.slots[index] = Slot::Occupied(key);
+        self.len += 1;
+        self.events.push(HashEvent::Insert { key, index });
+        true
+    }
+
+    /// Robin Hoodの挿入
+    ///
+    /// 本来の位置からの距離が自分より短いキーに出会ったら、
+    /// そのキーを追い出して自分が入り、追い出したキーの挿入を続ける
+    fn robin_hood_insert(&mut self, key: i32) -> bool {
+        let mut index = self.trace_hash(key);
+        let mut current = key;
+        let mut distance = 0;
+        for attempt in 0.. {
+            self.probe(current, index, attempt);
+            match self.slots[index] {
+                Slot::Occupied(k) if k == key => {
+                    // 既にあるなら、最初の入れ替えより前に必ず出会う
+                    self.events.push(HashEvent::Found { key, index });
+                    return false;
+                }
+                Slot::Occupied(occupant) => {
+                    let occupant_distance = self.distance(occupant, index);
+                    if occupant_distance < distance {
+                        self.slots[index] = Slot::Occupied(current);
+                        self.events.push(HashEvent::Displace {
+                            key: current,
+                            displaced: occupant,
+                            index,
+                        });
+                        current = occupant;
+                        distance = occupant_distance;
+                    } else {
+                        self.events.push(HashEvent::Collision {
+                            key: current,
+                            index,
+                            occupant,
+                        });
+                    }
+                }
+                _ => {
+                    self.slots[index] = Slot::Occupied(current);
+                    self.events.push(HashEvent::Insert {
+                        key: current,
+                        index,
+                    });
+                    break;
+                }
+            }
+            index = (index + 1) & self.mask();
+            distance += 1;
+        }
+        self.len += 1;
+        true
+    }
+
+    /// Robin Hoodの削除: 後ろのキーを1つずつ前に詰める
+    fn backward_shift(&mut self, key: i32, index: usize) {
+        self.slots[index] = Slot::Empty;
+        self.events.push(HashEvent::Remove { key, index });
+
+        let mut to = index;
+        loop {
+            let from = (to + 1) & self.mask();
+            let Slot::Occupied(k) = self.slots[from] else {
+                break;
+            };
+            if self.distance(k, from) == 0 {
+                break;
+            }
+            self.slots[to] = Slot::Occupied(k);
+            self.slots[from] = Slot::Empty;
+            self.events.push(HashEvent::Shift { key: k, from, to });
+            to = from;
+        }
+    }
+
+    /// カッコーハッシュの挿入
+    ///
+    /// 追い出しが容量回続いたら、居場所のなくなったキーをErrで返す
+    fn cuckoo_insert(&mut self, key: i32) -> Result<bool, i32> {
+        let home = self.trace_hash(key);
+        let candidates = [home, self.alternate(key)];
+        for (attempt, index) in candidates.into_iter().enumerate() {
+            self.probe(key, index, attempt);
+            if self.slots[index] == Slot::Occupied(key) {
+                self.events.push(HashEvent::Found { key, index });
+                return Ok(false);
+            }
+        }
+
+        let mut index = candidates
+            .into_iter()
+            .find(|&i| self.slots[i] == Slot::Empty)
+            .unwrap_or(home);
+        let mut current = key;
+        for attempt in 0..=self.capacity() {
+            match self.slots[index] {
+                Slot::Occupied(displaced) => {
+                    self.slots[index] = Slot::Occupied(current);
+                    self.events.push(HashEvent::Displace {
+                        key: current,
+                        displaced,
+                        index,
+                    });
+                    // 追い出されたキーはもう一方の候補位置へ
+                    index = if self.home(displaced) == index {
+                        self.alternate(displaced)
+                    } else {
+                        self.home(displaced)
+                    };
+                    current = displaced;
+                    self.probe(current, index, attempt + 2);
+                }
+                _ => {
+                    self.slots[index] = Slot::Occupied(current);
+                    self.len += 1;
+                    self.events.push(HashEvent::Insert {
+                        key: current,
+                        index,
+                    });
+                    return Ok(true);
+                }
+            }
+        }
+        // keyは表に入ったが、代わりにcurrentの居場所がなくなった
+        Err(current)
+    }
+
+    /// 容量capacityで作り直し、全キー (とextra) を入れ直す
+    fn rebuild(&mut self, capacity: usize, extra: Option<i32>) {
+        let old_capacity = self.capacity();
+        let mut keys = self.keys();
+        keys.extend(extra);
+
+        // 入れ直しの途中のイベントは記録しない
+        let events = std::mem::take(&mut self.events);
+        let mut capacity = capacity;
+        loop {
+            self.reset(capacity);
+            let placed = keys.iter().all(|&key| match self.strategy {
+                Strategy::Chaining => self.chaining_insert(key),
+                Strategy::RobinHood => self.robin_hood_insert(key),
+                Strategy::Cuckoo => self.cuckoo_insert(key).is_ok(),
+                _ => self.probing_insert(key),
+            });
+            if placed {
+                break;
+            }
+            capacity *= 2;
+        }
+        self.events = events;
+
+        self.events.push(HashEvent::Resize {
+            old_capacity,
+            new_capacity: capacity,
+        });
+        for (key, index) in self.placements() {
+            self.events.push(HashEvent::Rehash { key, index });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use std::collections::BTreeSet;
+
+    fn table(strategy: Strategy) -> HashTable {
+        HashTable::with_options(strategy, HashFunction::Modulo, 8).unwrap()
+    }
+
+    fn count(table: &HashTable, pred: fn(&HashEvent) -> bool) -> usize {
+        table.events().iter().filter(|e| pred(e)).count()
+    }
+
+    #[test]
+    fn test_chaining_collisions_and_resize() {
+        let mut t = HashTable::with_options(
+            Strategy::Chaining,
+            HashFunction::Modulo,
+            4,
+        )
+        .unwrap();
+        for key in [1, 5, 9] {
+            assert!(t.insert(key));
+        }
+        assert_eq!(t.chains()[1], vec![1, 5, 9]);
+        assert_eq!(
+            t.events().last(),
+            Some(&HashEvent::Insert { key: 9, index: 1 })
+        );
+        assert_eq!(
+            count(&t, |e| matches!(e, HashEvent::Collision { .. })),
+            1
+        );
+        assert!(!t.insert(5));
+
+        assert!(t.search(9));
+        assert_eq!(count(&t, |e| matches!(e, HashEvent::Probe { .. })), 3);
+
+        assert!(t.delete(5));
+        assert_eq!(t.chains()[1], vec![1, 9]);
+
+        // 容量4に5個目を入れると倍になる
+        for key in [2, 3, 4] {
+            t.insert(key);
+        }
+        assert_eq!(t.get_capacity(), 8);
+        assert!(t.events().contains(&HashEvent::Resize {
+            old_capacity: 4,
+            new_capacity: 8,
+        }));
+        assert!(
+            t.events().contains(&HashEvent::Rehash { key: 9, index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_linear_probing_tombstones() {
+        let mut t = table(Strategy::Linear);
+        for key in [1, 9, 17] {
+            t.insert(key);
+        }
+        assert_eq!(
+            &t.slots()[1..4],
+            &[Slot::Occupied(1), Slot::Occupied(9), Slot::Occupied(17)]
+        );
+
+        assert!(t.delete(9));
+        assert_eq!(t.slots()[2], Slot::Tombstone);
+        assert_eq!(
+            t.events().last(),
+            Some(&HashEvent::Tombstone { key: 9, index: 2 })
+        );
+
+        // 墓標を越えて探索を続ける
+        assert!(t.search(17));
+        assert!(t.events().contains(&HashEvent::Probe {
+            key: 17,
+            index: 2,
+            attempt: 1,
+        }));
+
+        // 重複がないことを確かめてから最初の墓標を再利用する
+        assert!(!t.insert(17));
+        assert!(t.insert(25));
+        assert_eq!(t.slots()[2], Slot::Occupied(25));
+    }
+
+    #[test]
+    fn test_quadratic_and_double_hashing_sequences() {
+        let mut t = table(Strategy::Quadratic);
+        for key in [0, 8, 16] {
+            t.insert(key);
+        }
+        // 0, 0+1, 0+3
+        assert_eq!(t.slots()[3], Slot::Occupied(16));
+
+        let mut t = table(Strategy::DoubleHashing);
+        for key in [0, 8] {
+            t.insert(key);
+        }
+        let step = (secondary_hash(8) as usize & 7) | 1;
+        assert_eq!(t.slots()[step], Slot::Occupied(8));
+    }
+
+    #[test]
+    fn test_robin_hood_displaces_and_shifts_back() {
+        let mut t = table(Strategy::RobinHood);
+        for key in [2, 10, 1] {
+            t.insert(key);
+        }
+
+        // 9 (本来の位置1) は距離0の2を追い出す
+        t.insert(9);
+        assert!(t.events().contains(&HashEvent::Displace {
+            key: 9,
+            displaced: 2,
+            index: 2,
+        }));
+        assert_eq!(
+            &t.slots()[1..5],
+            &[
+                Slot::Occupied(1),
+                Slot::Occupied(9),
+                Slot::Occupied(10),
+                Slot::Occupied(2),
+            ]
+        );
+
+        // 本来の位置より先で、自分より近いキーに出会ったら打ち切る
+        t.search(17);
+        assert_eq!(count(&t, |e| matches!(e, HashEvent::Probe { .. })), 3);
+
+        assert!(t.delete(9));
+        assert_eq!(
+            &t.slots()[1..5],
+            &[
+                Slot::Occupied(1),
+                Slot::Occupied(10),
+                Slot::Occupied(2),
+                Slot::Empty,
+            ]
+        );
+        assert!(t.events().contains(&HashEvent::Shift {
+            key: 2,
+            from: 4,
+            to: 3
+        }));
+    }
+
+    #[test]
+    fn test_cuckoo_keys_stay_in_candidate_slots() {
+        let mut t = table(Strategy::Cuckoo);
+        let mut displaced = false;
+        for key in 0..64 {
+            t.insert(key * 8);
+            displaced |= t
+                .events()
+                .iter()
+                .any(|e| matches!(e, HashEvent::Displace { .. }));
+        }
+        assert!(displaced);
+        assert!(t.get_load_factor() <= 0.5);
+
+        for (index, slot) in t.slots().iter().enumerate() {
+            if let Slot::Occupied(key) = *slot {
+                assert!(index == t.home(key) || index == t.alternate(key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_set_for_every_strategy_and_hash() {
+        let hashes = [
+            HashFunction::Modulo,
+            HashFunction::Multiplicative,
+            HashFunction::Fnv1a,
+            HashFunction::Murmur,
+        ];
+        for strategy in Strategy::ALL {
+            for hash in hashes {
+                let mut t =
+                    HashTable::with_options(strategy, hash, 4).unwrap();
+                let mut expected = BTreeSet::new();
+                let mut rng = SplitMix64::new(7);
+                for _ in 0..600 {
+                    let key = (rng.next_u64() % 200) as i32 - 100;
+                    let (got, want) = if rng.chance(0.3) {
+                        (t.delete(key), expected.remove(&key))
+                    } else {
+                        (t.insert(key), expected.insert(key))
+                    };
+                    assert_eq!(got, want, "{strategy:?} {hash:?} {key}");
+                }
+
+                let mut keys = t.keys();
+                keys.sort();
+                assert_eq!(keys, Vec::from_iter(expected.iter().copied()));
+                assert_eq!(t.get_total_keys(), expected.len());
+                for key in -100..100 {
+                    assert_eq!(t.search(key), expected.contains(&key));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(HashTable::new("robinhood", "fnv1a", 8).is_ok());
+        assert!(HashTable::new("hopscotch", "modulo", 8).is_err());
+        assert!(HashTable::new("linear", "sha256", 8).is_err());
+        assert!(HashTable::new("linear", "murmur", MAX_CAPACITY).is_ok());
+        assert!(
+            HashTable::new("linear", "murmur", MAX_CAPACITY + 1).is_err()
+        );
+        assert!(HashTable::new("linear", "murmur", 1 << 45).is_err());
+        assert_eq!(table(Strategy::Linear).get_capacity(), 8);
+        assert_eq!(
+            HashTable::with_options(
+                Strategy::Linear,
+                HashFunction::Modulo,
+                5
+            )
+            .unwrap()
+            .get_capacity(),
+            8
+        );
+
+        // 乗算法は上位ビットを添字にする
+        let h = HashFunction::Multiplicative;
+        assert_eq!(h.index(0xf000_0000, 16), 15);
+        assert_eq!(HashFunction::Modulo.index(13, 8), 5);
+    }
+}
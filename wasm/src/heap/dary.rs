@@ -0,0 +1,211 @@
+/// 指定できる分岐数の上限
+pub const MAX_ARITY: usize = 1 << 10;
+
+/// ヒープの順序
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeapOrder {
+    /// 最小値が根
+    Min,
+
+    /// 最大値が根
+    Max,
+}
+
+/// sift-up / sift-down で要素を入れ替えたことを表すイベント
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeapEvent<T> {
+    /// 位置childの値valueを親の位置parentへ上げた
+    SiftUp {
+        child: usize,
+        parent: usize,
+        value: T,
+    },
+
+    /// 位置parentの値valueを子の位置childへ下げた
+    SiftDown {
+        parent: usize,
+        child: usize,
+        value: T,
+    },
+}
+
+/// d分ヒープ (d = 2 で二分ヒープ)
+///
+/// 位置iの子は d*i+1 .. d*i+d、親は (i-1)/d
+#[derive(Clone, Debug)]
+pub struct DaryHeap<T> {
+    data: Vec<T>,
+    arity: usize,
+    order: HeapOrder,
+    events: Vec<HeapEvent<T>>,
+}
+
+impl<T: Ord + Clone> DaryHeap<T> {
+    /// arityが2未満かMAX_ARITYを超えるとErr
+    pub fn new(arity: usize, order: HeapOrder) -> Result<Self, String> {
+        if arity < 2 {
+            return Err("arity must be at least 2".into());
+        }
+        if arity > MAX_ARITY {
+            return Err(format!("arity must be at most {MAX_ARITY}"));
+        }
+        Ok(DaryHeap {
+            data: Vec::new(),
+            arity,
+            order,
+            events: Vec::new(),
+        })
+    }
+
+    /// 配列からボトムアップにヒープを構築 (O(n))
+    pub fn heapify(
+        values: Vec<T>,
+        arity: usize,
+        order: HeapOrder,
+    ) -> Result<Self, String> {
+        let mut heap = DaryHeap::new(arity, order)?;
+        heap.data = values;
+        if heap.data.len() > 1 {
+            // 子を持つ最後の位置から根へ向かって sift-down
+            for i in (0..=heap.parent(heap.data.len() - 1)).rev() {
+                heap.sift_down(i);
+            }
+        }
+        Ok(heap)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn order(&self) -> HeapOrder {
+        self.order
+    }
+
+    /// 配列表現
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    /// 直前の操作で発生した入れ替え
+    pub fn events(&self) -> &[HeapEvent<T>] {
+        &self.events
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        self.sift_up(self.data.len() - 1);
+    }
+
+    /// 根の値を取り出す
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let top = self.data.swap_remove(0);
+        if !self.data.is_empty() {
+            self.sift_down(0);
+        }
+        Some(top)
+    }
+
+    /// 位置indexの値をvalueに変えて優先度を上げる
+    ///
+    /// 最小ヒープでは値を小さく、最大ヒープでは値を大きくする場合のみ許す
+    pub fn decrease_key(&mut self, index: usize, value: T) -> bool {
+        if index >= self.data.len()
+            || self.before(&self.data[index], &value)
+        {
+            return false;
+        }
+        self.data[index] = value;
+        self.sift_up(index);
+        true
+    }
+
+    /// 条件を満たす最初の要素の位置
+    pub fn position(&self, pred: impl Fn(&T) -> bool) -> Option<usize> {
+        self.data.iter().position(pred)
+    }
+
+    /// 位置iの親
+    pub fn parent(&self, i: usize) -> usize {
+        (i - 1) / self.arity
+    }
+
+    /// 位置iの子の範囲
+    pub fn children(&self, i: usize) -> std::ops::Range<usize> {
+        // 末尾付近のiでは d*i がusizeを溢れうるので飽和させる
+        let first = self.arity.saturating_mul(i).saturating_add(1);
+        let last = first.saturating_add(self.arity);
+        let first = first.min(self.data.len());
+        let last = last.min(self.data.len());
+        first..last
+    }
+
+    /// aがbより根に近くあるべきか
+    fn before(&self, a: &T, b: &T) -> bool {
+        match self.order {
+            HeapOrder::Min => a < b,
+            HeapOrder::Max => a > b,
+        }
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 {
+            let p = self.parent(i);
+            if !self.before(&self.data[i], &self.data[p]) {
+                break;
+            }
+            self.events.push(HeapEvent::SiftUp {
+                child: i,
+                parent: p,
+                value: self.data[i].clone(),
+            });
+            self.data.swap(i, p);
+            i = p;
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            // 子の中で最も根に近くあるべきもの
+            let best = self.children(i).reduce(|a, b| {
+                if self.before(&self.data[b], &self.data[a]) {
+                    b
+                } else {
+                    a
+                }
+            });
+            let Some(c) = best else {
+                break;
+            };
+            if !self.before(&self.data[c], &self.data[i]) {
+                break;
+            }
+            self.events.push(HeapEvent::SiftDown {
+                parent: i,
+                child: c,
+                value: self.data[i].clone(),
+            });
+            self.data.swap(i, c);
+            i = c;
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod dary;
+mod operation;
+
+pub use dary::{DaryHeap, HeapEvent, HeapOrder, MAX_ARITY};
+pub use operation::PriorityQueue;
@@ -0,0 +1,272 @@
+use crate::heap::dary::{DaryHeap, HeapEvent, HeapOrder};
+use js_sys::{Array, Object};
+use wasm_bindgen::prelude::*;
+
+// Priority Queue (二分ヒープ / d分ヒープ)
+#[wasm_bindgen]
+pub struct PriorityQueue {
+    heap: DaryHeap<i32>,
+}
+
+#[wasm_bindgen]
+impl PriorityQueue {
+    /// arity = 2 で二分ヒープ、max = true で最大ヒープ
+    #[wasm_bindgen(constructor)]
+    pub fn new(arity: usize, max: bool) -> Result<PriorityQueue, String> {
+        Ok(PriorityQueue {
+            heap: DaryHeap::new(arity, Self::order(max))?,
+        })
+    }
+
+    /// 配列からヒープを構築
+    pub fn heapify(
+        values: Vec<i32>,
+        arity: usize,
+        max: bool,
+    ) -> Result<PriorityQueue, String> {
+        Ok(PriorityQueue {
+            heap: DaryHeap::heapify(values, arity, Self::order(max))?,
+        })
+    }
+
+    fn order(max: bool) -> HeapOrder {
+        if max { HeapOrder::Max } else { HeapOrder::Min }
+    }
+
+    pub fn push(&mut self, value: i32) {
+        self.heap.clear_events();
+        self.heap.push(value);
+    }
+
+    /// 根の値を取り出す
+    pub fn pop(&mut self) -> Option<i32> {
+        self.heap.clear_events();
+        self.heap.pop()
+    }
+
+    pub fn peek(&self) -> Option<i32> {
+        self.heap.peek().copied()
+    }
+
+    /// 位置indexの値をvalueに変えて優先度を上げる
+    pub fn decrease_key(&mut self, index: usize, value: i32) -> bool {
+        self.heap.clear_events();
+        self.heap.decrease_key(index, value)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// 配列表現を取得
+    #[wasm_bindgen]
+    pub fn get_array(&self) -> Vec<i32> {
+        self.heap.as_slice().to_vec()
+    }
+
+    fn node_to_js_value(&self, i: usize) -> JsValue {
+        let obj = Object::new();
+
+        let keys = Array::of1(&JsValue::from(self.heap.as_slice()[i]));
+        let _ = js_sys::Reflect::set(&obj, &"keys".into(), &keys.into());
+
+        let children = Array::from_iter(
+            self.heap.children(i).map(|c| self.node_to_js_value(c)),
+        );
+        let is_leaf = children.length() == 0;
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"children".into(),
+            &children.into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"isLeaf".into(),
+            &JsValue::from(is_leaf),
+        );
+        // 配列表現との対応がわかるよう、配列上の位置も入れる
+        let _ =
+            js_sys::Reflect::set(&obj, &"index".into(), &JsValue::from(i));
+
+        obj.into()
+    }
+
+    /// 木の形の表現を取得
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        if self.heap.is_empty() {
+            JsValue::NULL
+        } else {
+            self.node_to_js_value(0)
+        }
+    }
+
+    /// 直前の操作で発生した sift-up / sift-down の入れ替えを取得
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.heap.events().iter().map(|event| {
+            let obj = Object::new();
+            let (kind, from, to, value) = match *event {
+                HeapEvent::SiftUp {
+                    child,
+                    parent,
+                    value,
+                } => ("siftUp", child, parent, value),
+                HeapEvent::SiftDown {
+                    parent,
+                    child,
+                    value,
+                } => ("siftDown", parent, child, value),
+            };
+            let _ =
+                js_sys::Reflect::set(&obj, &"type".into(), &kind.into());
+            let _ =
+                js_sys::Reflect::set(&obj, &"from".into(), &from.into());
+            let _ = js_sys::Reflect::set(&obj, &"to".into(), &to.into());
+            let _ =
+                js_sys::Reflect::set(&obj, &"value".into(), &value.into());
+            JsValue::from(obj)
+        }))
+    }
+}
+
+impl PriorityQueue {
+    pub fn heap(&self) -> &DaryHeap<i32> {
+        &self.heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::heap::dary::MAX_ARITY;
+
+    fn assert_heap(q: &PriorityQueue) {
+        let heap = q.heap();
+        let data = heap.as_slice();
+        for i in 1..data.len() {
+            let p = heap.parent(i);
+            match heap.order() {
+                HeapOrder::Min => assert!(data[p] <= data[i], "{data:?}"),
+                HeapOrder::Max => assert!(data[p] >= data[i], "{data:?}"),
+            }
+        }
+    }
+
+    fn drain(q: &mut PriorityQueue) -> Vec<i32> {
+        std::iter::from_fn(|| q.pop()).collect()
+    }
+
+    #[test]
+    fn test_min_heap_push_pop() {
+        let mut q = PriorityQueue::new(2, false).unwrap();
+        for v in [10, 20, 5, 6, 12, 30, 7, 17] {
+            q.push(v);
+            assert_heap(&q);
+        }
+
+        assert_eq!(q.peek(), Some(5));
+        assert_eq!(drain(&mut q), vec![5, 6, 7, 10, 12, 17, 20, 30]);
+        assert_eq!(q.pop(), None);
+    }
+
+    #[test]
+    fn test_rejects_arity_out_of_range() {
+        assert!(PriorityQueue::new(1, true).is_err());
+        assert!(PriorityQueue::new(0, false).is_err());
+        assert!(PriorityQueue::heapify(vec![3, 1, 2], 1, false).is_err());
+        assert!(PriorityQueue::new(MAX_ARITY + 1, false).is_err());
+        assert!(PriorityQueue::new(usize::MAX, false).is_err());
+
+        // 最大の分岐数でも根の子の範囲は要素数で切り詰められる
+        let mut q = PriorityQueue::new(MAX_ARITY, false).unwrap();
+        for v in [3, 1, 2] {
+            q.push(v);
+        }
+        assert!(q.heap().children(usize::MAX / 2).is_empty());
+        assert_eq!(drain(&mut q), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_max_dary_heap() {
+        for arity in 2..=5 {
+            let mut q = PriorityQueue::new(arity, true).unwrap();
+            for v in 0..50 {
+                q.push((v * 17) % 50);
+                assert_heap(&q);
+            }
+            let drained = drain(&mut q);
+            assert_eq!(drained, (0..50).rev().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_heapify_matches_sorted_order() {
+        let values: Vec<i32> = (0..100).map(|v| (v * 37) % 101).collect();
+        let mut q =
+            PriorityQueue::heapify(values.clone(), 3, false).unwrap();
+        assert_heap(&q);
+
+        let mut sorted = values;
+        sorted.sort();
+        assert_eq!(drain(&mut q), sorted);
+    }
+
+    #[test]
+    fn test_push_records_sift_up_swaps() {
+        let mut q = PriorityQueue::new(2, false).unwrap();
+        for v in [1, 3, 5, 7] {
+            q.push(v);
+        }
+        q.push(0);
+
+        // 0 は位置4 → 1 → 0 と上がる
+        assert_eq!(
+            q.heap().events(),
+            &[
+                HeapEvent::SiftUp {
+                    child: 4,
+                    parent: 1,
+                    value: 0,
+                },
+                HeapEvent::SiftUp {
+                    child: 1,
+                    parent: 0,
+                    value: 0,
+                },
+            ]
+        );
+        assert_eq!(q.get_array(), vec![0, 1, 5, 7, 3]);
+    }
+
+    #[test]
+    fn test_pop_records_sift_down_swaps() {
+        let mut q =
+            PriorityQueue::heapify(vec![1, 2, 3, 4, 5], 2, false).unwrap();
+        q.pop();
+
+        // 末尾の5が根に来て、小さい方の子と入れ替わりながら下がる
+        assert!(
+            q.heap().events().iter().all(|e| matches!(
+                e,
+                HeapEvent::SiftDown { value: 5, .. }
+            ))
+        );
+        assert_eq!(q.heap().events().len(), 2);
+        assert_heap(&q);
+    }
+
+    #[test]
+    fn test_decrease_key() {
+        let mut q =
+            PriorityQueue::heapify(vec![1, 4, 2, 8, 9], 2, false).unwrap();
+        let index = q.heap().position(|&v| v == 9).unwrap();
+
+        assert!(!q.decrease_key(index, 10), "key must not increase");
+        assert!(q.decrease_key(index, 0));
+        assert_eq!(q.peek(), Some(0));
+        assert!(!q.heap().events().is_empty());
+        assert_heap(&q);
+    }
+}
@@ -0,0 +1,411 @@
+//! 入力データの読み込みとトレースの書き出しに使う最小限のJSON
+
+use std::collections::BTreeMap;
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+impl JsonValue {
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// 小数部のない数値を整数として取得
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_f64()
+            .filter(|n| n.fract() == 0.0 && n.abs() < 9.0e15)
+            .map(|n| n as i64)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl JsonValue {
+    /// キーと値の組からオブジェクトを作る
+    pub fn object<const N: usize>(
+        entries: [(&str, JsonValue); N],
+    ) -> JsonValue {
+        JsonValue::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    /// JSの値に変換
+    pub fn to_js_value(&self) -> JsValue {
+        js_sys::JSON::parse(&self.to_string()).unwrap_or(JsValue::NULL)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<i32> for JsonValue {
+    fn from(n: i32) -> Self {
+        JsonValue::Number(n as f64)
+    }
+}
+
+impl From<u32> for JsonValue {
+    fn from(n: u32) -> Self {
+        JsonValue::Number(n as f64)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(n: usize) -> Self {
+        JsonValue::Number(n as f64)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(items: Vec<T>) -> Self {
+        JsonValue::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// 空白を入れない1行のJSONとして書き出す
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(b) => write!(f, "{b}"),
+            JsonValue::Number(n) if n.is_finite() => write!(f, "{n}"),
+            JsonValue::Number(_) => f.write_str("null"),
+            JsonValue::String(s) => write_string(f, s),
+            JsonValue::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+            JsonValue::Object(map) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// 配列とオブジェクトの入れ子の深さの上限
+///
+/// パーサは再帰するので、深すぎる入力でスタックがあふれないようにする
+const MAX_DEPTH: usize = 128;
+
+/// JSON文字列をパース
+pub fn parse(text: &str) -> Result<JsonValue, String> {
+    let mut parser = Parser {
+        chars: text.char_indices().peekable(),
+        text,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.peek() {
+        None => Ok(value),
+        Some(&(pos, _)) => Err(format!("trailing data at {pos}")),
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    text: &'a str,
+
+    /// 今読んでいる配列とオブジェクトの入れ子の深さ
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((pos, c)) => {
+                Err(format!("expected '{expected}' at {pos}, found '{c}'"))
+            }
+            None => Err(format!("expected '{expected}' at end")),
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        let Some(&(pos, c)) = self.chars.peek() else {
+            return Err("unexpected end of input".to_string());
+        };
+        match c {
+            '{' | '[' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(format!("nested too deeply at {pos}"));
+                }
+                self.depth += 1;
+                let value = if c == '{' {
+                    self.object()
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                value
+            }
+            '"' => self.string().map(JsonValue::String),
+            't' => self.literal("true", JsonValue::Bool(true)),
+            'f' => self.literal("false", JsonValue::Bool(false)),
+            'n' => self.literal("null", JsonValue::Null),
+            '-' | '0'..='9' => self.number(),
+            _ => Err(format!("unexpected '{c}' at {pos}")),
+        }
+    }
+
+    fn literal(
+        &mut self,
+        word: &str,
+        value: JsonValue,
+    ) -> Result<JsonValue, String> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let start = self.chars.peek().map_or(0, |&(pos, _)| pos);
+        let mut end = start;
+        while let Some((pos, c)) = self.chars.next_if(|&(_, c)| {
+            c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')
+        }) {
+            end = pos + c.len_utf8();
+        }
+        self.text[start..end]
+            .parse()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("invalid number at {start}"))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err("unterminated string".to_string()),
+                Some((_, '"')) => return Ok(s),
+                Some((pos, '\\')) => s.push(self.escape(pos)?),
+                Some((_, c)) => s.push(c),
+            }
+        }
+    }
+
+    /// バックスラッシュに続くエスケープ文字を読む
+    fn escape(&mut self, pos: usize) -> Result<char, String> {
+        let c = match self.chars.next() {
+            Some((_, '"')) => '"',
+            Some((_, '\\')) => '\\',
+            Some((_, '/')) => '/',
+            Some((_, 'n')) => '\n',
+            Some((_, 't')) => '\t',
+            Some((_, 'r')) => '\r',
+            Some((_, 'b')) => '\u{8}',
+            Some((_, 'f')) => '\u{c}',
+            Some((_, 'u')) => {
+                let hex: String = (0..4)
+                    .filter_map(|_| self.chars.next())
+                    .map(|(_, c)| c)
+                    .collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or('\u{fffd}')
+            }
+            _ => return Err(format!("invalid escape at {pos}")),
+        };
+        Ok(c)
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|&(_, c)| c == ']').is_some() {
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(JsonValue::Array(items)),
+                Some((pos, c)) => {
+                    return Err(format!("unexpected '{c}' at {pos}"));
+                }
+                None => return Err("unterminated array".to_string()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut map = BTreeMap::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|&(_, c)| c == '}').is_some() {
+            return Ok(JsonValue::Object(map));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(key, value);
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(JsonValue::Object(map)),
+                Some((pos, c)) => {
+                    return Err(format!("unexpected '{c}' at {pos}"));
+                }
+                None => return Err("unterminated object".to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_values() {
+        let v = parse(r#" {"a": [1, -2.5, true, null], "b": "x\"yA"} "#)
+            .unwrap();
+        let a = v.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a[0].as_i64(), Some(1));
+        assert_eq!(a[1].as_f64(), Some(-2.5));
+        assert_eq!(a[1].as_i64(), None);
+        assert_eq!(a[2].as_bool(), Some(true));
+        assert_eq!(a[3], JsonValue::Null);
+        assert_eq!(
+            v.get("b"),
+            Some(&JsonValue::String("x\"yA".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("[1, 2").is_err());
+        assert!(parse(r#"{"a" 1}"#).is_err());
+        assert!(parse("[1] 2").is_err());
+        assert!(parse("tru").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_deep_nesting() {
+        let nested = |n| format!("{}{}", "[".repeat(n), "]".repeat(n));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(MAX_DEPTH + 1)),
+            Err(format!("nested too deeply at {MAX_DEPTH}"))
+        );
+        assert!(parse(&"[".repeat(1_000_000)).is_err());
+        assert!(parse(&r#"{"a":"#.repeat(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let v = JsonValue::object([
+            ("name", "a\"b\\c\n".into()),
+            ("keys", vec![1, -2].into()),
+            ("half", JsonValue::Number(0.5)),
+            ("none", None::<i32>.into()),
+            ("ok", true.into()),
+        ]);
+        let text = v.to_string();
+        assert_eq!(
+            text,
+            r#"{"half":0.5,"keys":[1,-2],"name":"a\"b\\c\n","none":null,"ok":true}"#
+        );
+        assert_eq!(parse(&text).unwrap(), v);
+    }
+}
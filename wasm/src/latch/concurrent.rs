@@ -0,0 +1,212 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+type Link = Arc<RwLock<Node>>;
+
+#[derive(Debug)]
+struct Node {
+    keys: Vec<i32>,
+    children: Vec<Link>,
+    leaf: bool,
+}
+
+impl Node {
+    fn new(leaf: bool) -> Self {
+        Node {
+            keys: Vec::new(),
+            children: Vec::new(),
+            leaf,
+        }
+    }
+
+    fn link(self) -> Link {
+        Arc::new(RwLock::new(self))
+    }
+}
+
+/// 各ノードを`RwLock`で守り、スレッドから同時に使えるB-Tree (ネイティブ版のみ)
+///
+/// 探索は読み込みロックを、挿入は書き込みロックを根から順に取り、
+/// 子のロックを取ってから親のロックを放す。挿入は満杯の子を
+/// 下りる前に分割するので、親が要るのはその子を分割する間だけでよい。
+/// 根の付け替えは根へのポインタの書き込みロックを持って行う
+#[derive(Debug)]
+pub struct ConcurrentBTree {
+    t: usize,
+    root: RwLock<Link>,
+}
+
+impl ConcurrentBTree {
+    pub fn new(t: usize) -> Self {
+        assert!(t >= 2, "min degree must be at least 2");
+        ConcurrentBTree {
+            t,
+            root: RwLock::new(Node::new(true).link()),
+        }
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        let root = self.root.read().unwrap();
+        let node = Arc::clone(&root);
+        let guard = node.read().unwrap();
+        drop(root);
+        Self::search_from(guard, k)
+    }
+
+    fn search_from(guard: RwLockReadGuard<'_, Node>, k: i32) -> bool {
+        let i = guard.keys.partition_point(|&x| x < k);
+        if i < guard.keys.len() && guard.keys[i] == k {
+            return true;
+        }
+        if guard.leaf {
+            return false;
+        }
+        let child = Arc::clone(&guard.children[i]);
+        let next = child.read().unwrap();
+        drop(guard);
+        Self::search_from(next, k)
+    }
+
+    pub fn insert(&self, k: i32) {
+        let mut root = self.root.write().unwrap();
+        let node = Arc::clone(&root);
+        let mut guard = node.write().unwrap();
+        if guard.keys.len() < 2 * self.t - 1 {
+            drop(root);
+            return self.insert_from(guard, k);
+        }
+
+        // 新しい根の下で古い根を分割する
+        let mut s = Node::new(false);
+        s.children.push(Arc::clone(&node));
+        let s = s.link();
+        let mut s_guard = s.write().unwrap();
+        self.split_child(&mut s_guard, 0, &mut guard);
+        *root = Arc::clone(&s);
+        drop(guard);
+        drop(root);
+        self.insert_from(s_guard, k)
+    }
+
+    /// 満杯でないノードguardの下にkを入れる
+    fn insert_from(&self, mut guard: RwLockWriteGuard<'_, Node>, k: i32) {
+        let mut i = guard.keys.partition_point(|&x| x <= k);
+        if guard.leaf {
+            guard.keys.insert(i, k);
+            return;
+        }
+        let child = Arc::clone(&guard.children[i]);
+        let mut child_guard = child.write().unwrap();
+        if child_guard.keys.len() < 2 * self.t - 1 {
+            drop(guard);
+            return self.insert_from(child_guard, k);
+        }
+
+        self.split_child(&mut guard, i, &mut child_guard);
+        if guard.keys[i] < k {
+            i += 1;
+        }
+        // 分割でできた右のノードは、親を放すまで誰も見つけられない
+        let next = Arc::clone(&guard.children[i]);
+        if !Arc::ptr_eq(&next, &child) {
+            drop(child_guard);
+            let next_guard = next.write().unwrap();
+            drop(guard);
+            return self.insert_from(next_guard, k);
+        }
+        drop(guard);
+        self.insert_from(child_guard, k)
+    }
+
+    fn split_child(&self, parent: &mut Node, i: usize, child: &mut Node) {
+        let t = self.t;
+        let mut z = Node::new(child.leaf);
+        z.keys = child.keys.split_off(t);
+        if !child.leaf {
+            z.children = child.children.split_off(t);
+        }
+        let middle_key = child.keys.pop().unwrap();
+        parent.children.insert(i + 1, z.link());
+        parent.keys.insert(i, middle_key);
+    }
+
+    /// 深さごとのノードのキー (根から順に、各段は左から)
+    ///
+    /// 他のスレッドが操作していないときに呼ぶ
+    pub fn levels(&self) -> Vec<Vec<Vec<i32>>> {
+        let mut levels = Vec::new();
+        let mut nodes = vec![Arc::clone(&self.root.read().unwrap())];
+        while !nodes.is_empty() {
+            let guards: Vec<_> =
+                nodes.iter().map(|n| n.read().unwrap()).collect();
+            levels.push(guards.iter().map(|g| g.keys.clone()).collect());
+            let next = guards
+                .iter()
+                .flat_map(|g| g.children.iter().cloned())
+                .collect();
+            drop(guards);
+            nodes = next;
+        }
+        levels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::BTree;
+    use std::thread;
+
+    #[test]
+    fn test_single_thread_matches_btree() {
+        let tree = ConcurrentBTree::new(2);
+        let mut btree = BTree::new(2);
+        for k in [5, 3, 8, 3, 1, 9, 7, 2, 6, 4, 8, 0] {
+            tree.insert(k);
+            btree.insert(k);
+            assert_eq!(tree.levels(), btree.levels());
+        }
+        assert!(tree.search(7));
+        assert!(!tree.search(10));
+    }
+
+    #[test]
+    fn test_threads_insert_and_search() {
+        const WRITERS: i32 = 4;
+        const PER_WRITER: i32 = 500;
+        let tree = Arc::new(ConcurrentBTree::new(3));
+
+        let mut handles = Vec::new();
+        for w in 0..WRITERS {
+            let tree = Arc::clone(&tree);
+            handles.push(thread::spawn(move || {
+                for i in 0..PER_WRITER {
+                    tree.insert(i * WRITERS + w);
+                    // 自分が入れたキーはすぐに見つかる
+                    assert!(tree.search(i * WRITERS + w));
+                }
+            }));
+        }
+        for _ in 0..2 {
+            let tree = Arc::clone(&tree);
+            handles.push(thread::spawn(move || {
+                for k in 0..WRITERS * PER_WRITER {
+                    tree.search(k);
+                    assert!(!tree.search(-1 - k));
+                }
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let levels = tree.levels();
+        let keys: Vec<i32> = levels.last().unwrap().concat();
+        let total: usize = levels.iter().flatten().map(|n| n.len()).sum();
+        assert_eq!(total, (WRITERS * PER_WRITER) as usize);
+        for level in &levels[1..] {
+            assert!(level.iter().all(|n| (2..=5).contains(&n.len())));
+        }
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!((0..WRITERS * PER_WRITER).all(|k| tree.search(k)));
+    }
+}
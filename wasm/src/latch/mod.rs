@@ -0,0 +1,11 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod concurrent;
+mod operation;
+mod table;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use concurrent::ConcurrentBTree;
+pub use operation::{
+    LatchAction, LatchEvent, LatchSim, Outcome, Protocol, TxnOp,
+};
+pub use table::{LatchMode, LatchTable, ROOT_LATCH};
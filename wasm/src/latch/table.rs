@@ -0,0 +1,100 @@
+use crate::json::JsonValue;
+use crate::trace::NodeId;
+use std::collections::HashMap;
+
+/// 根へのポインタを守るラッチ
+///
+/// 根の付け替え (高さの増減) はこのラッチを書き込みで取ってから行う
+pub const ROOT_LATCH: NodeId = NodeId(0);
+
+/// ラッチのモード
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatchMode {
+    /// 共有 (読み込み)
+    Read,
+
+    /// 排他 (書き込み)
+    Write,
+}
+
+impl LatchMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            LatchMode::Read => "read",
+            LatchMode::Write => "write",
+        }
+    }
+}
+
+impl From<LatchMode> for JsonValue {
+    fn from(mode: LatchMode) -> Self {
+        mode.name().into()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Latch {
+    readers: Vec<usize>,
+    writer: Option<usize>,
+}
+
+/// ノードごとの読み書きラッチ
+///
+/// 待ち行列は持たず、取れるかどうかだけを答える
+#[derive(Clone, Debug, Default)]
+pub struct LatchTable {
+    latches: HashMap<NodeId, Latch>,
+}
+
+impl LatchTable {
+    /// txnがnodeをmodeで取るのを妨げているトランザクション
+    ///
+    /// 自分だけが読み込みで持っていれば、書き込みへ格上げできる
+    pub fn blockers(
+        &self,
+        node: NodeId,
+        mode: LatchMode,
+        txn: usize,
+    ) -> Vec<usize> {
+        let Some(latch) = self.latches.get(&node) else {
+            return Vec::new();
+        };
+        let mut blockers: Vec<usize> =
+            latch.writer.filter(|&w| w != txn).into_iter().collect();
+        if mode == LatchMode::Write {
+            blockers.extend(latch.readers.iter().filter(|&&r| r != txn));
+        }
+        blockers
+    }
+
+    /// ラッチを取る (取れることは呼び出し側が確かめる)
+    pub fn acquire(&mut self, node: NodeId, mode: LatchMode, txn: usize) {
+        debug_assert!(self.blockers(node, mode, txn).is_empty());
+        let latch = self.latches.entry(node).or_default();
+        match mode {
+            LatchMode::Read if latch.writer == Some(txn) => {}
+            LatchMode::Read => latch.readers.push(txn),
+            LatchMode::Write => {
+                latch.readers.retain(|&r| r != txn);
+                latch.writer = Some(txn);
+            }
+        }
+    }
+
+    pub fn release(&mut self, node: NodeId, txn: usize) {
+        if let Some(latch) = self.latches.get_mut(&node) {
+            latch.readers.retain(|&r| r != txn);
+            if latch.writer == Some(txn) {
+                latch.writer = None;
+            }
+            if latch.readers.is_empty() && latch.writer.is_none() {
+                self.latches.remove(&node);
+            }
+        }
+    }
+
+    /// 誰かがラッチを持っているノードの数
+    pub fn held(&self) -> usize {
+        self.latches.len()
+    }
+}
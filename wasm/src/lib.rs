@@ -1,3 +1,88 @@
+mod avl;
+#[cfg(not(target_arch = "wasm32"))]
+mod bench;
+mod blink;
+mod bstar;
 mod btree;
+mod correspondence;
+#[cfg(not(target_arch = "wasm32"))]
+mod disk;
+mod graph;
+mod hashtable;
+mod heap;
+mod json;
+mod latch;
+mod locale;
+mod paged;
+mod persistent;
+mod rangetree;
+mod rbtree;
+mod rng;
+mod script;
+mod skiplist;
+mod sort;
+mod trace;
+mod trie;
+mod wal;
+mod workload;
 
-pub use btree::{BTree, BTreeNode};
+pub use avl::{AvlEvent, AvlNode, AvlTree, RotationCase};
+#[cfg(not(target_arch = "wasm32"))]
+pub use bench::{
+    AllocCounts, BenchResult, CountingAlloc, Subject, Workload,
+    run as run_bench, run_suite, to_csv as bench_to_csv,
+    to_json as bench_to_json,
+};
+pub use blink::{
+    BLinkNode, BLinkRace, BLinkStep, BLinkTree, InsertCursor, PageId,
+    RaceStep, SearchCursor,
+};
+pub use bstar::{BStarNode, BStarTree, FillComparison, compare_fill};
+pub use btree::{
+    BTree, BTreeEvent, BTreeNode, FillStats, MAX_MIN_DEGREE,
+    OperationStats, SplitStrategy, StrategyComparison, TreeStats,
+    compare_strategies,
+};
+pub use correspondence::{
+    AlignedStep, Correspondence, EventPair, Operation, btree_to_rbtree,
+    rbtree_to_btree,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use disk::{DiskBTree, DiskNode, FORMAT_VERSION, FileHeader};
+pub use graph::{
+    Edge, Graph, GraphEvent, GraphRun, MstEvent, MstRun, UnionFind,
+    UnionFindEvent,
+};
+pub use hashtable::{
+    HashEvent, HashFunction, HashTable, MAX_CAPACITY, Slot, Strategy,
+};
+pub use heap::{DaryHeap, HeapEvent, HeapOrder, MAX_ARITY, PriorityQueue};
+#[cfg(not(target_arch = "wasm32"))]
+pub use latch::ConcurrentBTree;
+pub use latch::{
+    LatchAction, LatchEvent, LatchMode, LatchSim, LatchTable, Outcome,
+    Protocol, ROOT_LATCH, TxnOp,
+};
+pub use locale::{Locale, Message};
+pub use paged::{
+    BufferPool, IoComparison, IoStats, PAGE_HEADER_SIZE, PagedBTree,
+    compare_io, page_size,
+};
+pub use persistent::{PNode, PersistentBTree, Version};
+pub use rangetree::{Aggregate, FenwickTree, RangeTrace, SegmentTree};
+pub use rbtree::{Color, RbEvent, RbShape, RedBlackTree};
+pub use script::{Command, Script, ScriptRun, ScriptStep};
+pub use skiplist::{PathStep, SkipList};
+pub use sort::{PivotStrategy, SortAlgorithm, SortRun, SortStep, sort};
+pub use trace::{
+    NodeId, NodeIds, NoopTracer, TRACE_VERSION, TraceEvent, Tracer,
+    trace_to_json, trace_version,
+};
+pub use trie::{RadixNode, RadixTree, Trie, TrieEvent, TrieNode};
+pub use wal::{
+    Checkpoint, LogRecord, Page, PageImage, Recovery, Wal, WalBTree,
+    crash_after_every_record,
+};
+pub use workload::{
+    MAX_KEY_RANGE, MAX_OPS, Mix, Pattern, WorkloadGenerator, WorkloadOp,
+};
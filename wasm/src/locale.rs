@@ -0,0 +1,245 @@
+//! 説明文の言語と、言語に依存しない説明文
+
+use std::str::FromStr;
+
+/// 説明文の言語
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Ja,
+    En,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ja" => Locale::Ja,
+            "en" => Locale::En,
+            _ => return Err(format!("unsupported locale: {s}")),
+        })
+    }
+}
+
+/// ノードのキーを [10|20|30] の形で書く
+pub fn format_keys(keys: &[i32]) -> String {
+    let keys: Vec<String> = keys.iter().map(i32::to_string).collect();
+    format!("[{}]", keys.join("|"))
+}
+
+/// アルゴリズムが何をしたかの説明
+///
+/// 事実だけを持ち、文にするのは`text`で言語を決めたとき
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// 訳さずにそのまま使う文
+    Text(String),
+
+    Insert {
+        key: i32,
+    },
+    Delete {
+        key: i32,
+    },
+    Search {
+        key: i32,
+    },
+    Found {
+        key: i32,
+    },
+    NotFound {
+        key: i32,
+    },
+
+    /// 満杯のノードkeysを中央のキーmedianで分割
+    Split {
+        keys: Vec<i32>,
+        median: i32,
+    },
+
+    /// 根が満杯なので新しい根を作る
+    GrowRoot,
+
+    /// 根が空になり、唯一の子が根になる
+    ShrinkRoot,
+
+    Merge {
+        left: Vec<i32>,
+        separator: i32,
+        right: Vec<i32>,
+    },
+    BorrowFromPrev {
+        parent_key: i32,
+        sibling_key: i32,
+    },
+    BorrowFromNext {
+        parent_key: i32,
+        sibling_key: i32,
+    },
+    ReplaceWithPredecessor {
+        key: i32,
+        predecessor: i32,
+    },
+    ReplaceWithSuccessor {
+        key: i32,
+        successor: i32,
+    },
+}
+
+impl Message {
+    pub fn text(&self, locale: Locale) -> String {
+        use Locale::{En, Ja};
+        match (self, locale) {
+            (Message::Text(text), _) => text.clone(),
+
+            (Message::Insert { key }, Ja) => format!("キー {key} を挿入します"),
+            (Message::Insert { key }, En) => format!("Inserting key {key}"),
+            (Message::Delete { key }, Ja) => format!("キー {key} を削除します"),
+            (Message::Delete { key }, En) => format!("Deleting key {key}"),
+            (Message::Search { key }, Ja) => format!("キー {key} を検索します"),
+            (Message::Search { key }, En) => {
+                format!("Searching for key {key}")
+            }
+            (Message::Found { key }, Ja) => {
+                format!("キー {key} が見つかりました")
+            }
+            (Message::Found { key }, En) => format!("Found key {key}"),
+            (Message::NotFound { key }, Ja) => {
+                format!("キー {key} は見つかりません")
+            }
+            (Message::NotFound { key }, En) => {
+                format!("Key {key} was not found")
+            }
+
+            (Message::Split { keys, median }, Ja) => format!(
+                "ノード {} は満杯なので、中央のキー {median} で分割します",
+                format_keys(keys)
+            ),
+            (Message::Split { keys, median }, En) => format!(
+                "Node {} is full; splitting at median {median}",
+                format_keys(keys)
+            ),
+            (Message::GrowRoot, Ja) => {
+                "根が満杯なので新しい根を作ります (木が1段高くなります)"
+                    .into()
+            }
+            (Message::GrowRoot, En) => {
+                "The root is full; creating a new root (the tree grows by one level)"
+                    .into()
+            }
+            (Message::ShrinkRoot, Ja) => {
+                "根が空になったので、唯一の子が新しい根になります".into()
+            }
+            (Message::ShrinkRoot, En) => {
+                "The root is empty; its only child becomes the new root"
+                    .into()
+            }
+
+            (
+                Message::Merge {
+                    left,
+                    separator,
+                    right,
+                },
+                Ja,
+            ) => format!(
+                "ノード {} と {} を区切りのキー {separator} とともにマージします",
+                format_keys(left),
+                format_keys(right)
+            ),
+            (
+                Message::Merge {
+                    left,
+                    separator,
+                    right,
+                },
+                En,
+            ) => format!(
+                "Merging {} and {} around separator {separator}",
+                format_keys(left),
+                format_keys(right)
+            ),
+            (
+                Message::BorrowFromPrev {
+                    parent_key,
+                    sibling_key,
+                },
+                Ja,
+            ) => format!(
+                "左の兄弟から借ります: 親のキー {parent_key} が子へ下り、兄弟のキー {sibling_key} が親へ上がります"
+            ),
+            (
+                Message::BorrowFromPrev {
+                    parent_key,
+                    sibling_key,
+                },
+                En,
+            ) => format!(
+                "Borrowing from the left sibling: parent key {parent_key} moves down and sibling key {sibling_key} moves up"
+            ),
+            (
+                Message::BorrowFromNext {
+                    parent_key,
+                    sibling_key,
+                },
+                Ja,
+            ) => format!(
+                "右の兄弟から借ります: 親のキー {parent_key} が子へ下り、兄弟のキー {sibling_key} が親へ上がります"
+            ),
+            (
+                Message::BorrowFromNext {
+                    parent_key,
+                    sibling_key,
+                },
+                En,
+            ) => format!(
+                "Borrowing from the right sibling: parent key {parent_key} moves down and sibling key {sibling_key} moves up"
+            ),
+            (Message::ReplaceWithPredecessor { key, predecessor }, Ja) => {
+                format!("内部ノードのキー {key} を前駆 {predecessor} で置き換えます")
+            }
+            (Message::ReplaceWithPredecessor { key, predecessor }, En) => {
+                format!(
+                    "Replacing internal key {key} with its predecessor {predecessor}"
+                )
+            }
+            (Message::ReplaceWithSuccessor { key, successor }, Ja) => {
+                format!("内部ノードのキー {key} を後継 {successor} で置き換えます")
+            }
+            (Message::ReplaceWithSuccessor { key, successor }, En) => {
+                format!(
+                    "Replacing internal key {key} with its successor {successor}"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        let m = Message::Split {
+            keys: vec![10, 20, 30],
+            median: 20,
+        };
+        assert_eq!(
+            m.text(Locale::En),
+            "Node [10|20|30] is full; splitting at median 20"
+        );
+        assert_eq!(
+            m.text(Locale::Ja),
+            "ノード [10|20|30] は満杯なので、中央のキー 20 で分割します"
+        );
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("en".parse(), Ok(Locale::En));
+        assert_eq!("ja".parse(), Ok(Locale::Ja));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+}
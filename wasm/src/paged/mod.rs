@@ -0,0 +1,7 @@
+mod operation;
+mod pool;
+
+pub use operation::{
+    IoComparison, PAGE_HEADER_SIZE, PagedBTree, compare_io, page_size,
+};
+pub use pool::{BufferPool, IoStats};
@@ -0,0 +1,234 @@
+use crate::btree::BTree;
+use crate::paged::pool::{BufferPool, IoStats};
+use wasm_bindgen::prelude::*;
+
+/// ページの見出し (キー数 u32、葉かどうか u8、予備) の大きさ
+pub const PAGE_HEADER_SIZE: usize = 8;
+
+/// 最小次数tのノードを1つ収めるページの大きさ (バイト)
+///
+/// キー (i32) が最大2t-1個、子のページ番号 (u32) が最大2t個
+pub fn page_size(t: usize) -> usize {
+    PAGE_HEADER_SIZE + (2 * t - 1) * 4 + 2 * t * 4
+}
+
+/// 各ノードを固定長のページに置き、ディスク上にあるとみなしたB-Tree
+///
+/// ページはLRUのバッファプールを通して読み書きし、その回数を数える
+#[wasm_bindgen]
+pub struct PagedBTree {
+    tree: BTree,
+    pool: BufferPool,
+
+    // 直前の操作のI/O
+    last: IoStats,
+}
+
+#[wasm_bindgen]
+impl PagedBTree {
+    /// 最小次数t、バッファプールにpool_pagesページ
+    #[wasm_bindgen(constructor)]
+    pub fn new(t: usize, pool_pages: usize) -> Result<PagedBTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(PagedBTree {
+            tree: BTree::new(t),
+            pool: BufferPool::new(pool_pages)?,
+            last: IoStats::default(),
+        })
+    }
+
+    pub fn insert(&mut self, k: i32) {
+        self.measure(|tree, pool| tree.insert_with(k, pool));
+    }
+
+    pub fn delete(&mut self, k: i32) -> bool {
+        self.measure(|tree, pool| tree.delete_with(k, pool))
+    }
+
+    pub fn search(&mut self, k: i32) -> bool {
+        self.measure(|tree, pool| tree.search_with(k, pool))
+    }
+
+    /// 汚れたページを全て書き戻す
+    pub fn flush(&mut self) {
+        self.measure(|_, pool| pool.flush());
+    }
+
+    /// 直前の操作のI/O
+    #[wasm_bindgen]
+    pub fn get_last_io(&self) -> IoStats {
+        self.last
+    }
+
+    /// 作成してからのI/Oの合計
+    #[wasm_bindgen]
+    pub fn get_total_io(&self) -> IoStats {
+        self.pool.stats()
+    }
+
+    /// バッファプールにあるページのノードID (最近使った順が後ろ)
+    #[wasm_bindgen]
+    pub fn get_cached_pages(&self) -> Vec<u32> {
+        self.pool.pages().iter().map(|id| id.0).collect()
+    }
+
+    /// 書き戻していないページのノードID
+    #[wasm_bindgen]
+    pub fn get_dirty_pages(&self) -> Vec<u32> {
+        self.pool.dirty_pages().iter().map(|id| id.0).collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_page_size(&self) -> usize {
+        page_size(self.tree.get_min_degree())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_pool_size(&self) -> usize {
+        self.pool.capacity()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_height(&self) -> usize {
+        self.tree.get_height()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        self.tree.get_structure()
+    }
+}
+
+impl PagedBTree {
+    fn measure<R>(
+        &mut self,
+        op: impl FnOnce(&mut BTree, &mut BufferPool) -> R,
+    ) -> R {
+        let before = self.pool.stats();
+        self.pool.begin();
+        let result = op(&mut self.tree, &mut self.pool);
+        self.last = self.pool.stats() - before;
+        result
+    }
+
+    pub fn tree(&self) -> &BTree {
+        &self.tree
+    }
+}
+
+/// 次数ごとのI/Oの比較
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoComparison {
+    pub t: usize,
+    pub page_size: usize,
+    pub height: usize,
+
+    /// 全てのキーの挿入と書き戻し
+    pub load: IoStats,
+
+    /// 続けて全てのキーを探索
+    pub lookup: IoStats,
+}
+
+/// 各次数tについて、keysを挿入してから全て探索したときのI/Oを比べる
+#[wasm_bindgen]
+pub fn compare_io(
+    ts: Vec<usize>,
+    pool_pages: usize,
+    keys: Vec<i32>,
+) -> Result<Vec<IoComparison>, String> {
+    ts.into_iter()
+        .map(|t| {
+            let mut tree = PagedBTree::new(t, pool_pages)?;
+            for &k in &keys {
+                tree.insert(k);
+            }
+            tree.flush();
+            let load = tree.get_total_io();
+            for &k in &keys {
+                tree.search(k);
+            }
+            Ok(IoComparison {
+                t,
+                page_size: tree.get_page_size(),
+                height: tree.get_height(),
+                load,
+                lookup: tree.get_total_io() - load,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_page_io_of_single_operations() {
+        let mut tree = PagedBTree::new(2, 2).unwrap();
+        tree.insert(1);
+        // 根のページを新しく作るだけ
+        assert_eq!(
+            tree.get_last_io(),
+            IoStats {
+                reads: 0,
+                writes: 0,
+                hits: 0,
+                misses: 0,
+            }
+        );
+        tree.flush();
+        assert_eq!(tree.get_last_io().writes, 1);
+        assert!(tree.get_dirty_pages().is_empty());
+
+        for k in 2..=4 {
+            tree.insert(k);
+        }
+        // 根 [2] と子 [1], [3|4] の3ページは2ページのプールに収まらない
+        assert_eq!(tree.get_height(), 2);
+        assert_eq!(tree.get_cached_pages().len(), 2);
+
+        assert!(tree.search(1));
+        let io = tree.get_last_io();
+        assert_eq!(io.hits + io.misses, 2);
+        assert_eq!(io.reads, io.misses);
+
+        // 直後に同じ経路をたどれば全てプールにある
+        assert!(tree.search(1));
+        assert_eq!(
+            tree.get_last_io(),
+            IoStats {
+                reads: 0,
+                writes: 0,
+                hits: 2,
+                misses: 0,
+            }
+        );
+        assert!(!tree.delete(9));
+        assert!(tree.delete(4));
+        assert!(!tree.search(4));
+    }
+
+    #[test]
+    fn test_larger_t_means_fewer_reads() {
+        let mut rng = SplitMix64::new(7);
+        let keys: Vec<i32> = (0..2000)
+            .map(|_| (rng.next_u64() % 100_000) as i32)
+            .collect();
+        let runs = compare_io(vec![2, 4, 16, 64], 8, keys).unwrap();
+        for pair in runs.windows(2) {
+            assert!(pair[0].height >= pair[1].height);
+            assert!(pair[0].page_size < pair[1].page_size);
+            assert!(
+                pair[0].lookup.reads > pair[1].lookup.reads,
+                "{pair:?}"
+            );
+        }
+        assert_eq!(runs[0].page_size, 8 + 3 * 4 + 4 * 4);
+        assert!(compare_io(vec![1], 8, vec![]).is_err());
+    }
+}
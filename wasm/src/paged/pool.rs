@@ -0,0 +1,185 @@
+use crate::trace::{NodeId, TraceEvent, Tracer};
+use std::collections::{HashMap, HashSet};
+use std::ops::Sub;
+use wasm_bindgen::prelude::*;
+
+/// ページのI/Oの回数
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// ディスクから読んだページ数
+    pub reads: usize,
+
+    /// ディスクへ書いたページ数
+    pub writes: usize,
+
+    /// バッファプールにあったページの要求
+    pub hits: usize,
+
+    /// バッファプールになかったページの要求
+    pub misses: usize,
+}
+
+impl Sub for IoStats {
+    type Output = IoStats;
+
+    fn sub(self, rhs: IoStats) -> IoStats {
+        IoStats {
+            reads: self.reads - rhs.reads,
+            writes: self.writes - rhs.writes,
+            hits: self.hits - rhs.hits,
+            misses: self.misses - rhs.misses,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    dirty: bool,
+    last_used: u64,
+}
+
+/// LRUで追い出すバッファプール
+///
+/// 汚れたページは追い出すときか`flush`のときに書き戻す。
+/// 1つの操作の中で同じページを何度触っても、要求は最初の1回と数える
+#[derive(Clone, Debug)]
+pub struct BufferPool {
+    capacity: usize,
+    frames: HashMap<NodeId, Frame>,
+    clock: u64,
+    stats: IoStats,
+
+    // 今の操作ですでに要求したページ
+    fixed: HashSet<NodeId>,
+}
+
+impl BufferPool {
+    pub fn new(capacity: usize) -> Result<Self, String> {
+        if capacity == 0 {
+            return Err("buffer pool needs at least one page".into());
+        }
+        Ok(BufferPool {
+            capacity,
+            frames: HashMap::new(),
+            clock: 0,
+            stats: IoStats::default(),
+            fixed: HashSet::new(),
+        })
+    }
+
+    /// 新しい操作を始める
+    pub fn begin(&mut self) {
+        self.fixed.clear();
+    }
+
+    /// ページpageを要求する
+    ///
+    /// createdなら新しく作ったページなので、なくても読み込まない
+    pub fn fix(&mut self, page: NodeId, dirty: bool, created: bool) {
+        self.clock += 1;
+        if let Some(frame) = self.frames.get_mut(&page) {
+            if self.fixed.insert(page) {
+                self.stats.hits += 1;
+            }
+            frame.dirty |= dirty;
+            frame.last_used = self.clock;
+            return;
+        }
+
+        if !created {
+            self.stats.misses += 1;
+            self.stats.reads += 1;
+        }
+        if self.frames.len() == self.capacity {
+            self.evict();
+        }
+        self.fixed.insert(page);
+        self.frames.insert(
+            page,
+            Frame {
+                dirty: dirty || created,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// ページを捨てる (消したノードなので書き戻さない)
+    pub fn free(&mut self, page: NodeId) {
+        self.frames.remove(&page);
+        self.fixed.remove(&page);
+    }
+
+    /// 汚れたページを全て書き戻す
+    pub fn flush(&mut self) {
+        for frame in self.frames.values_mut() {
+            if frame.dirty {
+                frame.dirty = false;
+                self.stats.writes += 1;
+            }
+        }
+    }
+
+    fn evict(&mut self) {
+        let victim = self
+            .frames
+            .iter()
+            .min_by_key(|(_, frame)| frame.last_used)
+            .map(|(&page, _)| page);
+        if let Some(frame) = victim.and_then(|p| self.frames.remove(&p)) {
+            if frame.dirty {
+                self.stats.writes += 1;
+            }
+        }
+    }
+
+    /// これまでの合計
+    pub fn stats(&self) -> IoStats {
+        self.stats
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// プールにあるページ (最近使った順が後ろ)
+    pub fn pages(&self) -> Vec<NodeId> {
+        let mut frames: Vec<_> = self.frames.iter().collect();
+        frames.sort_by_key(|(_, frame)| frame.last_used);
+        frames.into_iter().map(|(&page, _)| page).collect()
+    }
+
+    /// 書き戻していないページ
+    pub fn dirty_pages(&self) -> Vec<NodeId> {
+        let mut pages = self.pages();
+        pages.retain(|page| self.frames[page].dirty);
+        pages
+    }
+}
+
+/// トレースの各ステップを、そのノードのページへの要求とみなす
+///
+/// 子へのリンクは親のページだけを書き換える
+impl Tracer for BufferPool {
+    fn record(&mut self, event: TraceEvent) {
+        match event {
+            TraceEvent::Visit { node } => self.fix(node, false, false),
+            TraceEvent::CreateNode { node, .. } => {
+                self.fix(node, true, true)
+            }
+            TraceEvent::DeleteNode { node } => self.free(node),
+            TraceEvent::MoveKey { from, to, .. } => {
+                for page in [from, to].into_iter().flatten() {
+                    self.fix(page, true, false);
+                }
+            }
+            TraceEvent::Link { parent, .. }
+            | TraceEvent::Unlink { parent, .. } => {
+                self.fix(parent, true, false)
+            }
+            TraceEvent::Compare { .. }
+            | TraceEvent::Highlight { .. }
+            | TraceEvent::Annotate { .. } => {}
+        }
+    }
+}
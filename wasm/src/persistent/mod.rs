@@ -0,0 +1,5 @@
+mod node;
+mod operation;
+
+pub use node::PNode;
+pub use operation::{PersistentBTree, Version};
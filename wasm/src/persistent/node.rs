@@ -0,0 +1,253 @@
+use crate::trace::{NodeId, NodeIds};
+use std::rc::Rc;
+
+/// 版の間で共有するノード
+///
+/// 書き換えるときは`make_mut`で、他の版からも見えているノードだけを複製する。
+/// 複製には新しい識別子を付けるので、識別子が同じなら同じノードを共有している
+#[derive(Debug)]
+pub struct PNode {
+    id: NodeId,
+    t: usize,
+    keys: Vec<i32>,
+    children: Vec<Rc<PNode>>,
+    leaf: bool,
+}
+
+/// 他の版と共有していれば新しい識別子で複製してから、書き換えられる参照を返す
+pub(crate) fn make_mut<'a>(
+    node: &'a mut Rc<PNode>,
+    ids: &mut NodeIds,
+) -> &'a mut PNode {
+    if Rc::get_mut(node).is_none() {
+        *node = Rc::new(PNode {
+            id: ids.fresh(),
+            t: node.t,
+            keys: node.keys.clone(),
+            children: node.children.clone(),
+            leaf: node.leaf,
+        });
+    }
+    Rc::get_mut(node).unwrap()
+}
+
+impl PNode {
+    pub fn new(t: usize, leaf: bool, id: NodeId) -> Self {
+        PNode {
+            id,
+            t,
+            keys: Vec::new(),
+            children: Vec::new(),
+            leaf,
+        }
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn keys(&self) -> &[i32] {
+        &self.keys
+    }
+
+    pub fn children(&self) -> &[Rc<PNode>] {
+        &self.children
+    }
+
+    pub fn leaf(&self) -> bool {
+        self.leaf
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.keys.len() == 2 * self.t - 1
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        let i = self.keys.partition_point(|&key| key < k);
+        if i < self.keys.len() && self.keys[i] == k {
+            true
+        } else if self.leaf {
+            false
+        } else {
+            self.children[i].search(k)
+        }
+    }
+
+    /// 新しい根の唯一の子としてrootを置く
+    pub(crate) fn grow(t: usize, root: Rc<PNode>, id: NodeId) -> Self {
+        let mut node = PNode::new(t, false, id);
+        node.children.push(root);
+        node
+    }
+
+    /// i番目の子 (他の版と共有していれば複製する)
+    pub(crate) fn child_mut(
+        &mut self,
+        i: usize,
+        ids: &mut NodeIds,
+    ) -> &mut PNode {
+        make_mut(&mut self.children[i], ids)
+    }
+
+    /// 根が空になったときに根になる子
+    pub(crate) fn only_child(&self) -> Option<Rc<PNode>> {
+        if self.keys.is_empty() && !self.leaf {
+            self.children.first().cloned()
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn insert_not_full(&mut self, k: i32, ids: &mut NodeIds) {
+        // 同じキーの後ろに入れる
+        let mut i = self.keys.partition_point(|&key| key <= k);
+        if self.leaf {
+            self.keys.insert(i, k);
+            return;
+        }
+        if self.children[i].is_full() {
+            self.split_child(i, ids);
+            if self.keys[i] < k {
+                i += 1;
+            }
+        }
+        make_mut(&mut self.children[i], ids).insert_not_full(k, ids);
+    }
+
+    pub(crate) fn split_child(&mut self, i: usize, ids: &mut NodeIds) {
+        let t = self.t;
+        let y = make_mut(&mut self.children[i], ids);
+        let leaf = y.leaf;
+        let mut z = PNode::new(t, leaf, ids.fresh());
+        z.keys = y.keys.split_off(t);
+        if !y.leaf {
+            z.children = y.children.split_off(t);
+        }
+        let middle_key = y.keys.pop().unwrap();
+        self.children.insert(i + 1, Rc::new(z));
+        self.keys.insert(i, middle_key);
+    }
+
+    pub(crate) fn delete(&mut self, k: i32, ids: &mut NodeIds) -> bool {
+        let idx = self.keys.partition_point(|&key| key < k);
+        if idx < self.keys.len() && self.keys[idx] == k {
+            if self.leaf {
+                self.keys.remove(idx);
+                true
+            } else {
+                self.delete_from_internal_node(idx, ids)
+            }
+        } else if self.leaf {
+            false
+        } else {
+            self.delete_from_subtree(idx, k, ids)
+        }
+    }
+
+    fn delete_from_internal_node(
+        &mut self,
+        idx: usize,
+        ids: &mut NodeIds,
+    ) -> bool {
+        if self.children[idx].keys.len() >= self.t {
+            // 前駆で置き換え
+            let predecessor = self.children[idx].last_key();
+            self.keys[idx] = predecessor;
+            make_mut(&mut self.children[idx], ids).delete(predecessor, ids)
+        } else if self.children[idx + 1].keys.len() >= self.t {
+            // 後継で置き換え
+            let successor = self.children[idx + 1].first_key();
+            self.keys[idx] = successor;
+            make_mut(&mut self.children[idx + 1], ids)
+                .delete(successor, ids)
+        } else {
+            let k = self.keys[idx];
+            self.merge_children(idx, ids);
+            make_mut(&mut self.children[idx], ids).delete(k, ids)
+        }
+    }
+
+    fn last_key(&self) -> i32 {
+        match self.children.last() {
+            Some(child) if !self.leaf => child.last_key(),
+            _ => self.keys[self.keys.len() - 1],
+        }
+    }
+
+    fn first_key(&self) -> i32 {
+        match self.children.first() {
+            Some(child) if !self.leaf => child.first_key(),
+            _ => self.keys[0],
+        }
+    }
+
+    fn delete_from_subtree(
+        &mut self,
+        idx: usize,
+        k: i32,
+        ids: &mut NodeIds,
+    ) -> bool {
+        let is_last = idx == self.keys.len();
+        if self.children[idx].keys.len() < self.t {
+            self.fill_child(idx, ids);
+        }
+
+        // 最後の子が左の兄弟にマージされた場合、キーは1つ左の子にある
+        let idx = if is_last && idx > self.keys.len() {
+            idx - 1
+        } else {
+            idx
+        };
+        make_mut(&mut self.children[idx], ids).delete(k, ids)
+    }
+
+    fn fill_child(&mut self, idx: usize, ids: &mut NodeIds) {
+        if idx != 0 && self.children[idx - 1].keys.len() >= self.t {
+            self.borrow_from_prev(idx, ids);
+        } else if idx < self.children.len() - 1
+            && self.children[idx + 1].keys.len() >= self.t
+        {
+            self.borrow_from_next(idx, ids);
+        } else if idx != self.children.len() - 1 {
+            self.merge_children(idx, ids);
+        } else {
+            self.merge_children(idx - 1, ids);
+        }
+    }
+
+    fn borrow_from_prev(&mut self, idx: usize, ids: &mut NodeIds) {
+        let (left, right) = self.children.split_at_mut(idx);
+        let sibling = make_mut(&mut left[idx - 1], ids);
+        let child = make_mut(&mut right[0], ids);
+
+        child.keys.insert(0, self.keys[idx - 1]);
+        if !child.leaf {
+            child.children.insert(0, sibling.children.pop().unwrap());
+        }
+        self.keys[idx - 1] = sibling.keys.pop().unwrap();
+    }
+
+    fn borrow_from_next(&mut self, idx: usize, ids: &mut NodeIds) {
+        let (left, right) = self.children.split_at_mut(idx + 1);
+        let child = make_mut(&mut left[idx], ids);
+        let sibling = make_mut(&mut right[0], ids);
+
+        child.keys.push(self.keys[idx]);
+        if !child.leaf {
+            child.children.push(sibling.children.remove(0));
+        }
+        self.keys[idx] = sibling.keys.remove(0);
+    }
+
+    /// idx番目とidx+1番目の子を区切りのキーとともにマージする
+    ///
+    /// 右の子は書き換えないので複製しない
+    fn merge_children(&mut self, idx: usize, ids: &mut NodeIds) {
+        let sibling = self.children.remove(idx + 1);
+        let key = self.keys.remove(idx);
+        let child = make_mut(&mut self.children[idx], ids);
+        child.keys.push(key);
+        child.keys.extend_from_slice(&sibling.keys);
+        child.children.extend(sibling.children.iter().cloned());
+    }
+}
@@ -0,0 +1,405 @@
+use crate::json::JsonValue;
+use crate::persistent::node::{PNode, make_mut};
+use crate::trace::{NodeId, NodeIds};
+use std::collections::HashSet;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// 木の1つの版
+#[derive(Clone, Debug)]
+pub struct Version {
+    pub root: Option<Rc<PNode>>,
+
+    /// この版を作った操作 (最初の空の版はNone)
+    pub operation: Option<String>,
+
+    /// 版のノード数
+    pub nodes: usize,
+
+    /// そのうち1つ前の版と共有しているノード数
+    pub shared: usize,
+}
+
+impl Version {
+    fn ids(&self) -> HashSet<NodeId> {
+        let mut ids = HashSet::new();
+        let mut stack = Vec::from_iter(self.root.as_deref());
+        while let Some(node) = stack.pop() {
+            ids.insert(node.id());
+            stack.extend(node.children().iter().map(|c| c.as_ref()));
+        }
+        ids
+    }
+
+    pub fn search(&self, k: i32) -> bool {
+        self.root.as_ref().is_some_and(|root| root.search(k))
+    }
+
+    /// 全てのキーを昇順に
+    pub fn keys(&self) -> Vec<i32> {
+        fn walk(node: &PNode, out: &mut Vec<i32>) {
+            for (i, &key) in node.keys().iter().enumerate() {
+                if let Some(child) = node.children().get(i) {
+                    walk(child, out);
+                }
+                out.push(key);
+            }
+            if let Some(child) = node.children().get(node.keys().len()) {
+                walk(child, out);
+            }
+        }
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            walk(root, &mut out);
+        }
+        out
+    }
+}
+
+/// 操作の度に新しい版を作る、書き込み時コピーのB-Tree
+///
+/// 新しい版は根から書き換えたノードまでの経路だけを複製し、
+/// それ以外のノードは前の版と共有する。古い版もそのまま探索できる
+#[wasm_bindgen]
+pub struct PersistentBTree {
+    t: usize,
+    versions: Vec<Version>,
+
+    /// 全ての版で共通の識別子の割り当て
+    ids: NodeIds,
+}
+
+#[wasm_bindgen]
+impl PersistentBTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(t: usize) -> Result<PersistentBTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(PersistentBTree {
+            t,
+            versions: vec![Version {
+                root: None,
+                operation: None,
+                nodes: 0,
+                shared: 0,
+            }],
+            ids: NodeIds::new(),
+        })
+    }
+
+    /// キーkを挿入した新しい版を作り、その番号を返す
+    pub fn insert(&mut self, k: i32) -> usize {
+        let t = self.t;
+        self.derive(format!("insert {k}"), |root, ids| {
+            let node = match root.take() {
+                None => {
+                    let mut node = PNode::new(t, true, ids.fresh());
+                    node.insert_not_full(k, ids);
+                    node
+                }
+                Some(node) if node.is_full() => {
+                    // 新しい根の下で古い根を分割し、どちらかの子に入れる
+                    let mut s = PNode::grow(t, node, ids.fresh());
+                    s.split_child(0, ids);
+                    let i = if s.keys()[0] < k { 1 } else { 0 };
+                    s.child_mut(i, ids).insert_not_full(k, ids);
+                    s
+                }
+                Some(mut node) => {
+                    make_mut(&mut node, ids).insert_not_full(k, ids);
+                    *root = Some(node);
+                    return true;
+                }
+            };
+            *root = Some(Rc::new(node));
+            true
+        })
+        .0
+    }
+
+    /// キーkを削除した新しい版を作る (キーがなくても版は増える)
+    ///
+    /// キーがなければ、前の版と同じ根を持つ版になる
+    pub fn delete(&mut self, k: i32) -> bool {
+        if !self.versions.last().unwrap().search(k) {
+            // 何も書き換えないので、前の版の根をそのまま使う
+            return self.derive(format!("delete {k}"), |_, _| false).1;
+        }
+        self.derive(format!("delete {k}"), |root, ids| {
+            let Some(mut node) = root.take() else {
+                return false;
+            };
+            let deleted = make_mut(&mut node, ids).delete(k, ids);
+            *root = Some(node.only_child().unwrap_or(node));
+            deleted
+        })
+        .1
+    }
+
+    /// 版versionでキーkを探索
+    pub fn search(&self, version: usize, k: i32) -> Result<bool, String> {
+        Ok(self.version(version)?.search(k))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_version_count(&self) -> usize {
+        self.versions.len()
+    }
+
+    /// 版versionのノード数
+    #[wasm_bindgen]
+    pub fn get_node_count(&self, version: usize) -> Result<usize, String> {
+        Ok(self.version(version)?.nodes)
+    }
+
+    /// 版versionが1つ前の版と共有しているノード数
+    #[wasm_bindgen]
+    pub fn get_shared_nodes(
+        &self,
+        version: usize,
+    ) -> Result<usize, String> {
+        Ok(self.version(version)?.shared)
+    }
+
+    /// 版versionを作った操作 ("insert 5" など、最初の版は空文字列)
+    #[wasm_bindgen]
+    pub fn get_operation(&self, version: usize) -> Result<String, String> {
+        Ok(self.version(version)?.operation.clone().unwrap_or_default())
+    }
+
+    /// 版versionの構造 ({id, keys, children, isLeaf, shared})
+    ///
+    /// sharedは1つ前の版と共有しているノードでtrue
+    #[wasm_bindgen]
+    pub fn get_structure(
+        &self,
+        version: usize,
+    ) -> Result<JsValue, String> {
+        let current = self.version(version)?;
+        let previous = match version {
+            0 => HashSet::new(),
+            _ => self.versions[version - 1].ids(),
+        };
+        Ok(match &current.root {
+            Some(root) => node_to_json(root, &previous).to_js_value(),
+            None => JsValue::NULL,
+        })
+    }
+}
+
+fn node_to_json(node: &PNode, previous: &HashSet<NodeId>) -> JsonValue {
+    JsonValue::object([
+        ("id", node.id().into()),
+        ("keys", node.keys().to_vec().into()),
+        (
+            "children",
+            JsonValue::Array(
+                node.children()
+                    .iter()
+                    .map(|c| node_to_json(c, previous))
+                    .collect(),
+            ),
+        ),
+        ("isLeaf", node.leaf().into()),
+        ("shared", previous.contains(&node.id()).into()),
+    ])
+}
+
+impl PersistentBTree {
+    pub fn version(&self, version: usize) -> Result<&Version, String> {
+        self.versions
+            .get(version)
+            .ok_or(format!("no such version: {version}"))
+    }
+
+    pub fn versions(&self) -> &[Version] {
+        &self.versions
+    }
+
+    /// 最新の版の根を書き換えて新しい版を作る
+    ///
+    /// ノード数は両方の版を全て辿らず、書き換えた経路の周りだけから求める
+    fn derive(
+        &mut self,
+        operation: String,
+        op: impl FnOnce(&mut Option<Rc<PNode>>, &mut NodeIds) -> bool,
+    ) -> (usize, bool) {
+        let previous = self.versions.last().unwrap();
+        let first_fresh = self.ids.peek();
+        let mut root = previous.root.clone();
+        let result = op(&mut root, &mut self.ids);
+
+        // 新しいノードだけを辿り、その下に残った前の版の部分木の根を集める
+        let mut fresh = 0;
+        let mut kept = HashSet::new();
+        let mut stack = Vec::from_iter(root.as_deref());
+        while let Some(node) = stack.pop() {
+            if node.id() < first_fresh {
+                kept.insert(node.id());
+            } else {
+                fresh += 1;
+                stack.extend(node.children().iter().map(|c| c.as_ref()));
+            }
+        }
+
+        // 前の版で残った部分木の外にあるノードは、新しい版にはない
+        let mut dropped = 0;
+        let mut stack = Vec::from_iter(previous.root.as_deref());
+        while let Some(node) = stack.pop() {
+            if !kept.contains(&node.id()) {
+                dropped += 1;
+                stack.extend(node.children().iter().map(|c| c.as_ref()));
+            }
+        }
+
+        let nodes = previous.nodes - dropped + fresh;
+        self.versions.push(Version {
+            root,
+            operation: Some(operation),
+            nodes,
+            shared: nodes - fresh,
+        });
+        (self.versions.len() - 1, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::BTree;
+    use crate::rng::SplitMix64;
+
+    fn levels(root: Option<&PNode>) -> Vec<Vec<Vec<i32>>> {
+        let mut levels = Vec::new();
+        let mut nodes: Vec<&PNode> = root.into_iter().collect();
+        while !nodes.is_empty() {
+            levels.push(nodes.iter().map(|n| n.keys().to_vec()).collect());
+            nodes = nodes
+                .iter()
+                .flat_map(|n| n.children().iter().map(|c| c.as_ref()))
+                .collect();
+        }
+        levels
+    }
+
+    #[test]
+    fn test_old_versions_stay_queryable() {
+        let mut rng = SplitMix64::new(5);
+        let mut tree = PersistentBTree::new(2).unwrap();
+        let mut btree = BTree::new(2);
+        let mut expected = vec![Vec::new()];
+        for _ in 0..300 {
+            let k = (rng.next_u64() % 40) as i32;
+            let mut keys = expected.last().unwrap().clone();
+            if rng.chance(0.4) {
+                // キーがなければ木を書き換えないので、BTreeからも消さない
+                let deleted = tree.delete(k);
+                assert_eq!(deleted, btree.search(k));
+                if deleted {
+                    btree.delete(k);
+                }
+                if let Some(pos) = keys.iter().position(|&x| x == k) {
+                    keys.remove(pos);
+                }
+            } else {
+                tree.insert(k);
+                btree.insert(k);
+                keys.push(k);
+                keys.sort();
+            }
+            expected.push(keys);
+
+            // 手順はBTreeと同じなので同じ形になる
+            let latest = tree.versions().last().unwrap();
+            assert_eq!(levels(latest.root.as_deref()), btree.levels());
+        }
+
+        for (i, keys) in expected.iter().enumerate() {
+            assert_eq!(&tree.version(i).unwrap().keys(), keys, "v{i}");
+            for k in 0..40 {
+                assert_eq!(tree.search(i, k), Ok(keys.contains(&k)));
+            }
+        }
+        assert!(tree.search(expected.len(), 0).is_err());
+    }
+
+    #[test]
+    fn test_insert_copies_only_the_path() {
+        let mut rng = SplitMix64::new(9);
+        let mut tree = PersistentBTree::new(3).unwrap();
+        let mut checked = 0;
+        for _ in 0..500 {
+            let before = tree.versions().last().unwrap().clone();
+            let v = tree.insert((rng.next_u64() % 10_000) as i32);
+            let after = tree.version(v).unwrap();
+            let height = levels(after.root.as_deref()).len();
+
+            // 分割がなければ、根から葉までのノードだけが新しい
+            if v > 1 && after.nodes == before.nodes {
+                assert_eq!(after.nodes - after.shared, height);
+                checked += 1;
+            }
+            assert!(after.shared <= before.nodes);
+        }
+        assert!(checked > 100);
+        assert_eq!(tree.get_operation(0), Ok(String::new()));
+        assert_eq!(tree.get_shared_nodes(1), Ok(0));
+    }
+
+    #[test]
+    fn test_node_counts_match_full_walk() {
+        let mut rng = SplitMix64::new(13);
+        let mut tree = PersistentBTree::new(2).unwrap();
+        for _ in 0..1000 {
+            let k = (rng.next_u64() % 200) as i32;
+            if rng.chance(0.4) {
+                tree.delete(k);
+            } else {
+                tree.insert(k);
+            }
+        }
+
+        for pair in tree.versions().windows(2) {
+            let before = pair[0].ids();
+            let after = pair[1].ids();
+            assert_eq!(pair[1].nodes, after.len());
+            assert_eq!(
+                pair[1].shared,
+                after.intersection(&before).count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_delete_missing_key_reuses_root() {
+        let mut tree = PersistentBTree::new(2).unwrap();
+        assert!(!tree.delete(1));
+        for k in 0..20 {
+            tree.insert(k * 2);
+        }
+        let before = tree.versions().last().unwrap().clone();
+        assert!(!tree.delete(7));
+        let after = tree.versions().last().unwrap();
+        assert!(Rc::ptr_eq(
+            before.root.as_ref().unwrap(),
+            after.root.as_ref().unwrap()
+        ));
+        assert_eq!(after.nodes, before.nodes);
+        assert_eq!(after.shared, before.nodes);
+        assert_eq!(after.operation.as_deref(), Some("delete 7"));
+    }
+
+    #[test]
+    fn test_same_operations_same_ids() {
+        let ids = || {
+            let mut tree = PersistentBTree::new(2).unwrap();
+            for k in [5, 1, 9, 3, 7, 2, 8] {
+                tree.insert(k);
+            }
+            tree.delete(5);
+            tree.versions().iter().map(Version::ids).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(), ids());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::rangetree::trace::{RangeTrace, check_index, check_range};
+use wasm_bindgen::prelude::*;
+
+/// iの最下位の1のビット
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+// FenwickTree (Binary Indexed Tree, 区間和)
+//
+// 節点は1始まりの番号で、節点iは区間 (i - lowbit(i), i] の和を持つ
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FenwickTree {
+    // tree[0]は使わない
+    tree: Vec<i64>,
+
+    // 各要素の現在の値 (点代入で差分を求めるため)
+    values: Vec<i64>,
+}
+
+#[wasm_bindgen]
+impl FenwickTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(values: &[i32]) -> Self {
+        let values: Vec<i64> = values.iter().map(|&v| v as i64).collect();
+        FenwickTree::build(&values)
+    }
+
+    /// 区間 [lo, hi) の和
+    ///
+    /// nodesは接頭辞和 [0, hi) に足した節点、subtractedは [0, lo) の分として引いた節点
+    pub fn query(
+        &self,
+        lo: usize,
+        hi: usize,
+    ) -> Result<RangeTrace, String> {
+        check_range(lo, hi, self.len())?;
+        let mut trace = RangeTrace::default();
+        let (sum, nodes) = self.prefix(hi);
+        let (minus, subtracted) = self.prefix(lo);
+        trace.value = sum - minus;
+        trace.visited = nodes.iter().chain(&subtracted).copied().collect();
+        trace.nodes = nodes;
+        trace.subtracted = subtracted;
+        Ok(trace)
+    }
+
+    /// 接頭辞 [0, end) の和
+    pub fn prefix_sum(&self, end: usize) -> Result<RangeTrace, String> {
+        if end > self.len() {
+            return Err(format!(
+                "prefix length {end} is out of range for length {}",
+                self.len()
+            ));
+        }
+        let (value, nodes) = self.prefix(end);
+        Ok(RangeTrace {
+            value,
+            visited: nodes.clone(),
+            nodes,
+            ..Default::default()
+        })
+    }
+
+    /// index番目にdeltaを足し、更新した節点を返す
+    pub fn add(
+        &mut self,
+        index: usize,
+        delta: i32,
+    ) -> Result<RangeTrace, String> {
+        check_index(index, self.len())?;
+        Ok(self.add_delta(index, delta as i64))
+    }
+
+    /// index番目の値をvalueにする
+    pub fn update(
+        &mut self,
+        index: usize,
+        value: i32,
+    ) -> Result<RangeTrace, String> {
+        check_index(index, self.len())?;
+        Ok(self.add_delta(index, value as i64 - self.values[index]))
+    }
+
+    /// 節点の配列 ({index, lo, hi, value} の配列、区間は半開区間 [lo, hi))
+    #[wasm_bindgen]
+    pub fn get_nodes(&self) -> js_sys::Array {
+        js_sys::Array::from_iter((1..self.tree.len()).map(|i| {
+            let obj = js_sys::Object::new();
+            let set = |name: &str, value: JsValue| {
+                let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+            };
+            set("index", i.into());
+            set("lo", (i - lowbit(i)).into());
+            set("hi", i.into());
+            set("value", (self.tree[i] as f64).into());
+            JsValue::from(obj)
+        }))
+    }
+
+    #[wasm_bindgen]
+    pub fn get_values(&self) -> Vec<f64> {
+        self.values.iter().map(|&v| v as f64).collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl FenwickTree {
+    /// 各節点が自分の区間の和を親へ足し込むことで O(n) で構築する
+    pub fn build(values: &[i64]) -> Self {
+        let n = values.len();
+        let mut tree = vec![0; n + 1];
+        tree[1..].copy_from_slice(values);
+        for i in 1..=n {
+            let parent = i + lowbit(i);
+            if parent <= n {
+                tree[parent] += tree[i];
+            }
+        }
+        FenwickTree {
+            tree,
+            values: values.to_vec(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    /// [0, end) の和と、足した節点 (endから最下位ビットを落としていく)
+    fn prefix(&self, end: usize) -> (i64, Vec<usize>) {
+        let mut sum = 0;
+        let mut nodes = Vec::new();
+        let mut i = end;
+        while i > 0 {
+            sum += self.tree[i];
+            nodes.push(i);
+            i -= lowbit(i);
+        }
+        (sum, nodes)
+    }
+
+    fn add_delta(&mut self, index: usize, delta: i64) -> RangeTrace {
+        self.values[index] += delta;
+        let mut trace = RangeTrace::default();
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            trace.nodes.push(i);
+            i += lowbit(i);
+        }
+        trace.visited = trace.nodes.clone();
+        trace.value = self.values[index];
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_query_decomposition() {
+        let tree = FenwickTree::new(&[3, 2, -1, 6, 5, 4, -3, 3, 7, 2, 3]);
+        let trace = tree.query(2, 11).unwrap();
+        assert_eq!(trace.value(), 26);
+        // [0,11) = 11 + 10 + 8, [0,2) = 2
+        assert_eq!(trace.nodes(), &[11, 10, 8]);
+        assert_eq!(trace.subtracted(), &[2]);
+
+        assert_eq!(tree.prefix_sum(0).unwrap().value(), 0);
+        assert_eq!(tree.prefix_sum(4).unwrap().nodes(), &[4]);
+        assert!(tree.prefix_sum(12).is_err());
+        assert!(tree.query(4, 4).is_err());
+    }
+
+    #[test]
+    fn test_point_update_path() {
+        let mut tree = FenwickTree::new(&[0; 16]);
+        let trace = tree.add(4, 7).unwrap();
+        // 5 -> 6 -> 8 -> 16
+        assert_eq!(trace.nodes(), &[5, 6, 8, 16]);
+        assert_eq!(trace.value(), 7);
+
+        let trace = tree.update(4, 2).unwrap();
+        assert_eq!(trace.value(), 2);
+        assert_eq!(tree.query(0, 16).unwrap().value(), 2);
+        assert!(tree.add(16, 1).is_err());
+    }
+
+    #[test]
+    fn test_matches_naive_prefix_sums() {
+        let mut rng = SplitMix64::new(5);
+        let mut naive: Vec<i64> = (0..50)
+            .map(|_| (rng.next_u64() % 100) as i64 - 50)
+            .collect();
+        let mut tree = FenwickTree::build(&naive);
+
+        for _ in 0..500 {
+            let a = (rng.next_u64() % 50) as usize;
+            let b = (rng.next_u64() % 50) as usize;
+            if rng.chance(0.5) {
+                let v = (rng.next_u64() % 100) as i32 - 50;
+                tree.update(a, v).unwrap();
+                naive[a] = v as i64;
+            } else {
+                let (lo, hi) = (a.min(b), a.max(b) + 1);
+                let want: i64 = naive[lo..hi].iter().sum();
+                assert_eq!(tree.query(lo, hi).unwrap().value(), want);
+            }
+        }
+        assert_eq!(tree.values(), naive.as_slice());
+    }
+}
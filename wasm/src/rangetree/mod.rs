@@ -0,0 +1,7 @@
+mod fenwick;
+mod segment;
+mod trace;
+
+pub use fenwick::FenwickTree;
+pub use segment::{Aggregate, SegmentTree};
+pub use trace::RangeTrace;
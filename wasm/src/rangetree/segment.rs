@@ -0,0 +1,456 @@
+use crate::rangetree::trace::{RangeTrace, check_index, check_range};
+use js_sys::Array;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// セグメント木で集約する演算
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+impl FromStr for Aggregate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "sum" => Aggregate::Sum,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            _ => return Err(format!("unknown aggregate: {s}")),
+        })
+    }
+}
+
+impl Aggregate {
+    /// 単位元
+    fn identity(&self) -> i64 {
+        match self {
+            Aggregate::Sum => 0,
+            Aggregate::Min => i64::MAX,
+            Aggregate::Max => i64::MIN,
+        }
+    }
+
+    fn combine(&self, a: i64, b: i64) -> i64 {
+        match self {
+            Aggregate::Sum => a + b,
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+
+    /// 長さlenの区間の全要素にdeltaを足したときの、集約値の変化量
+    fn scale(&self, delta: i64, len: usize) -> i64 {
+        match self {
+            Aggregate::Sum => delta * len as i64,
+            Aggregate::Min | Aggregate::Max => delta,
+        }
+    }
+}
+
+// SegmentTree (区間加算を遅延評価するセグメント木)
+//
+// 節点は1始まりの番号で、節点iの子は2iと2i+1。根は区間 [0, n) を受け持つ
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct SegmentTree {
+    // 要素数
+    n: usize,
+
+    aggregate: Aggregate,
+
+    // 各節点の集約値 (その節点の遅延値は反映済み)
+    tree: Vec<i64>,
+
+    // 子にまだ伝えていない加算量
+    lazy: Vec<i64>,
+}
+
+#[wasm_bindgen]
+impl SegmentTree {
+    /// aggregateは "sum", "min", "max" のいずれか
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        values: &[i32],
+        aggregate: &str,
+    ) -> Result<SegmentTree, String> {
+        let values: Vec<i64> = values.iter().map(|&v| v as i64).collect();
+        Ok(SegmentTree::build(&values, aggregate.parse()?))
+    }
+
+    /// 区間 [lo, hi) の集約値と、区間を覆う節点
+    pub fn query(
+        &mut self,
+        lo: usize,
+        hi: usize,
+    ) -> Result<RangeTrace, String> {
+        check_range(lo, hi, self.n)?;
+        let mut trace = RangeTrace::default();
+        trace.value = self.query_node(1, 0, self.n, lo, hi, &mut trace);
+        Ok(trace)
+    }
+
+    /// index番目の値をvalueにし、根から葉への経路を返す
+    pub fn update(
+        &mut self,
+        index: usize,
+        value: i32,
+    ) -> Result<RangeTrace, String> {
+        check_index(index, self.n)?;
+        let mut trace = RangeTrace::default();
+        self.set_node(1, 0, self.n, index, value as i64, &mut trace);
+        trace.value = self.tree[1];
+        Ok(trace)
+    }
+
+    /// 区間 [lo, hi) の全要素にdeltaを足す (遅延評価)
+    pub fn add_range(
+        &mut self,
+        lo: usize,
+        hi: usize,
+        delta: i32,
+    ) -> Result<RangeTrace, String> {
+        check_range(lo, hi, self.n)?;
+        let mut trace = RangeTrace::default();
+        self.add_node(1, 0, self.n, lo, hi, delta as i64, &mut trace);
+        trace.value = self.tree[1];
+        Ok(trace)
+    }
+
+    /// 現在の各要素の値 (遅延値を反映した値)
+    #[wasm_bindgen]
+    pub fn get_values(&self) -> Vec<f64> {
+        self.values().into_iter().map(|v| v as f64).collect()
+    }
+
+    /// 木の構造 (BTreeと同じ形。keysは集約値1つ)
+    ///
+    /// 各節点にはindex (節点番号)、lo, hi (受け持つ区間)、lazyも入れる
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        if self.n == 0 {
+            return JsValue::NULL;
+        }
+        self.node_to_js_value(1, 0, self.n)
+    }
+
+    fn node_to_js_value(
+        &self,
+        node: usize,
+        l: usize,
+        r: usize,
+    ) -> JsValue {
+        let obj = js_sys::Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        let is_leaf = r - l == 1;
+        let children = if is_leaf {
+            Array::new()
+        } else {
+            let m = (l + r) / 2;
+            Array::of2(
+                &self.node_to_js_value(2 * node, l, m),
+                &self.node_to_js_value(2 * node + 1, m, r),
+            )
+        };
+        set("keys", Array::of1(&(self.tree[node] as f64).into()).into());
+        set("children", children.into());
+        set("isLeaf", is_leaf.into());
+        set("index", node.into());
+        set("lo", l.into());
+        set("hi", r.into());
+        set("lazy", (self.lazy[node] as f64).into());
+
+        obj.into()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_length(&self) -> usize {
+        self.n
+    }
+}
+
+impl SegmentTree {
+    pub fn build(values: &[i64], aggregate: Aggregate) -> Self {
+        let n = values.len();
+        let size = 4 * n.max(1);
+        let mut tree = SegmentTree {
+            n,
+            aggregate,
+            tree: vec![aggregate.identity(); size],
+            lazy: vec![0; size],
+        };
+        if n > 0 {
+            tree.build_node(1, 0, n, values);
+        }
+        tree
+    }
+
+    fn build_node(
+        &mut self,
+        node: usize,
+        l: usize,
+        r: usize,
+        values: &[i64],
+    ) {
+        if r - l == 1 {
+            self.tree[node] = values[l];
+            return;
+        }
+        let m = (l + r) / 2;
+        self.build_node(2 * node, l, m, values);
+        self.build_node(2 * node + 1, m, r, values);
+        self.pull(node);
+    }
+
+    pub fn values(&self) -> Vec<i64> {
+        let mut values = Vec::with_capacity(self.n);
+        if self.n > 0 {
+            self.collect(1, 0, self.n, 0, &mut values);
+        }
+        values
+    }
+
+    fn collect(
+        &self,
+        node: usize,
+        l: usize,
+        r: usize,
+        pending: i64,
+        out: &mut Vec<i64>,
+    ) {
+        if r - l == 1 {
+            out.push(self.tree[node] + pending);
+            return;
+        }
+        let pending = pending + self.lazy[node];
+        let m = (l + r) / 2;
+        self.collect(2 * node, l, m, pending, out);
+        self.collect(2 * node + 1, m, r, pending, out);
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = self
+            .aggregate
+            .combine(self.tree[2 * node], self.tree[2 * node + 1]);
+    }
+
+    /// 節点に加算を反映し、葉でなければ遅延値として溜める
+    fn apply(&mut self, node: usize, l: usize, r: usize, delta: i64) {
+        self.tree[node] += self.aggregate.scale(delta, r - l);
+        if r - l > 1 {
+            self.lazy[node] += delta;
+        }
+    }
+
+    /// 遅延値を子へ押し下げる
+    fn push(
+        &mut self,
+        node: usize,
+        l: usize,
+        r: usize,
+        trace: &mut RangeTrace,
+    ) {
+        let delta = std::mem::take(&mut self.lazy[node]);
+        if delta != 0 {
+            let m = (l + r) / 2;
+            self.apply(2 * node, l, m, delta);
+            self.apply(2 * node + 1, m, r, delta);
+            trace.pushed.push(node);
+        }
+    }
+
+    fn query_node(
+        &mut self,
+        node: usize,
+        l: usize,
+        r: usize,
+        lo: usize,
+        hi: usize,
+        trace: &mut RangeTrace,
+    ) -> i64 {
+        trace.visited.push(node);
+        if lo <= l && r <= hi {
+            trace.nodes.push(node);
+            return self.tree[node];
+        }
+
+        self.push(node, l, r, trace);
+        let m = (l + r) / 2;
+        let mut value = self.aggregate.identity();
+        if lo < m {
+            let left = self.query_node(2 * node, l, m, lo, hi, trace);
+            value = self.aggregate.combine(value, left);
+        }
+        if m < hi {
+            let right = self.query_node(2 * node + 1, m, r, lo, hi, trace);
+            value = self.aggregate.combine(value, right);
+        }
+        value
+    }
+
+    fn set_node(
+        &mut self,
+        node: usize,
+        l: usize,
+        r: usize,
+        index: usize,
+        value: i64,
+        trace: &mut RangeTrace,
+    ) {
+        trace.visited.push(node);
+        trace.nodes.push(node);
+        if r - l == 1 {
+            self.tree[node] = value;
+            return;
+        }
+
+        self.push(node, l, r, trace);
+        let m = (l + r) / 2;
+        if index < m {
+            self.set_node(2 * node, l, m, index, value, trace);
+        } else {
+            self.set_node(2 * node + 1, m, r, index, value, trace);
+        }
+        self.pull(node);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_node(
+        &mut self,
+        node: usize,
+        l: usize,
+        r: usize,
+        lo: usize,
+        hi: usize,
+        delta: i64,
+        trace: &mut RangeTrace,
+    ) {
+        trace.visited.push(node);
+        if lo <= l && r <= hi {
+            self.apply(node, l, r, delta);
+            trace.nodes.push(node);
+            return;
+        }
+
+        self.push(node, l, r, trace);
+        let m = (l + r) / 2;
+        if lo < m {
+            self.add_node(2 * node, l, m, lo, hi, delta, trace);
+        }
+        if m < hi {
+            self.add_node(2 * node + 1, m, r, lo, hi, delta, trace);
+        }
+        self.pull(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+
+    #[test]
+    fn test_query_cover_nodes() {
+        let mut tree =
+            SegmentTree::new(&[5, 8, 6, 3, 2, 7, 2, 6], "sum").unwrap();
+        let trace = tree.query(1, 7).unwrap();
+        assert_eq!(trace.value(), 28);
+        // [1,2) [2,4) [4,6) [6,7)
+        assert_eq!(trace.nodes(), &[9, 5, 6, 14]);
+        // [0,1) は区間と重ならないので訪問しない
+        assert_eq!(trace.visited(), &[1, 2, 4, 9, 5, 3, 6, 7, 14]);
+
+        let trace = tree.query(0, 8).unwrap();
+        assert_eq!(trace.nodes(), &[1]);
+        assert!(tree.query(3, 3).is_err());
+        assert!(tree.query(2, 9).is_err());
+    }
+
+    #[test]
+    fn test_point_update_path() {
+        let mut tree = SegmentTree::new(&[5, 8, 6, 3, 2], "min").unwrap();
+        let trace = tree.update(3, 1).unwrap();
+        assert_eq!(trace.value(), 1);
+        // [0,5) -> [2,5) -> [3,5) -> [3,4)
+        assert_eq!(trace.nodes(), &[1, 3, 7, 14]);
+        assert_eq!(tree.query(0, 3).unwrap().value(), 5);
+        assert!(tree.update(5, 0).is_err());
+    }
+
+    #[test]
+    fn test_lazy_range_add_is_pushed_on_demand() {
+        let mut tree =
+            SegmentTree::new(&[1, 2, 3, 4, 5, 6, 7, 8], "max").unwrap();
+        let trace = tree.add_range(0, 4, 10).unwrap();
+        assert_eq!(trace.nodes(), &[2]);
+        assert!(trace.pushed().is_empty());
+        assert_eq!(trace.value(), 14);
+
+        // 左半分の内側を問い合わせると、節点2の遅延値が押し下げられる
+        let trace = tree.query(1, 3).unwrap();
+        assert_eq!(trace.value(), 13);
+        assert_eq!(trace.pushed(), &[2, 4, 5]);
+        assert_eq!(
+            tree.get_values(),
+            [11., 12., 13., 14., 5., 6., 7., 8.]
+        );
+    }
+
+    #[test]
+    fn test_matches_naive_for_every_aggregate() {
+        for aggregate in [Aggregate::Sum, Aggregate::Min, Aggregate::Max] {
+            let mut rng = SplitMix64::new(3);
+            let mut naive: Vec<i64> =
+                (0..37).map(|_| (rng.next_u64() % 100) as i64).collect();
+            let mut tree = SegmentTree::build(&naive, aggregate);
+
+            for _ in 0..500 {
+                let a = (rng.next_u64() % 37) as usize;
+                let b = (rng.next_u64() % 37) as usize;
+                let (lo, hi) = (a.min(b), a.max(b) + 1);
+                let v = (rng.next_u64() % 50) as i32 - 25;
+                match rng.next_u64() % 3 {
+                    0 => {
+                        tree.update(a, v).unwrap();
+                        naive[a] = v as i64;
+                    }
+                    1 => {
+                        tree.add_range(lo, hi, v).unwrap();
+                        naive[lo..hi]
+                            .iter_mut()
+                            .for_each(|x| *x += v as i64);
+                    }
+                    _ => {
+                        let slice = &naive[lo..hi];
+                        let want = match aggregate {
+                            Aggregate::Sum => slice.iter().sum(),
+                            Aggregate::Min => *slice.iter().min().unwrap(),
+                            Aggregate::Max => *slice.iter().max().unwrap(),
+                        };
+                        let got = tree.query(lo, hi).unwrap().value();
+                        assert_eq!(
+                            got, want,
+                            "{aggregate:?} [{lo}, {hi})"
+                        );
+                    }
+                }
+            }
+            assert_eq!(tree.values(), naive);
+        }
+    }
+
+    #[test]
+    fn test_unknown_aggregate_and_empty_tree() {
+        assert!(SegmentTree::new(&[1], "avg").is_err());
+        let mut empty = SegmentTree::new(&[], "sum").unwrap();
+        assert!(empty.query(0, 1).is_err());
+        assert!(empty.values().is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use wasm_bindgen::prelude::*;
+
+/// 区間クエリ・更新の結果と、たどった節点
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RangeTrace {
+    /// クエリの答え (更新では更新後の全体の値)
+    pub(crate) value: i64,
+
+    /// クエリでは区間を覆う節点、点更新では根から葉 (Fenwick木では更新した節点) への経路、
+    /// 区間更新では遅延値を置いた節点
+    pub(crate) nodes: Vec<usize>,
+
+    /// 訪問した全ての節点 (区間と部分的に重なるだけの節点も含む)
+    pub(crate) visited: Vec<usize>,
+
+    /// 遅延値を子へ押し下げた節点
+    pub(crate) pushed: Vec<usize>,
+
+    /// Fenwick木の区間和で差し引いた節点 (接頭辞和 [0, lo) の分)
+    pub(crate) subtracted: Vec<usize>,
+}
+
+#[wasm_bindgen]
+impl RangeTrace {
+    #[wasm_bindgen]
+    pub fn get_value(&self) -> f64 {
+        self.value as f64
+    }
+
+    #[wasm_bindgen]
+    pub fn get_nodes(&self) -> Vec<usize> {
+        self.nodes.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_visited(&self) -> Vec<usize> {
+        self.visited.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_pushed(&self) -> Vec<usize> {
+        self.pushed.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_subtracted(&self) -> Vec<usize> {
+        self.subtracted.clone()
+    }
+}
+
+impl RangeTrace {
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    pub fn visited(&self) -> &[usize] {
+        &self.visited
+    }
+
+    pub fn pushed(&self) -> &[usize] {
+        &self.pushed
+    }
+
+    pub fn subtracted(&self) -> &[usize] {
+        &self.subtracted
+    }
+}
+
+/// 区間 [lo, hi) が長さnの配列の空でない部分区間か確かめる
+pub(crate) fn check_range(
+    lo: usize,
+    hi: usize,
+    n: usize,
+) -> Result<(), String> {
+    if lo < hi && hi <= n {
+        Ok(())
+    } else {
+        Err(format!("invalid range [{lo}, {hi}) for length {n}"))
+    }
+}
+
+pub(crate) fn check_index(index: usize, n: usize) -> Result<(), String> {
+    if index < n {
+        Ok(())
+    } else {
+        Err(format!("index {index} is out of range for length {n}"))
+    }
+}
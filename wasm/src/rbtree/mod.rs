@@ -0,0 +1,5 @@
+mod node;
+mod operation;
+
+pub use node::{Color, RbEvent, RbShape};
+pub use operation::RedBlackTree;
@@ -0,0 +1,132 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+/// 番兵(NIL)ノードのインデックス
+pub(super) const NIL: usize = 0;
+
+/// ノードの色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+impl Color {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Color::Red => "red",
+            Color::Black => "black",
+        }
+    }
+}
+
+/// 赤黒木のノード
+///
+/// ノードは木の持つ配列に格納され、親子はインデックスで参照する
+#[derive(Clone, Debug)]
+pub(super) struct RbNode {
+    /// キー
+    pub key: i32,
+
+    /// 色
+    pub color: Color,
+
+    /// 左の子 (なければNIL)
+    pub left: usize,
+
+    /// 右の子 (なければNIL)
+    pub right: usize,
+
+    /// 親 (根の場合はNIL)
+    pub parent: usize,
+}
+
+impl RbNode {
+    pub fn new(key: i32, color: Color) -> Self {
+        RbNode {
+            key,
+            color,
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+        }
+    }
+}
+
+/// ノードの色と形だけを表した赤黒木
+///
+/// 他の木との変換に使う
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RbShape {
+    pub key: i32,
+    pub color: Color,
+    pub left: Option<Box<RbShape>>,
+    pub right: Option<Box<RbShape>>,
+}
+
+/// 挿入・削除中に発生した回転と色の変更
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RbEvent {
+    /// キーkeyの赤ノードを葉の位置に追加
+    Insert { key: i32 },
+
+    /// キーkeyのノードを取り除く
+    Remove { key: i32 },
+
+    /// キーkeyのノードを軸に左回転
+    RotateLeft { key: i32 },
+
+    /// キーkeyのノードを軸に右回転
+    RotateRight { key: i32 },
+
+    /// キーkeyのノードの色をcolorに変更
+    Recolor { key: i32, color: Color },
+
+    /// 2つの赤い子を持つキーkeyのノードで色を反転
+    /// (key を赤に、子を黒にする。根の場合は key は黒のまま)
+    ColorFlip { key: i32 },
+
+    /// 2つの黒い子を持つキーkeyのノードで色を反転
+    /// (key を黒に、子を赤にする。ColorFlipの逆で、2-3-4木のマージに当たる)
+    ReverseFlip { key: i32 },
+
+    /// キーkeyのノードのキーをwithに書き換える
+    Replace { key: i32, with: i32 },
+}
+
+impl RbEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let (kind, key) = match self {
+            RbEvent::Insert { key } => ("insert", key),
+            RbEvent::Remove { key } => ("remove", key),
+            RbEvent::RotateLeft { key } => ("rotateLeft", key),
+            RbEvent::RotateRight { key } => ("rotateRight", key),
+            RbEvent::Recolor { key, .. } => ("recolor", key),
+            RbEvent::ColorFlip { key } => ("colorFlip", key),
+            RbEvent::ReverseFlip { key } => ("reverseFlip", key),
+            RbEvent::Replace { key, .. } => ("replace", key),
+        };
+        let _ = js_sys::Reflect::set(&obj, &"type".into(), &kind.into());
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"key".into(),
+            &JsValue::from(*key),
+        );
+        if let RbEvent::Recolor { color, .. } = self {
+            let _ = js_sys::Reflect::set(
+                &obj,
+                &"color".into(),
+                &color.as_str().into(),
+            );
+        }
+        if let RbEvent::Replace { with, .. } = self {
+            let _ = js_sys::Reflect::set(
+                &obj,
+                &"with".into(),
+                &JsValue::from(*with),
+            );
+        }
+        obj.into()
+    }
+}
@@ -0,0 +1,1120 @@
+use crate::rbtree::node::{Color, NIL, RbEvent, RbNode, RbShape};
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+// Red-Black Tree
+#[wasm_bindgen]
+pub struct RedBlackTree {
+    // ノードの配列 (先頭は番兵NIL)
+    nodes: Vec<RbNode>,
+
+    // 削除されて再利用できるノードのインデックス
+    free: Vec<usize>,
+
+    // 根 (空の場合はNIL)
+    root: usize,
+
+    // キーの数
+    len: usize,
+
+    // 直前の操作で発生したイベント
+    events: Vec<RbEvent>,
+
+    // 直前のトップダウン削除で、2-3-4木の1手が始まるイベントの位置
+    steps: Vec<usize>,
+}
+
+impl Default for RedBlackTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl RedBlackTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        RedBlackTree {
+            nodes: vec![RbNode::new(0, Color::Black)],
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+            events: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// キーkを探索
+    pub fn search(&self, k: i32) -> bool {
+        self.find(k) != NIL
+    }
+
+    /// キーkを挿入 (既に存在する場合はfalse)
+    pub fn insert(&mut self, k: i32) -> bool {
+        self.events.clear();
+        self.steps.clear();
+
+        // 二分探索木として挿入位置を探す
+        let mut parent = NIL;
+        let mut x = self.root;
+        while x != NIL {
+            parent = x;
+            if k == self.nodes[x].key {
+                return false;
+            }
+            x = if k < self.nodes[x].key {
+                self.nodes[x].left
+            } else {
+                self.nodes[x].right
+            };
+        }
+
+        // 新しいノードは赤で挿入
+        let z = self.alloc(k);
+        self.nodes[z].parent = parent;
+        if parent == NIL {
+            self.root = z;
+        } else if k < self.nodes[parent].key {
+            self.nodes[parent].left = z;
+        } else {
+            self.nodes[parent].right = z;
+        }
+        self.len += 1;
+        self.events.push(RbEvent::Insert { key: k });
+
+        self.insert_fixup(z);
+        true
+    }
+
+    /// キーkをトップダウンに挿入 (既に存在する場合はfalse)
+    ///
+    /// 根から下る途中で2つの赤い子を持つノードの色を反転していく。
+    /// これは2-3-4木で満杯のノードを下りながら先に分割するのと同じで、
+    /// t=2 の BTree::insert と1対1に対応する。
+    pub fn insert_top_down(&mut self, k: i32) -> bool {
+        self.events.clear();
+        self.steps.clear();
+        if self.find(k) != NIL {
+            return false;
+        }
+
+        let mut parent = NIL;
+        let mut x = self.root;
+        while x != NIL {
+            let (left, right) = (self.nodes[x].left, self.nodes[x].right);
+            if self.color(left) == Color::Red
+                && self.color(right) == Color::Red
+            {
+                // 4-ノードの分割: 中央のキーを親の2-3-4ノードへ押し上げる
+                self.events.push(RbEvent::ColorFlip {
+                    key: self.nodes[x].key,
+                });
+                self.nodes[left].color = Color::Black;
+                self.nodes[right].color = Color::Black;
+                if x != self.root {
+                    self.nodes[x].color = Color::Red;
+                    self.insert_fixup(x);
+                }
+            }
+
+            parent = x;
+            x = if k < self.nodes[x].key {
+                self.nodes[x].left
+            } else {
+                self.nodes[x].right
+            };
+        }
+
+        let z = self.alloc(k);
+        self.nodes[z].parent = parent;
+        if parent == NIL {
+            self.root = z;
+        } else if k < self.nodes[parent].key {
+            self.nodes[parent].left = z;
+        } else {
+            self.nodes[parent].right = z;
+        }
+        self.len += 1;
+        self.events.push(RbEvent::Insert { key: k });
+
+        self.insert_fixup(z);
+        true
+    }
+
+    /// キーkを削除 (存在しない場合はfalse)
+    pub fn delete(&mut self, k: i32) -> bool {
+        self.events.clear();
+        self.steps.clear();
+
+        let z = self.find(k);
+        if z == NIL {
+            return false;
+        }
+
+        self.events.push(RbEvent::Remove { key: k });
+
+        let mut y = z;
+        let mut y_original_color = self.nodes[y].color;
+        let x;
+
+        if self.nodes[z].left == NIL {
+            // 左の子がない場合、右の子で置き換え
+            x = self.nodes[z].right;
+            self.transplant(z, x);
+        } else if self.nodes[z].right == NIL {
+            // 右の子がない場合、左の子で置き換え
+            x = self.nodes[z].left;
+            self.transplant(z, x);
+        } else {
+            // 子が2つある場合、後継で置き換え
+            y = self.minimum(self.nodes[z].right);
+            y_original_color = self.nodes[y].color;
+            x = self.nodes[y].right;
+
+            if self.nodes[y].parent == z {
+                self.nodes[x].parent = y;
+            } else {
+                self.transplant(y, x);
+                let right = self.nodes[z].right;
+                self.nodes[y].right = right;
+                self.nodes[right].parent = y;
+            }
+
+            self.transplant(z, y);
+            let left = self.nodes[z].left;
+            self.nodes[y].left = left;
+            self.nodes[left].parent = y;
+            self.set_color(y, self.nodes[z].color);
+        }
+
+        // 黒ノードが取り除かれた場合、黒高さを修復
+        if y_original_color == Color::Black {
+            self.delete_fixup(x);
+        }
+
+        self.free.push(z);
+        self.len -= 1;
+        true
+    }
+
+    /// キーkをトップダウンに削除 (存在しない場合はfalse)
+    ///
+    /// 黒ノードとその赤い子を2-3-4木の1ノードとみなし、t=2 の
+    /// BTree::deleteと同じ順に、下りる先の子を兄弟から借りるかマージして
+    /// 2キー以上にしてから下りる。マージは色の逆反転、借用は2回の回転になる。
+    /// キーがなくても、BTree::deleteと同じく途中の補強は行う
+    pub fn delete_top_down(&mut self, k: i32) -> bool {
+        self.events.clear();
+        self.steps.clear();
+        let mut k = k;
+        let mut h = self.root;
+        while h != NIL {
+            let (members, children) = self.group(h);
+            let idx = members.partition_point(|&m| self.nodes[m].key < k);
+            let leaf = children.iter().all(|&c| c == NIL);
+            let found =
+                members.get(idx).is_some_and(|&m| self.nodes[m].key == k);
+
+            if found && leaf {
+                self.steps.push(self.events.len());
+                self.remove_from_leaf(h, members[idx]);
+                return true;
+            }
+            if leaf {
+                return false;
+            }
+            if found {
+                let (left, right) = (children[idx], children[idx + 1]);
+                let m = members[idx];
+                if self.group(left).0.len() >= 2 {
+                    // 前駆で置き換え、前駆を左の部分木から消す
+                    let predecessor = self.nodes[self.maximum(left)].key;
+                    self.replace_key(m, predecessor);
+                    k = predecessor;
+                    h = left;
+                } else if self.group(right).0.len() >= 2 {
+                    let successor = self.nodes[self.minimum(right)].key;
+                    self.replace_key(m, successor);
+                    k = successor;
+                    h = right;
+                } else {
+                    h = self.merge(m, left, right);
+                }
+                continue;
+            }
+
+            let child = children[idx];
+            let last = children.len() - 1;
+            let enough = |x: usize| self.group(x).0.len() >= 2;
+            h = if enough(child) {
+                child
+            } else if idx != 0 && enough(children[idx - 1]) {
+                self.borrow_from_prev(
+                    members[idx - 1],
+                    children[idx - 1],
+                    child,
+                )
+            } else if idx != last && enough(children[idx + 1]) {
+                self.borrow_from_next(
+                    members[idx],
+                    child,
+                    children[idx + 1],
+                )
+            } else if idx != last {
+                self.merge(members[idx], child, children[idx + 1])
+            } else {
+                self.merge(members[idx - 1], children[idx - 1], child)
+            };
+        }
+        false
+    }
+
+    fn node_to_js_value(&self, x: usize) -> JsValue {
+        let node = &self.nodes[x];
+        let obj = js_sys::Object::new();
+
+        let keys = Array::of1(&JsValue::from(node.key));
+        let _ = js_sys::Reflect::set(&obj, &"keys".into(), &keys.into());
+
+        // 片方の子しかない場合も左右が区別できるよう、NILはnullにする
+        let is_leaf = node.left == NIL && node.right == NIL;
+        let children = if is_leaf {
+            Array::new()
+        } else {
+            Array::from_iter([node.left, node.right].iter().map(|&c| {
+                if c == NIL {
+                    JsValue::NULL
+                } else {
+                    self.node_to_js_value(c)
+                }
+            }))
+        };
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"children".into(),
+            &children.into(),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"isLeaf".into(),
+            &JsValue::from(is_leaf),
+        );
+        let _ = js_sys::Reflect::set(
+            &obj,
+            &"color".into(),
+            &node.color.as_str().into(),
+        );
+
+        obj.into()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        if self.root == NIL {
+            JsValue::NULL
+        } else {
+            self.node_to_js_value(self.root)
+        }
+    }
+
+    /// 直前の操作で発生した回転・色変更のイベントを取得
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(RbEvent::to_js_value))
+    }
+
+    /// キーの総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    #[wasm_bindgen]
+    pub fn get_height(&self) -> usize {
+        self.height(self.root)
+    }
+
+    /// 赤黒木の性質を満たしているか
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        self.check_invariants().is_ok()
+    }
+}
+
+impl RedBlackTree {
+    /// 直前の操作で発生したイベント
+    pub fn events(&self) -> &[RbEvent] {
+        &self.events
+    }
+
+    /// 直前のトップダウン削除のイベントを、2-3-4木の1手ごとに分けたもの
+    ///
+    /// マージ・借用・キーの置き換えが起きた順に並び、
+    /// キーを消した場合は最後に葉からの削除が続く
+    pub fn steps(&self) -> Vec<&[RbEvent]> {
+        let mut ends = self.steps.iter().skip(1).copied();
+        self.steps
+            .iter()
+            .map(|&start| {
+                &self.events
+                    [start..ends.next().unwrap_or(self.events.len())]
+            })
+            .collect()
+    }
+
+    /// 色付きの二分木から赤黒木を作成 (性質の検査はしない)
+    pub fn from_shape(shape: Option<&RbShape>) -> Self {
+        let mut tree = RedBlackTree::new();
+        if let Some(shape) = shape {
+            tree.root = tree.build(shape, NIL);
+        }
+        tree
+    }
+
+    fn build(&mut self, shape: &RbShape, parent: usize) -> usize {
+        let x = self.alloc(shape.key);
+        self.nodes[x].color = shape.color;
+        self.nodes[x].parent = parent;
+        self.len += 1;
+
+        if let Some(ref left) = shape.left {
+            self.nodes[x].left = self.build(left, x);
+        }
+        if let Some(ref right) = shape.right {
+            self.nodes[x].right = self.build(right, x);
+        }
+        x
+    }
+
+    /// 木の色と形を取得
+    pub fn shape(&self) -> Option<RbShape> {
+        self.shape_of(self.root)
+    }
+
+    fn shape_of(&self, x: usize) -> Option<RbShape> {
+        if x == NIL {
+            return None;
+        }
+        let node = &self.nodes[x];
+        Some(RbShape {
+            key: node.key,
+            color: node.color,
+            left: self.shape_of(node.left).map(Box::new),
+            right: self.shape_of(node.right).map(Box::new),
+        })
+    }
+
+    /// 中間順で並べたキー
+    pub fn keys(&self) -> Vec<i32> {
+        let mut keys = Vec::with_capacity(self.len);
+        let mut stack = Vec::new();
+        let mut x = self.root;
+        while x != NIL || !stack.is_empty() {
+            while x != NIL {
+                stack.push(x);
+                x = self.nodes[x].left;
+            }
+            x = stack.pop().unwrap();
+            keys.push(self.nodes[x].key);
+            x = self.nodes[x].right;
+        }
+        keys
+    }
+
+    /// 赤黒木の性質を検査し、黒高さを返す
+    ///
+    /// - 根は黒
+    /// - 赤ノードの子は黒
+    /// - 根から各NILまでの黒ノードの数が等しい
+    pub fn check_invariants(&self) -> Result<usize, String> {
+        if self.nodes[self.root].color != Color::Black {
+            return Err("root is red".to_string());
+        }
+        if self.nodes[self.root].parent != NIL {
+            return Err("root has a parent".to_string());
+        }
+        self.check_node(self.root, None, None)
+    }
+
+    fn check_node(
+        &self,
+        x: usize,
+        lower: Option<i32>,
+        upper: Option<i32>,
+    ) -> Result<usize, String> {
+        if x == NIL {
+            return Ok(1);
+        }
+
+        let node = &self.nodes[x];
+        if lower.is_some_and(|l| node.key <= l)
+            || upper.is_some_and(|u| node.key >= u)
+        {
+            return Err(format!("key {} is out of order", node.key));
+        }
+
+        for child in [node.left, node.right] {
+            if child == NIL {
+                continue;
+            }
+            if self.nodes[child].parent != x {
+                return Err(format!(
+                    "parent link of {} is broken",
+                    self.nodes[child].key
+                ));
+            }
+            if node.color == Color::Red
+                && self.nodes[child].color == Color::Red
+            {
+                return Err(format!(
+                    "red node {} has a red child {}",
+                    node.key, self.nodes[child].key
+                ));
+            }
+        }
+
+        let left = self.check_node(node.left, lower, Some(node.key))?;
+        let right = self.check_node(node.right, Some(node.key), upper)?;
+        if left != right {
+            return Err(format!(
+                "black height differs under {} ({} vs {})",
+                node.key, left, right
+            ));
+        }
+
+        Ok(left + usize::from(node.color == Color::Black))
+    }
+
+    fn alloc(&mut self, key: i32) -> usize {
+        let node = RbNode::new(key, Color::Red);
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn find(&self, k: i32) -> usize {
+        let mut x = self.root;
+        while x != NIL && self.nodes[x].key != k {
+            x = if k < self.nodes[x].key {
+                self.nodes[x].left
+            } else {
+                self.nodes[x].right
+            };
+        }
+        x
+    }
+
+    fn minimum(&self, mut x: usize) -> usize {
+        while self.nodes[x].left != NIL {
+            x = self.nodes[x].left;
+        }
+        x
+    }
+
+    fn maximum(&self, mut x: usize) -> usize {
+        while self.nodes[x].right != NIL {
+            x = self.nodes[x].right;
+        }
+        x
+    }
+
+    /// 黒ノードhを先頭とする2-3-4ノードのキーのノードと子を、中間順に返す
+    ///
+    /// キーはhとその赤い子、子はその下の黒ノード (またはNIL)
+    fn group(&self, h: usize) -> (Vec<usize>, Vec<usize>) {
+        fn walk(
+            tree: &RedBlackTree,
+            x: usize,
+            h: usize,
+            members: &mut Vec<usize>,
+            children: &mut Vec<usize>,
+        ) {
+            if x != NIL && (x == h || tree.color(x) == Color::Red) {
+                walk(tree, tree.nodes[x].left, h, members, children);
+                members.push(x);
+                walk(tree, tree.nodes[x].right, h, members, children);
+            } else {
+                children.push(x);
+            }
+        }
+        let (mut members, mut children) = (Vec::new(), Vec::new());
+        if h != NIL {
+            walk(self, h, h, &mut members, &mut children);
+        }
+        (members, children)
+    }
+
+    /// 内部ノードmのキーを前駆・後継で置き換える
+    fn replace_key(&mut self, m: usize, with: i32) {
+        self.steps.push(self.events.len());
+        self.events.push(RbEvent::Replace {
+            key: self.nodes[m].key,
+            with,
+        });
+        self.nodes[m].key = with;
+    }
+
+    /// 2-3-4ノードの区切りキーsを、両隣の子を直接の子に持つ形にする
+    ///
+    /// sが赤ならすでにその形。sが先頭の黒なら、赤いキーを回転で
+    /// 持ち上げてsを赤にする (2-3-4木としての形は変わらない)
+    fn expose(&mut self, s: usize) {
+        if self.color(s) == Color::Red {
+            return;
+        }
+        let (left, right) = (self.nodes[s].left, self.nodes[s].right);
+        match (self.color(left), self.color(right)) {
+            (Color::Red, Color::Red) => {
+                // 4-ノードの中央: 左を先頭にし、右をsの上に持ち上げる
+                self.rotate_right(s);
+                self.rotate_left(s);
+                self.set_color(left, Color::Black);
+                self.set_color(s, Color::Red);
+            }
+            (Color::Black, Color::Red) => {
+                self.rotate_left(s);
+                self.set_color(right, Color::Black);
+                self.set_color(s, Color::Red);
+            }
+            (Color::Red, Color::Black) => {
+                self.rotate_right(s);
+                self.set_color(left, Color::Black);
+                self.set_color(s, Color::Red);
+            }
+            // 根の2-ノード
+            (Color::Black, Color::Black) => {}
+        }
+    }
+
+    /// 4-ノードの中央をexposeした後に借用で上がってきたキーxを、先頭に戻す
+    ///
+    /// そのときxは赤い右のキーの左の子になっている
+    fn recenter(&mut self, x: usize) {
+        let right = self.nodes[x].parent;
+        if self.color(x) != Color::Red || self.color(right) != Color::Red {
+            return;
+        }
+        let left = self.nodes[right].parent;
+        self.rotate_right(right);
+        self.rotate_left(left);
+        self.set_color(x, Color::Black);
+        self.set_color(left, Color::Red);
+    }
+
+    /// 区切りキーsと、その両側の2-ノードc, dをマージし、新しいノードの先頭を返す
+    fn merge(&mut self, s: usize, c: usize, d: usize) -> usize {
+        self.steps.push(self.events.len());
+        self.expose(s);
+        self.events.push(RbEvent::ReverseFlip {
+            key: self.nodes[s].key,
+        });
+        self.nodes[s].color = Color::Black;
+        self.nodes[c].color = Color::Red;
+        self.nodes[d].color = Color::Red;
+        s
+    }
+
+    /// 2-ノードcが左の兄弟pから借りる。sは区切りキーで、cの新しい先頭になる
+    ///
+    /// pの最後のキーがsの位置へ上がり、sがcへ下りる
+    fn borrow_from_prev(&mut self, s: usize, p: usize, c: usize) -> usize {
+        self.steps.push(self.events.len());
+        self.expose(s);
+        let color = self.color(s);
+
+        // pの最後のキーを先頭の右の赤い子にする
+        let mut head = p;
+        if self.color(self.nodes[p].right) != Color::Red {
+            head = self.nodes[p].left;
+            self.rotate_right(p);
+            self.set_color(head, Color::Black);
+            self.set_color(p, Color::Red);
+        }
+        let up = self.nodes[head].right;
+
+        self.set_color(up, color);
+        self.set_color(s, Color::Black);
+        self.set_color(c, Color::Red);
+        self.rotate_left(head);
+        self.rotate_right(s);
+        self.recenter(up);
+        s
+    }
+
+    /// 2-ノードcが右の兄弟dから借りる。sは区切りキーで、cの新しい先頭になる
+    ///
+    /// dの最初のキーがsの位置へ上がり、sがcへ下りる
+    fn borrow_from_next(&mut self, s: usize, c: usize, d: usize) -> usize {
+        self.steps.push(self.events.len());
+        self.expose(s);
+        let color = self.color(s);
+
+        // dの最初のキーを先頭の左の赤い子にする
+        let mut head = d;
+        if self.color(self.nodes[d].left) != Color::Red {
+            head = self.nodes[d].right;
+            self.rotate_left(d);
+            self.set_color(head, Color::Black);
+            self.set_color(d, Color::Red);
+        }
+        let up = self.nodes[head].left;
+
+        self.set_color(up, color);
+        self.set_color(s, Color::Black);
+        self.set_color(c, Color::Red);
+        self.rotate_right(head);
+        self.rotate_left(s);
+        self.recenter(up);
+        s
+    }
+
+    /// 先頭hの葉の2-3-4ノードからキーのノードmを取り除く
+    fn remove_from_leaf(&mut self, h: usize, m: usize) {
+        self.events.push(RbEvent::Remove {
+            key: self.nodes[m].key,
+        });
+        if m != h {
+            // 赤い葉はそのまま外せる
+            self.transplant(m, NIL);
+        } else {
+            let (left, right) = (self.nodes[h].left, self.nodes[h].right);
+            if left != NIL {
+                // 左の赤い子を先頭にし、右の赤い子をその右につなぐ
+                self.transplant(h, left);
+                self.nodes[left].right = right;
+                self.nodes[right].parent = left;
+                self.set_color(left, Color::Black);
+            } else {
+                self.transplant(h, right);
+                self.set_color(right, Color::Black);
+            }
+        }
+        self.nodes[NIL].parent = NIL;
+        self.free.push(m);
+        self.len -= 1;
+    }
+
+    fn height(&self, x: usize) -> usize {
+        if x == NIL {
+            0
+        } else {
+            1 + self
+                .height(self.nodes[x].left)
+                .max(self.height(self.nodes[x].right))
+        }
+    }
+
+    /// ノードxの色を変更し、変化があればイベントを記録
+    fn set_color(&mut self, x: usize, color: Color) {
+        if x != NIL && self.nodes[x].color != color {
+            self.nodes[x].color = color;
+            self.events.push(RbEvent::Recolor {
+                key: self.nodes[x].key,
+                color,
+            });
+        }
+    }
+
+    fn color(&self, x: usize) -> Color {
+        self.nodes[x].color
+    }
+
+    /// uの位置をvで置き換える
+    fn transplant(&mut self, u: usize, v: usize) {
+        let parent = self.nodes[u].parent;
+        if parent == NIL {
+            self.root = v;
+        } else if u == self.nodes[parent].left {
+            self.nodes[parent].left = v;
+        } else {
+            self.nodes[parent].right = v;
+        }
+        // vがNILでも親を記録しておく(削除の修復で使う)
+        self.nodes[v].parent = parent;
+    }
+
+    fn rotate_left(&mut self, x: usize) {
+        self.events.push(RbEvent::RotateLeft {
+            key: self.nodes[x].key,
+        });
+
+        let y = self.nodes[x].right;
+        let y_left = self.nodes[y].left;
+        self.nodes[x].right = y_left;
+        if y_left != NIL {
+            self.nodes[y_left].parent = x;
+        }
+
+        let parent = self.nodes[x].parent;
+        self.nodes[y].parent = parent;
+        if parent == NIL {
+            self.root = y;
+        } else if x == self.nodes[parent].left {
+            self.nodes[parent].left = y;
+        } else {
+            self.nodes[parent].right = y;
+        }
+
+        self.nodes[y].left = x;
+        self.nodes[x].parent = y;
+    }
+
+    fn rotate_right(&mut self, x: usize) {
+        self.events.push(RbEvent::RotateRight {
+            key: self.nodes[x].key,
+        });
+
+        let y = self.nodes[x].left;
+        let y_right = self.nodes[y].right;
+        self.nodes[x].left = y_right;
+        if y_right != NIL {
+            self.nodes[y_right].parent = x;
+        }
+
+        let parent = self.nodes[x].parent;
+        self.nodes[y].parent = parent;
+        if parent == NIL {
+            self.root = y;
+        } else if x == self.nodes[parent].right {
+            self.nodes[parent].right = y;
+        } else {
+            self.nodes[parent].left = y;
+        }
+
+        self.nodes[y].right = x;
+        self.nodes[x].parent = y;
+    }
+
+    /// 挿入後に赤が連続している箇所を修復
+    fn insert_fixup(&mut self, mut z: usize) {
+        while self.color(self.nodes[z].parent) == Color::Red {
+            let p = self.nodes[z].parent;
+            let g = self.nodes[p].parent;
+
+            if p == self.nodes[g].left {
+                let uncle = self.nodes[g].right;
+                if self.color(uncle) == Color::Red {
+                    // 叔父が赤: 親と叔父を黒、祖父を赤にして上へ
+                    self.set_color(p, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(g, Color::Red);
+                    z = g;
+                } else {
+                    // 叔父が黒: zが内側なら外側に回転してから祖父で回転
+                    if z == self.nodes[p].right {
+                        z = p;
+                        self.rotate_left(z);
+                    }
+                    let p = self.nodes[z].parent;
+                    let g = self.nodes[p].parent;
+                    self.set_color(p, Color::Black);
+                    self.set_color(g, Color::Red);
+                    self.rotate_right(g);
+                }
+            } else {
+                let uncle = self.nodes[g].left;
+                if self.color(uncle) == Color::Red {
+                    self.set_color(p, Color::Black);
+                    self.set_color(uncle, Color::Black);
+                    self.set_color(g, Color::Red);
+                    z = g;
+                } else {
+                    if z == self.nodes[p].left {
+                        z = p;
+                        self.rotate_right(z);
+                    }
+                    let p = self.nodes[z].parent;
+                    let g = self.nodes[p].parent;
+                    self.set_color(p, Color::Black);
+                    self.set_color(g, Color::Red);
+                    self.rotate_left(g);
+                }
+            }
+        }
+
+        self.set_color(self.root, Color::Black);
+    }
+
+    /// 削除で黒が1つ足りなくなったxの位置を修復
+    fn delete_fixup(&mut self, mut x: usize) {
+        while x != self.root && self.color(x) == Color::Black {
+            let p = self.nodes[x].parent;
+
+            if x == self.nodes[p].left {
+                let mut w = self.nodes[p].right;
+                // 兄弟が赤: 回転して兄弟を黒にする
+                if self.color(w) == Color::Red {
+                    self.set_color(w, Color::Black);
+                    self.set_color(p, Color::Red);
+                    self.rotate_left(p);
+                    w = self.nodes[self.nodes[x].parent].right;
+                }
+
+                let w_left = self.nodes[w].left;
+                let w_right = self.nodes[w].right;
+                if self.color(w_left) == Color::Black
+                    && self.color(w_right) == Color::Black
+                {
+                    // 兄弟の子が両方黒: 兄弟を赤にして上へ
+                    self.set_color(w, Color::Red);
+                    x = self.nodes[x].parent;
+                } else {
+                    // 兄弟の外側の子が黒なら内側を外側に回転
+                    if self.color(w_right) == Color::Black {
+                        self.set_color(w_left, Color::Black);
+                        self.set_color(w, Color::Red);
+                        self.rotate_right(w);
+                        w = self.nodes[self.nodes[x].parent].right;
+                    }
+                    let p = self.nodes[x].parent;
+                    self.set_color(w, self.color(p));
+                    self.set_color(p, Color::Black);
+                    self.set_color(self.nodes[w].right, Color::Black);
+                    self.rotate_left(p);
+                    x = self.root;
+                }
+            } else {
+                let mut w = self.nodes[p].left;
+                if self.color(w) == Color::Red {
+                    self.set_color(w, Color::Black);
+                    self.set_color(p, Color::Red);
+                    self.rotate_right(p);
+                    w = self.nodes[self.nodes[x].parent].left;
+                }
+
+                let w_left = self.nodes[w].left;
+                let w_right = self.nodes[w].right;
+                if self.color(w_left) == Color::Black
+                    && self.color(w_right) == Color::Black
+                {
+                    self.set_color(w, Color::Red);
+                    x = self.nodes[x].parent;
+                } else {
+                    if self.color(w_left) == Color::Black {
+                        self.set_color(w_right, Color::Black);
+                        self.set_color(w, Color::Red);
+                        self.rotate_left(w);
+                        w = self.nodes[self.nodes[x].parent].left;
+                    }
+                    let p = self.nodes[x].parent;
+                    self.set_color(w, self.color(p));
+                    self.set_color(p, Color::Black);
+                    self.set_color(self.nodes[w].left, Color::Black);
+                    self.rotate_right(p);
+                    x = self.root;
+                }
+            }
+        }
+
+        self.set_color(x, Color::Black);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudo_random_keys(n: usize) -> Vec<i32> {
+        let mut x: u32 = 7;
+        let mut keys = Vec::new();
+        while keys.len() < n {
+            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+            let k = ((x >> 8) % 10_000) as i32;
+            if !keys.contains(&k) {
+                keys.push(k);
+            }
+        }
+        keys
+    }
+
+    #[test]
+    fn test_rbtree_insertion_and_search() {
+        let mut t = RedBlackTree::new();
+
+        for k in [10, 20, 5, 6, 12, 30, 7, 17] {
+            assert!(t.insert(k), "{k} should be inserted");
+            assert_eq!(t.check_invariants().map(|_| ()), Ok(()));
+        }
+
+        assert!(t.search(6), "6 should be present");
+        assert!(!t.search(15), "15 should not be present");
+        assert!(!t.insert(10), "duplicate key should be rejected");
+        assert_eq!(t.get_total_keys(), 8);
+        assert_eq!(t.keys(), vec![5, 6, 7, 10, 12, 17, 20, 30]);
+    }
+
+    #[test]
+    fn test_rbtree_sequential_insert_rotates() {
+        let mut t = RedBlackTree::new();
+
+        t.insert(1);
+        t.insert(2);
+        t.insert(3);
+
+        // 1-2-3 と右に連なるので、1を軸に左回転して2が根になる
+        assert_eq!(
+            t.events(),
+            &[
+                RbEvent::Insert { key: 3 },
+                RbEvent::Recolor {
+                    key: 2,
+                    color: Color::Black
+                },
+                RbEvent::Recolor {
+                    key: 1,
+                    color: Color::Red
+                },
+                RbEvent::RotateLeft { key: 1 },
+            ]
+        );
+        assert_eq!(t.nodes[t.root].key, 2);
+        assert!(t.is_valid());
+    }
+
+    #[test]
+    fn test_rbtree_recolor_without_rotation() {
+        let mut t = RedBlackTree::new();
+
+        for k in [10, 5, 15] {
+            t.insert(k);
+        }
+        t.insert(1);
+
+        // 叔父(15)が赤なので色の変更だけで済む
+        assert!(t.events().iter().all(|e| matches!(
+            e,
+            RbEvent::Insert { .. } | RbEvent::Recolor { .. }
+        )));
+        assert!(t.is_valid());
+    }
+
+    #[test]
+    fn test_rbtree_height_is_logarithmic() {
+        let mut t = RedBlackTree::new();
+        for k in 1..=1023 {
+            t.insert(k);
+        }
+        assert!(t.get_height() <= 2 * 10, "height {}", t.get_height());
+        assert!(t.is_valid());
+    }
+
+    #[test]
+    fn test_rbtree_delete_keeps_invariants() {
+        let keys = pseudo_random_keys(300);
+        let mut t = RedBlackTree::new();
+        for &k in &keys {
+            t.insert(k);
+        }
+
+        for (i, &k) in keys.iter().enumerate().rev() {
+            assert!(t.delete(k), "{k} should be deleted");
+            assert!(!t.search(k), "{k} should not be present");
+            if let Err(e) = t.check_invariants() {
+                panic!("invalid after deleting {k}: {e}");
+            }
+            assert_eq!(t.get_total_keys(), i);
+        }
+        assert_eq!(t.get_height(), 0);
+    }
+
+    #[test]
+    fn test_rbtree_delete_nonexistent_key() {
+        let mut t = RedBlackTree::new();
+        t.insert(10);
+        t.insert(20);
+
+        assert!(!t.delete(99), "99 should not be deleted");
+        assert_eq!(t.get_total_keys(), 2);
+        assert!(t.events().is_empty());
+    }
+
+    #[test]
+    fn test_rbtree_top_down_delete_keeps_invariants() {
+        let keys = pseudo_random_keys(300);
+        let mut t = RedBlackTree::new();
+        for &k in &keys {
+            t.insert_top_down(k);
+        }
+
+        for (i, &k) in keys.iter().enumerate() {
+            assert!(t.delete_top_down(k), "{k} should be deleted");
+            assert!(!t.search(k), "{k} should not be present");
+            if let Err(e) = t.check_invariants() {
+                panic!("invalid after deleting {k}: {e}");
+            }
+            assert_eq!(t.get_total_keys(), keys.len() - i - 1);
+            // 各手の区切りはイベント列を先頭から覆う
+            let steps = t.steps();
+            assert_eq!(
+                steps.iter().map(|s| s.len()).sum::<usize>(),
+                t.events().len()
+            );
+        }
+        assert!(!t.delete_top_down(keys[0]));
+        assert!(t.events().is_empty());
+    }
+
+    #[test]
+    fn test_rbtree_top_down_insert_flips_colors() {
+        let mut t = RedBlackTree::new();
+        for k in [2, 1, 3] {
+            t.insert_top_down(k);
+        }
+        t.insert_top_down(4);
+
+        // 根の4-ノード [1|2|3] を分割してから4を挿入する
+        assert_eq!(
+            t.events(),
+            &[RbEvent::ColorFlip { key: 2 }, RbEvent::Insert { key: 4 }]
+        );
+        assert!(t.is_valid());
+
+        let keys = pseudo_random_keys(300);
+        let mut t = RedBlackTree::new();
+        for &k in &keys {
+            assert!(t.insert_top_down(k));
+            if let Err(e) = t.check_invariants() {
+                panic!("invalid after inserting {k}: {e}");
+            }
+        }
+        assert!(!t.insert_top_down(keys[0]));
+        assert_eq!(t.get_total_keys(), keys.len());
+    }
+
+    #[test]
+    fn test_rbtree_shape_round_trip() {
+        let mut t = RedBlackTree::new();
+        for k in 1..=50 {
+            t.insert(k);
+        }
+
+        let copy = RedBlackTree::from_shape(t.shape().as_ref());
+        assert_eq!(copy.shape(), t.shape());
+        assert_eq!(copy.keys(), t.keys());
+        assert!(copy.is_valid());
+    }
+
+    #[test]
+    fn test_rbtree_reuses_freed_nodes() {
+        let mut t = RedBlackTree::new();
+        for k in 1..=20 {
+            t.insert(k);
+        }
+        for k in 1..=10 {
+            t.delete(k);
+        }
+        for k in 21..=30 {
+            t.insert(k);
+        }
+        assert_eq!(t.nodes.len(), 21);
+        assert_eq!(t.keys(), (11..=30).collect::<Vec<_>>());
+        assert!(t.is_valid());
+    }
+}
@@ -0,0 +1,5 @@
+mod parse;
+mod run;
+
+pub use parse::{Command, Script};
+pub use run::{ScriptRun, ScriptStep};
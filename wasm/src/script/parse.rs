@@ -0,0 +1,186 @@
+use crate::btree::MAX_MIN_DEGREE;
+use std::fmt;
+
+/// スクリプトの1文
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// 最小次数tの空の木を作る
+    New {
+        t: usize,
+    },
+    Insert(Vec<i32>),
+    Delete(Vec<i32>),
+    Search(Vec<i32>),
+}
+
+/// 操作スクリプト
+///
+/// ```text
+/// # コメント
+/// new t=3; insert 10 20 5
+/// delete 6; search 12
+/// ```
+///
+/// 文は`;`か改行で区切る。最初の文は`new`でなければならない
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Script {
+    pub commands: Vec<Command>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut commands = Vec::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line_no = line_no + 1;
+            let line = match line.find('#') {
+                Some(pos) => &line[..pos],
+                None => line,
+            };
+            for statement in line.split(';') {
+                let fields: Vec<&str> =
+                    statement.split_whitespace().collect();
+                let Some((&name, args)) = fields.split_first() else {
+                    continue;
+                };
+                let command = parse_command(name, args)
+                    .map_err(|e| format!("line {line_no}: {e}"))?;
+                if commands.is_empty()
+                    && !matches!(command, Command::New { .. })
+                {
+                    return Err(format!(
+                        "line {line_no}: script must start with 'new'"
+                    ));
+                }
+                commands.push(command);
+            }
+        }
+        if commands.is_empty() {
+            return Err("empty script".into());
+        }
+        Ok(Script { commands })
+    }
+}
+
+fn parse_command(name: &str, args: &[&str]) -> Result<Command, String> {
+    match name {
+        "new" => {
+            let [arg] = args else {
+                return Err("usage: new t=<min degree>".into());
+            };
+            let t = arg
+                .strip_prefix("t=")
+                .ok_or(format!("unknown option '{arg}'"))?;
+            let t: usize = t
+                .parse()
+                .map_err(|_| format!("invalid min degree '{t}'"))?;
+            if t < 2 {
+                return Err("min degree must be at least 2".into());
+            }
+            if t > MAX_MIN_DEGREE {
+                return Err(format!(
+                    "min degree must be at most {MAX_MIN_DEGREE}"
+                ));
+            }
+            Ok(Command::New { t })
+        }
+        "insert" => Ok(Command::Insert(parse_keys(name, args)?)),
+        "delete" => Ok(Command::Delete(parse_keys(name, args)?)),
+        "search" => Ok(Command::Search(parse_keys(name, args)?)),
+        _ => Err(format!("unknown command '{name}'")),
+    }
+}
+
+fn parse_keys(name: &str, args: &[&str]) -> Result<Vec<i32>, String> {
+    if args.is_empty() {
+        return Err(format!("'{name}' needs at least one key"));
+    }
+    args.iter()
+        .map(|s| s.parse().map_err(|_| format!("invalid key '{s}'")))
+        .collect()
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, keys) = match self {
+            Command::New { t } => return write!(f, "new t={t}"),
+            Command::Insert(keys) => ("insert", keys),
+            Command::Delete(keys) => ("delete", keys),
+            Command::Search(keys) => ("search", keys),
+        };
+        write!(f, "{name}")?;
+        for key in keys {
+            write!(f, " {key}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 1行にまとめた正規形 (`new t=3; insert 10 20`)
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, command) in self.commands.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{command}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_print() {
+        let text =
+            "# fixture\nnew t=3; insert 10 20 5\n\ndelete 6;search -12;";
+        let script = Script::parse(text).unwrap();
+        assert_eq!(
+            script.commands,
+            vec![
+                Command::New { t: 3 },
+                Command::Insert(vec![10, 20, 5]),
+                Command::Delete(vec![6]),
+                Command::Search(vec![-12]),
+            ]
+        );
+        let canonical = script.to_string();
+        assert_eq!(
+            canonical,
+            "new t=3; insert 10 20 5; delete 6; search -12"
+        );
+        assert_eq!(Script::parse(&canonical), Ok(script));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text| Script::parse(text).unwrap_err();
+        assert_eq!(error(""), "empty script");
+        assert_eq!(
+            error("insert 1"),
+            "line 1: script must start with 'new'"
+        );
+        assert_eq!(
+            error("new t=1"),
+            "line 1: min degree must be at least 2"
+        );
+        assert_eq!(
+            error("# shared\nnew t=100000000000; insert 1"),
+            "line 2: min degree must be at most 1024"
+        );
+        assert_eq!(
+            error("new t=2\ninsert 1 x"),
+            "line 2: invalid key 'x'"
+        );
+        assert_eq!(
+            error("new t=2; delete"),
+            "line 1: 'delete' needs at least one key"
+        );
+        assert_eq!(
+            error("new t=2; push 1"),
+            "line 1: unknown command 'push'"
+        );
+    }
+}
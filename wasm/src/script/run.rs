@@ -0,0 +1,236 @@
+use crate::btree::{BTree, BTreeEvent};
+use crate::json::JsonValue;
+use crate::locale::Locale;
+use crate::script::parse::{Command, Script};
+use crate::trace::{TraceEvent, trace_to_json};
+use wasm_bindgen::prelude::*;
+
+/// 1つのキーに対する操作とその結果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptStep {
+    /// 実行した操作 (キーは1つだけ)
+    pub command: Command,
+
+    /// 探索はキーが見つかったか、削除はキーを消したか。それ以外はtrue
+    pub result: bool,
+
+    /// 操作中の構造変更
+    pub events: Vec<BTreeEvent>,
+
+    /// 操作のトレース
+    pub trace: Vec<TraceEvent>,
+}
+
+impl ScriptStep {
+    pub fn to_json(&self, locale: Locale) -> JsonValue {
+        JsonValue::object([
+            ("command", self.command.to_string().as_str().into()),
+            ("result", self.result.into()),
+            ("trace", trace_to_json(&self.trace, locale)),
+        ])
+    }
+}
+
+/// スクリプトを実行した結果
+#[wasm_bindgen]
+pub struct ScriptRun {
+    script: Script,
+    steps: Vec<ScriptStep>,
+    tree: BTree,
+}
+
+#[wasm_bindgen]
+impl ScriptRun {
+    /// スクリプトを読んで実行
+    pub fn run(text: &str) -> Result<ScriptRun, String> {
+        Ok(Script::parse(text)?.run())
+    }
+
+    /// 正規形のスクリプト (共有用URLなどに使う)
+    #[wasm_bindgen]
+    pub fn get_script(&self) -> String {
+        self.script.to_string()
+    }
+
+    /// 各操作の結果 ([{command, result, trace}])
+    #[wasm_bindgen]
+    pub fn get_steps(&self, locale: &str) -> Result<JsValue, String> {
+        let locale: Locale = locale.parse()?;
+        let steps = self.steps.iter().map(|s| s.to_json(locale));
+        Ok(JsonValue::Array(steps.collect()).to_js_value())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_step_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// 実行後の木の構造
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        self.tree.get_structure()
+    }
+}
+
+impl ScriptRun {
+    pub fn steps(&self) -> &[ScriptStep] {
+        &self.steps
+    }
+
+    /// 実行後の木
+    pub fn tree(&self) -> &BTree {
+        &self.tree
+    }
+}
+
+impl Script {
+    /// 先頭から順に実行する
+    ///
+    /// 複数のキーを持つ文はキーごとの操作に分ける。
+    /// `new`は木を作り直す
+    pub fn run(&self) -> ScriptRun {
+        let mut tree = BTree::new(2);
+        let mut steps = Vec::new();
+        for command in &self.commands {
+            let (keys, op): (&[i32], fn(i32) -> Command) = match command {
+                Command::New { t } => {
+                    tree = BTree::new(*t);
+                    steps.push(ScriptStep {
+                        command: command.clone(),
+                        result: true,
+                        events: Vec::new(),
+                        trace: Vec::new(),
+                    });
+                    continue;
+                }
+                Command::Insert(keys) => {
+                    (keys, |k| Command::Insert(vec![k]))
+                }
+                Command::Delete(keys) => {
+                    (keys, |k| Command::Delete(vec![k]))
+                }
+                Command::Search(keys) => {
+                    (keys, |k| Command::Search(vec![k]))
+                }
+            };
+            for &key in keys {
+                let command = op(key);
+                let mut trace = Vec::new();
+                let result = match command {
+                    Command::Insert(_) => {
+                        tree.insert_with(key, &mut trace);
+                        true
+                    }
+                    Command::Delete(_) => {
+                        tree.delete_with(key, &mut trace)
+                    }
+                    _ => tree.search_with(key, &mut trace),
+                };
+                let events = match command {
+                    Command::Search(_) => Vec::new(),
+                    _ => tree.events().to_vec(),
+                };
+                steps.push(ScriptStep {
+                    command,
+                    result,
+                    events,
+                    trace,
+                });
+            }
+        }
+        ScriptRun {
+            script: self.clone(),
+            steps,
+            tree,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_in_order(tree: &BTree) -> Vec<i32> {
+        fn walk(node: &crate::btree::BTreeNode, out: &mut Vec<i32>) {
+            let keys = node.keys();
+            let children = node.child_nodes();
+            for (i, key) in keys.iter().enumerate() {
+                if let Some(child) = children.get(i) {
+                    walk(child, out);
+                }
+                out.push(*key);
+            }
+            if let Some(child) = children.get(keys.len()) {
+                walk(child, out);
+            }
+        }
+        let mut out = Vec::new();
+        if let Some(root) = tree.root() {
+            walk(root, &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn test_run_script() {
+        let run = ScriptRun::run(
+            "new t=2; insert 10 20 5 30; delete 6 20; search 5 20",
+        )
+        .unwrap();
+        let summary: Vec<(String, bool)> = run
+            .steps()
+            .iter()
+            .map(|s| (s.command.to_string(), s.result))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("new t=2", true),
+                ("insert 10", true),
+                ("insert 20", true),
+                ("insert 5", true),
+                ("insert 30", true),
+                ("delete 6", false),
+                ("delete 20", true),
+                ("search 5", true),
+                ("search 20", false),
+            ]
+            .map(|(c, r)| (c.to_string(), r))
+        );
+        assert_eq!(keys_in_order(run.tree()), vec![5, 10, 30]);
+
+        // 4つ目の挿入で根が分割される
+        assert_eq!(
+            run.steps()[4].events,
+            vec![BTreeEvent::Split {
+                keys: vec![5, 10, 20],
+                median: 10,
+            }]
+        );
+        assert!(run.steps()[8].trace.len() > 2);
+    }
+
+    #[test]
+    fn test_run_is_deterministic() {
+        let text = "new t=3\ninsert 8 3 9 1 7 2 6 4 5\ndelete 3 7\nnew t=2; insert 1";
+        let a = ScriptRun::run(text).unwrap();
+        ScriptRun::run("new t=2; insert 1 2 3 4; search 4").unwrap();
+        let b = ScriptRun::run(&a.get_script()).unwrap();
+
+        // トレースの識別子まで含めて同じ
+        assert_eq!(a.steps(), b.steps());
+        let json = |run: &ScriptRun| {
+            run.steps()
+                .iter()
+                .map(|s| s.to_json(Locale::En).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(json(&a), json(&b));
+        assert_eq!(a.tree().get_min_degree(), 2);
+        assert_eq!(keys_in_order(a.tree()), vec![1]);
+        assert_eq!(
+            a.steps()[1].to_json(Locale::En).get("command"),
+            Some(&"insert 8".into())
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod node;
+mod operation;
+
+pub use node::PathStep;
+pub use operation::SkipList;
@@ -0,0 +1,49 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+/// 先頭(ヘッダ)ノードのインデックス
+pub(super) const HEAD: usize = 0;
+
+/// スキップリストのノード
+#[derive(Clone, Debug)]
+pub(super) struct SkipNode {
+    /// キー (ヘッダでは使わない)
+    pub key: i32,
+
+    /// 各レベルの次のノード (レベル0が最下層)
+    pub next: Vec<Option<usize>>,
+}
+
+impl SkipNode {
+    pub fn new(key: i32, level: usize) -> Self {
+        SkipNode {
+            key,
+            next: vec![None; level],
+        }
+    }
+}
+
+/// 探索経路の1歩
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStep {
+    /// レベルlevelで右隣のキーkeyのノードへ進む
+    Right { level: usize, key: i32 },
+
+    /// キーkeyのノード (ヘッダの場合はNone) でレベルlevelへ下りる
+    Down { level: usize, key: Option<i32> },
+}
+
+impl PathStep {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let (kind, level, key) = match *self {
+            PathStep::Right { level, key } => ("right", level, Some(key)),
+            PathStep::Down { level, key } => ("down", level, key),
+        };
+        let _ = js_sys::Reflect::set(&obj, &"type".into(), &kind.into());
+        let _ = js_sys::Reflect::set(&obj, &"level".into(), &level.into());
+        let key = key.map_or(JsValue::NULL, JsValue::from);
+        let _ = js_sys::Reflect::set(&obj, &"key".into(), &key);
+        obj.into()
+    }
+}
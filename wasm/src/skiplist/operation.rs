@@ -0,0 +1,346 @@
+use crate::rng::SplitMix64;
+use crate::skiplist::node::{HEAD, PathStep, SkipNode};
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+/// レベルの上限
+const MAX_LEVEL: usize = 16;
+
+// Skip List
+//
+// 各ノードの高さはシード付き乱数で決める(確率1/2で1段ずつ伸びる)。
+// 同じシードと同じ操作列からは常に同じ構造ができる。
+#[wasm_bindgen]
+pub struct SkipList {
+    // ノードの配列 (先頭はヘッダ)
+    nodes: Vec<SkipNode>,
+
+    // 削除されて再利用できるノードのインデックス
+    free: Vec<usize>,
+
+    // 現在使われているレベルの数
+    level: usize,
+
+    // キーの数
+    len: usize,
+
+    // レベルを決める乱数
+    rng: SplitMix64,
+
+    // 直前の操作の探索経路
+    path: Vec<PathStep>,
+}
+
+#[wasm_bindgen]
+impl SkipList {
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u32) -> Self {
+        SkipList {
+            nodes: vec![SkipNode::new(0, MAX_LEVEL)],
+            free: Vec::new(),
+            level: 1,
+            len: 0,
+            rng: SplitMix64::new(seed as u64),
+            path: Vec::new(),
+        }
+    }
+
+    /// キーkを探索
+    pub fn search(&mut self, k: i32) -> bool {
+        let update = self.descend(k);
+        self.next(update[0], 0)
+            .is_some_and(|n| self.nodes[n].key == k)
+    }
+
+    /// キーkを挿入 (既に存在する場合はfalse)
+    pub fn insert(&mut self, k: i32) -> bool {
+        let mut update = self.descend(k);
+        if self
+            .next(update[0], 0)
+            .is_some_and(|n| self.nodes[n].key == k)
+        {
+            return false;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            // 新しいレベルではヘッダの直後に入る
+            update[self.level..level].fill(HEAD);
+            self.level = level;
+        }
+
+        let x = self.alloc(k, level);
+        for (lv, &prev) in update.iter().enumerate().take(level) {
+            self.nodes[x].next[lv] = self.nodes[prev].next[lv];
+            self.nodes[prev].next[lv] = Some(x);
+        }
+        self.len += 1;
+        true
+    }
+
+    /// キーkを削除 (存在しない場合はfalse)
+    pub fn delete(&mut self, k: i32) -> bool {
+        let update = self.descend(k);
+        let x = match self.next(update[0], 0) {
+            Some(x) if self.nodes[x].key == k => x,
+            _ => return false,
+        };
+
+        for (lv, &prev) in update.iter().enumerate().take(self.level) {
+            if self.nodes[prev].next[lv] != Some(x) {
+                break;
+            }
+            self.nodes[prev].next[lv] = self.nodes[x].next[lv];
+        }
+
+        // 空になった上のレベルを取り除く
+        while self.level > 1
+            && self.nodes[HEAD].next[self.level - 1].is_none()
+        {
+            self.level -= 1;
+        }
+
+        self.free.push(x);
+        self.len -= 1;
+        true
+    }
+
+    /// lo以上hi以下のキーを昇順に取得
+    ///
+    /// 探索経路にはloまでの経路と、最下層を右へたどる手順が入る
+    pub fn range(&mut self, lo: i32, hi: i32) -> Vec<i32> {
+        let update = self.descend(lo);
+        let mut keys = Vec::new();
+        let mut x = self.next(update[0], 0);
+        while let Some(n) = x {
+            let key = self.nodes[n].key;
+            if key > hi {
+                break;
+            }
+            keys.push(key);
+            self.path.push(PathStep::Right { level: 0, key });
+            x = self.next(n, 0);
+        }
+        keys
+    }
+
+    /// レベルごとのキーの列 (先頭がレベル0)
+    #[wasm_bindgen]
+    pub fn get_levels(&self) -> Array {
+        Array::from_iter(self.levels().iter().map(|keys| {
+            JsValue::from(Array::from_iter(
+                keys.iter().map(|k| JsValue::from(*k)),
+            ))
+        }))
+    }
+
+    /// 直前の操作の探索経路を取得
+    #[wasm_bindgen]
+    pub fn get_search_path(&self) -> Array {
+        Array::from_iter(self.path.iter().map(PathStep::to_js_value))
+    }
+
+    /// キーの総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    /// 使われているレベルの数を取得
+    #[wasm_bindgen]
+    pub fn get_level_count(&self) -> usize {
+        self.level
+    }
+}
+
+impl SkipList {
+    /// レベルごとのキーの列 (先頭がレベル0)
+    pub fn levels(&self) -> Vec<Vec<i32>> {
+        (0..self.level)
+            .map(|lv| {
+                let mut keys = Vec::new();
+                let mut x = self.next(HEAD, lv);
+                while let Some(n) = x {
+                    keys.push(self.nodes[n].key);
+                    x = self.next(n, lv);
+                }
+                keys
+            })
+            .collect()
+    }
+
+    /// 直前の操作の探索経路
+    pub fn path(&self) -> &[PathStep] {
+        &self.path
+    }
+
+    fn next(&self, x: usize, level: usize) -> Option<usize> {
+        self.nodes[x].next[level]
+    }
+
+    fn alloc(&mut self, key: i32, level: usize) -> usize {
+        let node = SkipNode::new(key, level);
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.chance(0.5) {
+            level += 1;
+        }
+        level
+    }
+
+    /// 上のレベルから右と下へ進み、各レベルでk未満の最後のノードを返す
+    fn descend(&mut self, k: i32) -> Vec<usize> {
+        self.path.clear();
+        let mut update = vec![HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+
+        for lv in (0..self.level).rev() {
+            while let Some(n) = self.next(x, lv) {
+                if self.nodes[n].key >= k {
+                    break;
+                }
+                self.path.push(PathStep::Right {
+                    level: lv,
+                    key: self.nodes[n].key,
+                });
+                x = n;
+            }
+            update[lv] = x;
+
+            if lv > 0 {
+                self.path.push(PathStep::Down {
+                    level: lv - 1,
+                    key: (x != HEAD).then(|| self.nodes[x].key),
+                });
+            }
+        }
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skiplist_insertion_and_search() {
+        let mut s = SkipList::new(1);
+
+        for k in [10, 20, 5, 6, 12, 30, 7, 17] {
+            assert!(s.insert(k), "{k} should be inserted");
+        }
+
+        assert!(s.search(6), "6 should be present");
+        assert!(!s.search(15), "15 should not be present");
+        assert!(!s.insert(6), "duplicate key should be rejected");
+        assert_eq!(s.get_total_keys(), 8);
+        assert_eq!(s.levels()[0], vec![5, 6, 7, 10, 12, 17, 20, 30]);
+    }
+
+    #[test]
+    fn test_skiplist_same_seed_same_structure() {
+        let build = |seed| {
+            let mut s = SkipList::new(seed);
+            for k in 0..200 {
+                s.insert((k * 37) % 101);
+            }
+            s.levels()
+        };
+
+        assert_eq!(build(3), build(3));
+        assert_ne!(build(3), build(4));
+    }
+
+    #[test]
+    fn test_skiplist_levels_are_nested() {
+        let mut s = SkipList::new(9);
+        for k in 0..500 {
+            s.insert(k);
+        }
+
+        let levels = s.levels();
+        assert!(levels.len() > 1);
+        for pair in levels.windows(2) {
+            assert!(pair[1].iter().all(|k| pair[0].contains(k)));
+            assert!(pair[1].len() <= pair[0].len());
+        }
+    }
+
+    #[test]
+    fn test_skiplist_search_path_moves_right_and_down() {
+        let mut s = SkipList::new(5);
+        for k in 0..100 {
+            s.insert(k);
+        }
+
+        assert!(s.search(73));
+        let path = s.path();
+        let downs = path
+            .iter()
+            .filter(|p| matches!(p, PathStep::Down { .. }))
+            .count();
+        assert_eq!(downs, s.get_level_count() - 1);
+
+        // 右へ進むキーは単調増加で、すべて73未満
+        let rights: Vec<i32> = path
+            .iter()
+            .filter_map(|p| match p {
+                PathStep::Right { key, .. } => Some(*key),
+                _ => None,
+            })
+            .collect();
+        assert!(rights.windows(2).all(|w| w[0] < w[1]));
+        assert!(rights.iter().all(|&k| k < 73));
+        // 線形探索より短い経路でたどり着く
+        assert!(path.len() < 73);
+    }
+
+    #[test]
+    fn test_skiplist_delete_and_range() {
+        let mut s = SkipList::new(11);
+        for k in 1..=50 {
+            s.insert(k);
+        }
+
+        for k in (1..=50).filter(|k| k % 2 == 0) {
+            assert!(s.delete(k), "{k} should be deleted");
+        }
+        assert!(!s.delete(2), "2 should already be deleted");
+        assert_eq!(s.get_total_keys(), 25);
+
+        assert_eq!(s.range(10, 20), vec![11, 13, 15, 17, 19]);
+        assert_eq!(s.range(60, 70), Vec::<i32>::new());
+        for level in s.levels() {
+            assert!(level.iter().all(|k| k % 2 == 1));
+        }
+    }
+
+    #[test]
+    fn test_skiplist_delete_all_shrinks_levels() {
+        let mut s = SkipList::new(2);
+        for k in 0..64 {
+            s.insert(k);
+        }
+        for k in 0..64 {
+            assert!(s.delete(k));
+        }
+        assert_eq!(s.get_level_count(), 1);
+        assert_eq!(s.levels(), vec![Vec::<i32>::new()]);
+
+        // 解放したノードを再利用する
+        s.insert(1);
+        assert!(s.nodes.len() <= 65);
+    }
+}
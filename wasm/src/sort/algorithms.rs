@@ -0,0 +1,291 @@
+use crate::rng::SplitMix64;
+use crate::sort::operation::PivotStrategy;
+use crate::sort::step::Recorder;
+
+/// 計数ソートで扱える値の範囲の上限
+const COUNTING_RANGE_LIMIT: i64 = 1 << 20;
+
+pub fn bubble(rec: &mut Recorder) {
+    let n = rec.data.len();
+    for i in 0..n {
+        let mut swapped = false;
+        for j in 0..n.saturating_sub(i + 1) {
+            if rec.less(j + 1, j) {
+                rec.swap(j, j + 1);
+                swapped = true;
+            }
+        }
+        // 交換がなければ整列済み
+        if !swapped {
+            break;
+        }
+    }
+}
+
+pub fn insertion(rec: &mut Recorder) {
+    for i in 1..rec.data.len() {
+        let mut j = i;
+        while j > 0 && rec.less(j, j - 1) {
+            rec.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+pub fn selection(rec: &mut Recorder) {
+    let n = rec.data.len();
+    for i in 0..n {
+        let mut min = i;
+        for j in i + 1..n {
+            if rec.less(j, min) {
+                min = j;
+            }
+        }
+        if min != i {
+            rec.swap(i, min);
+        }
+    }
+}
+
+pub fn merge(rec: &mut Recorder) {
+    let mut aux = rec.data.clone();
+    merge_sort(rec, 0, rec.data.len(), &mut aux);
+}
+
+fn merge_sort(rec: &mut Recorder, lo: usize, hi: usize, aux: &mut [i32]) {
+    if hi - lo < 2 {
+        return;
+    }
+    rec.range(lo, hi);
+
+    let mid = lo + (hi - lo) / 2;
+    merge_sort(rec, lo, mid, aux);
+    merge_sort(rec, mid, hi, aux);
+
+    // 左右の整列済みの列を作業領域に退避してから書き戻す
+    aux[lo..hi].copy_from_slice(&rec.data[lo..hi]);
+    let (mut i, mut j) = (lo, mid);
+    for k in lo..hi {
+        // 等しい場合は左を先にして安定にする
+        let take_left =
+            i < mid && (j >= hi || !rec.less_values(j, i, aux[j], aux[i]));
+        if take_left {
+            rec.write(k, aux[i]);
+            i += 1;
+        } else {
+            rec.write(k, aux[j]);
+            j += 1;
+        }
+    }
+}
+
+pub fn quick(rec: &mut Recorder, strategy: PivotStrategy, seed: u32) {
+    let mut rng = SplitMix64::new(seed as u64);
+    let n = rec.data.len();
+    quick_sort(rec, 0, n, strategy, &mut rng);
+}
+
+fn quick_sort(
+    rec: &mut Recorder,
+    mut lo: usize,
+    mut hi: usize,
+    strategy: PivotStrategy,
+    rng: &mut SplitMix64,
+) {
+    // 小さい方だけ再帰し、大きい方はループで処理して再帰を浅く保つ
+    while hi - lo >= 2 {
+        rec.range(lo, hi);
+        let p = partition(rec, lo, hi, strategy, rng);
+
+        if p - lo < hi - p {
+            quick_sort(rec, lo, p, strategy, rng);
+            lo = p + 1;
+        } else {
+            quick_sort(rec, p + 1, hi, strategy, rng);
+            hi = p;
+        }
+    }
+}
+
+/// [lo, hi) をピボットで分割し、ピボットの最終位置を返す (Lomuto)
+fn partition(
+    rec: &mut Recorder,
+    lo: usize,
+    hi: usize,
+    strategy: PivotStrategy,
+    rng: &mut SplitMix64,
+) -> usize {
+    let last = hi - 1;
+    let p = choose_pivot(rec, lo, hi, strategy, rng);
+    if p != last {
+        rec.swap(p, last);
+    }
+
+    let mut store = lo;
+    for i in lo..last {
+        if rec.less(i, last) {
+            if i != store {
+                rec.swap(i, store);
+            }
+            store += 1;
+        }
+    }
+    if store != last {
+        rec.swap(store, last);
+    }
+
+    rec.partition(lo, hi, store);
+    store
+}
+
+fn choose_pivot(
+    rec: &mut Recorder,
+    lo: usize,
+    hi: usize,
+    strategy: PivotStrategy,
+    rng: &mut SplitMix64,
+) -> usize {
+    let mid = lo + (hi - lo) / 2;
+    match strategy {
+        PivotStrategy::First => lo,
+        PivotStrategy::Last => hi - 1,
+        PivotStrategy::Middle => mid,
+        PivotStrategy::Random => {
+            lo + (rng.next_u64() % (hi - lo) as u64) as usize
+        }
+        PivotStrategy::MedianOfThree => {
+            let (a, b, c) = (lo, mid, hi - 1);
+            if rec.less(a, b) {
+                if rec.less(b, c) {
+                    b
+                } else if rec.less(a, c) {
+                    c
+                } else {
+                    a
+                }
+            } else if rec.less(a, c) {
+                a
+            } else if rec.less(b, c) {
+                c
+            } else {
+                b
+            }
+        }
+    }
+}
+
+pub fn heap(rec: &mut Recorder) {
+    let n = rec.data.len();
+    // 最大ヒープを作り、根を末尾と交換していく
+    for i in (0..n / 2).rev() {
+        sift_down(rec, i, n);
+    }
+    for end in (1..n).rev() {
+        rec.swap(0, end);
+        sift_down(rec, 0, end);
+    }
+}
+
+fn sift_down(rec: &mut Recorder, mut i: usize, n: usize) {
+    loop {
+        let mut largest = i;
+        for c in [2 * i + 1, 2 * i + 2] {
+            if c < n && rec.less(largest, c) {
+                largest = c;
+            }
+        }
+        if largest == i {
+            break;
+        }
+        rec.swap(i, largest);
+        i = largest;
+    }
+}
+
+pub fn shell(rec: &mut Recorder) {
+    let n = rec.data.len();
+
+    // Knuthの間隔列 1, 4, 13, 40, ...
+    let mut gap = 1;
+    while gap < n / 3 {
+        gap = 3 * gap + 1;
+    }
+
+    while gap >= 1 {
+        for i in gap..n {
+            let mut j = i;
+            while j >= gap && rec.less(j, j - gap) {
+                rec.swap(j, j - gap);
+                j -= gap;
+            }
+        }
+        gap /= 3;
+    }
+}
+
+pub fn counting(rec: &mut Recorder) -> Result<(), String> {
+    let (Some(&min), Some(&max)) =
+        (rec.data.iter().min(), rec.data.iter().max())
+    else {
+        return Ok(());
+    };
+    let range = max as i64 - min as i64 + 1;
+    if range > COUNTING_RANGE_LIMIT {
+        return Err(format!(
+            "counting sort supports a value range up to {COUNTING_RANGE_LIMIT}, got {range}"
+        ));
+    }
+
+    let mut counts = vec![0usize; range as usize];
+    for &v in &rec.data {
+        counts[(v as i64 - min as i64) as usize] += 1;
+    }
+
+    let mut k = 0;
+    for (offset, &count) in counts.iter().enumerate() {
+        let value = (min as i64 + offset as i64) as i32;
+        for _ in 0..count {
+            rec.write(k, value);
+            k += 1;
+        }
+    }
+    Ok(())
+}
+
+pub fn radix(rec: &mut Recorder) {
+    let Some(&min) = rec.data.iter().min() else {
+        return;
+    };
+    // 負の値も扱えるよう、最小値からの差で桁を見る
+    let offset = |v: i32| (v as i64 - min as i64) as u64;
+    let max = rec.data.iter().map(|&v| offset(v)).max().unwrap_or(0);
+
+    // 10進数で下の桁から安定な計数ソートを繰り返す
+    let mut exp = 1u64;
+    while max / exp > 0 {
+        let digit = |v: i32| ((offset(v) / exp) % 10) as usize;
+
+        let mut starts = [0usize; 10];
+        for &v in &rec.data {
+            starts[digit(v)] += 1;
+        }
+        let mut total = 0;
+        for s in starts.iter_mut() {
+            let count = *s;
+            *s = total;
+            total += count;
+        }
+
+        let mut output = vec![0; rec.data.len()];
+        for &v in &rec.data {
+            let d = digit(v);
+            output[starts[d]] = v;
+            starts[d] += 1;
+        }
+        for (k, v) in output.into_iter().enumerate() {
+            rec.write(k, v);
+        }
+
+        exp *= 10;
+    }
+}
@@ -0,0 +1,6 @@
+mod algorithms;
+mod operation;
+mod step;
+
+pub use operation::{PivotStrategy, SortAlgorithm, SortRun, sort};
+pub use step::SortStep;
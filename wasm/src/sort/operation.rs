@@ -0,0 +1,348 @@
+use crate::sort::algorithms;
+use crate::sort::step::{Recorder, SortStep};
+use js_sys::Array;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// ソートアルゴリズム
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortAlgorithm {
+    Bubble,
+    Insertion,
+    Selection,
+    Merge,
+    Quick,
+    Heap,
+    Shell,
+    Counting,
+    Radix,
+}
+
+impl SortAlgorithm {
+    pub const ALL: [SortAlgorithm; 9] = [
+        SortAlgorithm::Bubble,
+        SortAlgorithm::Insertion,
+        SortAlgorithm::Selection,
+        SortAlgorithm::Merge,
+        SortAlgorithm::Quick,
+        SortAlgorithm::Heap,
+        SortAlgorithm::Shell,
+        SortAlgorithm::Counting,
+        SortAlgorithm::Radix,
+    ];
+}
+
+impl FromStr for SortAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "bubble" => SortAlgorithm::Bubble,
+            "insertion" => SortAlgorithm::Insertion,
+            "selection" => SortAlgorithm::Selection,
+            "merge" => SortAlgorithm::Merge,
+            "quick" => SortAlgorithm::Quick,
+            "heap" => SortAlgorithm::Heap,
+            "shell" => SortAlgorithm::Shell,
+            "counting" => SortAlgorithm::Counting,
+            "radix" => SortAlgorithm::Radix,
+            _ => return Err(format!("unknown sort algorithm: {s}")),
+        })
+    }
+}
+
+/// クイックソートのピボットの選び方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotStrategy {
+    First,
+    #[default]
+    Last,
+    Middle,
+    MedianOfThree,
+    Random,
+}
+
+impl FromStr for PivotStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "first" => PivotStrategy::First,
+            "last" => PivotStrategy::Last,
+            "middle" => PivotStrategy::Middle,
+            "median3" => PivotStrategy::MedianOfThree,
+            "random" => PivotStrategy::Random,
+            _ => return Err(format!("unknown pivot strategy: {s}")),
+        })
+    }
+}
+
+/// ソートの実行結果と記録した手順
+#[wasm_bindgen]
+pub struct SortRun {
+    sorted: Vec<i32>,
+    steps: Vec<SortStep>,
+
+    /// 比較の回数
+    #[wasm_bindgen(readonly)]
+    pub comparisons: usize,
+
+    /// 交換の回数
+    #[wasm_bindgen(readonly)]
+    pub swaps: usize,
+
+    /// 配列への書き込みの回数 (交換は2回と数える)
+    #[wasm_bindgen(readonly)]
+    pub writes: usize,
+}
+
+#[wasm_bindgen]
+impl SortRun {
+    /// ソート後の配列
+    #[wasm_bindgen]
+    pub fn get_sorted(&self) -> Vec<i32> {
+        self.sorted.clone()
+    }
+
+    /// 記録した手順を取得
+    #[wasm_bindgen]
+    pub fn get_steps(&self) -> Array {
+        Array::from_iter(self.steps.iter().map(SortStep::to_js_value))
+    }
+}
+
+impl SortRun {
+    pub fn sorted(&self) -> &[i32] {
+        &self.sorted
+    }
+
+    pub fn steps(&self) -> &[SortStep] {
+        &self.steps
+    }
+
+    /// 手順を元の配列に順に適用した結果
+    ///
+    /// 記録した手順だけでアニメーションを再現できることの確認に使う
+    pub fn replay(&self, values: &[i32]) -> Vec<i32> {
+        let mut data = values.to_vec();
+        for step in &self.steps {
+            match *step {
+                SortStep::Swap { i, j } => data.swap(i, j),
+                SortStep::Write { index, value } => data[index] = value,
+                _ => {}
+            }
+        }
+        data
+    }
+}
+
+/// valuesをalgorithmでソートし、手順を記録する
+///
+/// pivotとseedはクイックソートのみで使う
+/// (pivot: "first" | "last" | "middle" | "median3" | "random")
+#[wasm_bindgen]
+pub fn sort(
+    values: &[i32],
+    algorithm: &str,
+    pivot: &str,
+    seed: u32,
+) -> Result<SortRun, String> {
+    let algorithm: SortAlgorithm = algorithm.parse()?;
+    let pivot: PivotStrategy = if pivot.is_empty() {
+        PivotStrategy::default()
+    } else {
+        pivot.parse()?
+    };
+    run(values, algorithm, pivot, seed)
+}
+
+pub fn run(
+    values: &[i32],
+    algorithm: SortAlgorithm,
+    pivot: PivotStrategy,
+    seed: u32,
+) -> Result<SortRun, String> {
+    let mut rec = Recorder::new(values.to_vec());
+    match algorithm {
+        SortAlgorithm::Bubble => algorithms::bubble(&mut rec),
+        SortAlgorithm::Insertion => algorithms::insertion(&mut rec),
+        SortAlgorithm::Selection => algorithms::selection(&mut rec),
+        SortAlgorithm::Merge => algorithms::merge(&mut rec),
+        SortAlgorithm::Quick => algorithms::quick(&mut rec, pivot, seed),
+        SortAlgorithm::Heap => algorithms::heap(&mut rec),
+        SortAlgorithm::Shell => algorithms::shell(&mut rec),
+        SortAlgorithm::Counting => algorithms::counting(&mut rec)?,
+        SortAlgorithm::Radix => algorithms::radix(&mut rec),
+    }
+
+    Ok(SortRun {
+        sorted: rec.data,
+        steps: rec.steps,
+        comparisons: rec.comparisons,
+        swaps: rec.swaps,
+        writes: rec.writes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> Vec<Vec<i32>> {
+        vec![
+            vec![],
+            vec![1],
+            vec![2, 1],
+            vec![5, 3, 8, 1, 9, 2, 7],
+            (0..50).collect(),
+            (0..50).rev().collect(),
+            vec![3; 20],
+            (0..200).map(|i| (i * 7919 % 401) - 200).collect(),
+            vec![i32::MIN, 0, i32::MAX, -1, 1],
+        ]
+    }
+
+    #[test]
+    fn test_every_algorithm_sorts_and_replays() {
+        let pivots = [
+            PivotStrategy::First,
+            PivotStrategy::Last,
+            PivotStrategy::Middle,
+            PivotStrategy::MedianOfThree,
+            PivotStrategy::Random,
+        ];
+
+        for values in inputs() {
+            let mut expected = values.clone();
+            expected.sort();
+
+            for algorithm in SortAlgorithm::ALL {
+                for pivot in pivots {
+                    let result = run(&values, algorithm, pivot, 1);
+                    if algorithm == SortAlgorithm::Counting
+                        && values.contains(&i32::MIN)
+                    {
+                        assert!(result.is_err());
+                        continue;
+                    }
+                    let result = result.unwrap();
+                    assert_eq!(result.sorted(), expected, "{algorithm:?}");
+                    assert_eq!(
+                        result.replay(&values),
+                        expected,
+                        "{algorithm:?} {pivot:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_totals_match_steps() {
+        let values: Vec<i32> = (0..64).map(|i| (i * 37) % 64).collect();
+        for algorithm in SortAlgorithm::ALL {
+            let r =
+                run(&values, algorithm, PivotStrategy::Middle, 0).unwrap();
+            let count = |f: fn(&SortStep) -> bool| {
+                r.steps().iter().filter(|s| f(s)).count()
+            };
+            assert_eq!(
+                r.comparisons,
+                count(|s| matches!(s, SortStep::Compare { .. }))
+            );
+            assert_eq!(
+                r.swaps,
+                count(|s| matches!(s, SortStep::Swap { .. }))
+            );
+            assert_eq!(
+                r.writes,
+                2 * r.swaps
+                    + count(|s| matches!(s, SortStep::Write { .. }))
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_comparison_sorts_do_not_compare() {
+        let values = vec![170, 45, 75, -90, 802, 24, 2, 66];
+        for algorithm in [SortAlgorithm::Counting, SortAlgorithm::Radix] {
+            let r = run(&values, algorithm, PivotStrategy::default(), 0)
+                .unwrap();
+            assert_eq!(r.comparisons, 0);
+            assert_eq!(r.sorted(), [-90, 2, 24, 45, 66, 75, 170, 802]);
+        }
+    }
+
+    #[test]
+    fn test_quick_sort_records_partitions() {
+        let values = vec![5, 3, 8, 1, 9, 2, 7];
+        let r = run(&values, SortAlgorithm::Quick, PivotStrategy::Last, 0)
+            .unwrap();
+
+        // 最初の分割は全体の範囲で、ピボット7は位置4に確定する
+        assert_eq!(r.steps()[0], SortStep::Range { lo: 0, hi: 7 });
+        let first = r
+            .steps()
+            .iter()
+            .find(|s| matches!(s, SortStep::Partition { .. }))
+            .unwrap();
+        assert_eq!(
+            *first,
+            SortStep::Partition {
+                lo: 0,
+                hi: 7,
+                pivot: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_median_of_three_beats_first_pivot_on_sorted_input() {
+        let values: Vec<i32> = (0..200).collect();
+        let first =
+            run(&values, SortAlgorithm::Quick, PivotStrategy::First, 0)
+                .unwrap();
+        let median = run(
+            &values,
+            SortAlgorithm::Quick,
+            PivotStrategy::MedianOfThree,
+            0,
+        )
+        .unwrap();
+        assert!(median.comparisons * 4 < first.comparisons);
+    }
+
+    #[test]
+    fn test_merge_sort_records_ranges() {
+        let values = vec![4, 3, 2, 1];
+        let r = run(
+            &values,
+            SortAlgorithm::Merge,
+            PivotStrategy::default(),
+            0,
+        )
+        .unwrap();
+        let ranges: Vec<SortStep> = r
+            .steps()
+            .iter()
+            .filter(|s| matches!(s, SortStep::Range { .. }))
+            .copied()
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                SortStep::Range { lo: 0, hi: 4 },
+                SortStep::Range { lo: 0, hi: 2 },
+                SortStep::Range { lo: 2, hi: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_parses_names() {
+        assert!(sort(&[2, 1], "quick", "median3", 0).is_ok());
+        assert!(sort(&[2, 1], "quick", "", 0).is_ok());
+        assert!(sort(&[2, 1], "bogo", "", 0).is_err());
+        assert!(sort(&[2, 1], "quick", "best", 0).is_err());
+    }
+}
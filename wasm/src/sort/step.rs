@@ -0,0 +1,133 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+/// ソート中の1手順
+///
+/// 位置はすべて配列の添字で、範囲は lo 以上 hi 未満
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortStep {
+    /// 位置iとjの値を比較
+    Compare { i: usize, j: usize },
+
+    /// 位置iとjの値を交換
+    Swap { i: usize, j: usize },
+
+    /// 位置indexにvalueを書き込む
+    Write { index: usize, value: i32 },
+
+    /// 範囲 [lo, hi) の分割が終わり、ピボットが位置pivotに確定
+    Partition { lo: usize, hi: usize, pivot: usize },
+
+    /// 範囲 [lo, hi) の再帰呼び出しに入る
+    Range { lo: usize, hi: usize },
+}
+
+impl SortStep {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+        };
+
+        match *self {
+            SortStep::Compare { i, j } => {
+                set("type", "compare".into());
+                set("i", i.into());
+                set("j", j.into());
+            }
+            SortStep::Swap { i, j } => {
+                set("type", "swap".into());
+                set("i", i.into());
+                set("j", j.into());
+            }
+            SortStep::Write { index, value } => {
+                set("type", "write".into());
+                set("index", index.into());
+                set("value", value.into());
+            }
+            SortStep::Partition { lo, hi, pivot } => {
+                set("type", "partition".into());
+                set("lo", lo.into());
+                set("hi", hi.into());
+                set("pivot", pivot.into());
+            }
+            SortStep::Range { lo, hi } => {
+                set("type", "range".into());
+                set("lo", lo.into());
+                set("hi", hi.into());
+            }
+        }
+
+        obj.into()
+    }
+}
+
+/// 配列への操作を手順として記録しながら実行する
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    /// ソート中の配列
+    pub data: Vec<i32>,
+
+    /// 記録した手順
+    pub steps: Vec<SortStep>,
+
+    /// 比較の回数
+    pub comparisons: usize,
+
+    /// 交換の回数
+    pub swaps: usize,
+
+    /// 配列への書き込みの回数 (交換は2回と数える)
+    pub writes: usize,
+}
+
+impl Recorder {
+    pub fn new(data: Vec<i32>) -> Self {
+        Recorder {
+            data,
+            ..Default::default()
+        }
+    }
+
+    /// data[i] < data[j] か
+    pub fn less(&mut self, i: usize, j: usize) -> bool {
+        let (a, b) = (self.data[i], self.data[j]);
+        self.less_values(i, j, a, b)
+    }
+
+    /// 位置iとjにあった値aとbを比較 (a < b か)
+    ///
+    /// マージソートのように、作業領域に退避した値を比べるときに使う
+    pub fn less_values(
+        &mut self,
+        i: usize,
+        j: usize,
+        a: i32,
+        b: i32,
+    ) -> bool {
+        self.comparisons += 1;
+        self.steps.push(SortStep::Compare { i, j });
+        a < b
+    }
+
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.swaps += 1;
+        self.writes += 2;
+        self.steps.push(SortStep::Swap { i, j });
+        self.data.swap(i, j);
+    }
+
+    pub fn write(&mut self, index: usize, value: i32) {
+        self.writes += 1;
+        self.steps.push(SortStep::Write { index, value });
+        self.data[index] = value;
+    }
+
+    pub fn range(&mut self, lo: usize, hi: usize) {
+        self.steps.push(SortStep::Range { lo, hi });
+    }
+
+    pub fn partition(&mut self, lo: usize, hi: usize, pivot: usize) {
+        self.steps.push(SortStep::Partition { lo, hi, pivot });
+    }
+}
@@ -0,0 +1,458 @@
+//! 全てのデータ構造で共通の操作トレース
+//!
+//! 各データ構造は操作中の出来事を`Tracer`に報告する。
+//! 書き出した形式 (`{version, steps}`) はTS側の`Trace`型に対応する。
+//! 各ステップには、指定した言語の説明文 (`description`) が付く
+
+use crate::json::JsonValue;
+use crate::locale::{Locale, Message, format_keys};
+use wasm_bindgen::prelude::*;
+
+/// トレースの形式のバージョン
+///
+/// イベントの種類やフィールドを変えたら上げる
+pub const TRACE_VERSION: u32 = 2;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TRACE_TS: &str = r#"
+export type TraceStep = { description: string } & (
+  | { type: "visit"; node: number }
+  | { type: "compare"; node: number; key: number; other: number }
+  | { type: "highlight"; node: number; key: number | null }
+  | { type: "createNode"; node: number; keys: number[] }
+  | { type: "deleteNode"; node: number }
+  | { type: "moveKey"; key: number; from: number | null; to: number | null }
+  | { type: "link"; parent: number; child: number; index: number }
+  | { type: "unlink"; parent: number; child: number }
+  | { type: "annotate"; message: string }
+);
+
+export interface Trace {
+  version: 2;
+  steps: TraceStep[];
+}
+"#;
+
+/// トレースの形式のバージョン
+#[wasm_bindgen]
+pub fn trace_version() -> u32 {
+    TRACE_VERSION
+}
+
+/// 節点の識別子
+///
+/// 節点を作るときに割り当て、節点が消えるまで変わらない
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
+/// 木ごとの識別子の割り当て
+///
+/// 木が自分で持つので、同じ操作列からは常に同じ識別子が得られる
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeIds {
+    next: u32,
+}
+
+impl NodeIds {
+    /// 1から割り当てる
+    pub fn new() -> Self {
+        NodeIds { next: 1 }
+    }
+
+    /// 既にある識別子より後から割り当てる
+    pub fn after(ids: impl IntoIterator<Item = NodeId>) -> Self {
+        let max = ids.into_iter().map(|id| id.0).max().unwrap_or(0);
+        NodeIds { next: max + 1 }
+    }
+
+    /// 次のfreshが返す識別子 (これ以降は全て未使用)
+    pub fn peek(&self) -> NodeId {
+        NodeId(self.next)
+    }
+
+    /// まだ使われていない識別子
+    pub fn fresh(&mut self) -> NodeId {
+        let id = NodeId(self.next);
+        self.next += 1;
+        id
+    }
+}
+
+impl Default for NodeIds {
+    fn default() -> Self {
+        NodeIds::new()
+    }
+}
+
+impl From<NodeId> for JsonValue {
+    fn from(id: NodeId) -> Self {
+        id.0.into()
+    }
+}
+
+/// 操作中の1ステップ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// 節点を訪問した
+    Visit { node: NodeId },
+
+    /// 探しているkeyと節点のキーotherを比較した
+    Compare { node: NodeId, key: i32, other: i32 },
+
+    /// 節点 (keyがあればそのキー) を強調する
+    Highlight { node: NodeId, key: Option<i32> },
+
+    /// キーkeysを持つ節点を作った
+    CreateNode { node: NodeId, keys: Vec<i32> },
+
+    /// 節点を消した
+    DeleteNode { node: NodeId },
+
+    /// キーをfromからtoへ移した
+    ///
+    /// fromがNoneなら新しく入ったキー、toがNoneなら取り除かれたキー
+    MoveKey {
+        key: i32,
+        from: Option<NodeId>,
+        to: Option<NodeId>,
+    },
+
+    /// childをparentのindex番目の子にした
+    Link {
+        parent: NodeId,
+        child: NodeId,
+        index: usize,
+    },
+
+    /// childをparentの子から外した
+    Unlink { parent: NodeId, child: NodeId },
+
+    /// アルゴリズムが何をしたかの説明
+    Annotate { message: Message },
+}
+
+impl TraceEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TraceEvent::Visit { .. } => "visit",
+            TraceEvent::Compare { .. } => "compare",
+            TraceEvent::Highlight { .. } => "highlight",
+            TraceEvent::CreateNode { .. } => "createNode",
+            TraceEvent::DeleteNode { .. } => "deleteNode",
+            TraceEvent::MoveKey { .. } => "moveKey",
+            TraceEvent::Link { .. } => "link",
+            TraceEvent::Unlink { .. } => "unlink",
+            TraceEvent::Annotate { .. } => "annotate",
+        }
+    }
+
+    /// このステップの説明文
+    pub fn describe(&self, locale: Locale) -> String {
+        let ja = locale == Locale::Ja;
+        match self {
+            TraceEvent::Visit { node } => {
+                if ja {
+                    format!("ノード #{} を訪問します", node.0)
+                } else {
+                    format!("Visiting node #{}", node.0)
+                }
+            }
+            TraceEvent::Compare { key, other, .. } => {
+                let relation = match key.cmp(other) {
+                    std::cmp::Ordering::Less => "<",
+                    std::cmp::Ordering::Equal => "=",
+                    std::cmp::Ordering::Greater => ">",
+                };
+                if ja {
+                    format!(
+                        "{key} と {other} を比較します ({key} {relation} {other})"
+                    )
+                } else {
+                    format!(
+                        "Comparing {key} with {other} ({key} {relation} {other})"
+                    )
+                }
+            }
+            TraceEvent::Highlight {
+                node,
+                key: Some(key),
+            } => {
+                if ja {
+                    format!("ノード #{} のキー {key}", node.0)
+                } else {
+                    format!("Key {key} in node #{}", node.0)
+                }
+            }
+            TraceEvent::Highlight { node, key: None } => {
+                if ja {
+                    format!("ノード #{}", node.0)
+                } else {
+                    format!("Node #{}", node.0)
+                }
+            }
+            TraceEvent::CreateNode { node, keys } => {
+                let keys = if keys.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", format_keys(keys))
+                };
+                if ja {
+                    format!("ノード #{}{keys} を作ります", node.0)
+                } else {
+                    format!("Creating node #{}{keys}", node.0)
+                }
+            }
+            TraceEvent::DeleteNode { node } => {
+                if ja {
+                    format!("ノード #{} を削除します", node.0)
+                } else {
+                    format!("Deleting node #{}", node.0)
+                }
+            }
+            TraceEvent::MoveKey { key, from, to } => {
+                match (from, to, ja) {
+                    (Some(from), Some(to), true) => format!(
+                        "キー {key} をノード #{} からノード #{} へ移します",
+                        from.0, to.0
+                    ),
+                    (Some(from), Some(to), false) => format!(
+                        "Moving key {key} from node #{} to node #{}",
+                        from.0, to.0
+                    ),
+                    (None, Some(to), true) => {
+                        format!("キー {key} をノード #{} に入れます", to.0)
+                    }
+                    (None, Some(to), false) => {
+                        format!("Putting key {key} into node #{}", to.0)
+                    }
+                    (Some(from), None, true) => {
+                        format!(
+                            "キー {key} をノード #{} から取り除きます",
+                            from.0
+                        )
+                    }
+                    (Some(from), None, false) => {
+                        format!("Removing key {key} from node #{}", from.0)
+                    }
+                    (None, None, true) => format!("キー {key}"),
+                    (None, None, false) => format!("Key {key}"),
+                }
+            }
+            TraceEvent::Link {
+                parent,
+                child,
+                index,
+            } => {
+                if ja {
+                    format!(
+                        "ノード #{} をノード #{} の {index} 番目の子にします",
+                        child.0, parent.0
+                    )
+                } else {
+                    format!(
+                        "Attaching node #{} as child {index} of node #{}",
+                        child.0, parent.0
+                    )
+                }
+            }
+            TraceEvent::Unlink { parent, child } => {
+                if ja {
+                    format!(
+                        "ノード #{} をノード #{} の子から外します",
+                        child.0, parent.0
+                    )
+                } else {
+                    format!(
+                        "Detaching node #{} from node #{}",
+                        child.0, parent.0
+                    )
+                }
+            }
+            TraceEvent::Annotate { message } => message.text(locale),
+        }
+    }
+
+    /// 説明文をlocaleの言語で付けて書き出す
+    pub fn to_json(&self, locale: Locale) -> JsonValue {
+        let JsonValue::Object(mut obj) = self.fields(locale) else {
+            unreachable!("trace steps are objects");
+        };
+        obj.insert(
+            "description".into(),
+            self.describe(locale).as_str().into(),
+        );
+        JsonValue::Object(obj)
+    }
+
+    fn fields(&self, locale: Locale) -> JsonValue {
+        let kind = ("type", self.kind().into());
+        match self {
+            TraceEvent::Visit { node }
+            | TraceEvent::DeleteNode { node } => {
+                JsonValue::object([kind, ("node", (*node).into())])
+            }
+            TraceEvent::Compare { node, key, other } => {
+                JsonValue::object([
+                    kind,
+                    ("node", (*node).into()),
+                    ("key", (*key).into()),
+                    ("other", (*other).into()),
+                ])
+            }
+            TraceEvent::Highlight { node, key } => JsonValue::object([
+                kind,
+                ("node", (*node).into()),
+                ("key", (*key).into()),
+            ]),
+            TraceEvent::CreateNode { node, keys } => JsonValue::object([
+                kind,
+                ("node", (*node).into()),
+                ("keys", keys.clone().into()),
+            ]),
+            TraceEvent::MoveKey { key, from, to } => JsonValue::object([
+                kind,
+                ("key", (*key).into()),
+                ("from", (*from).into()),
+                ("to", (*to).into()),
+            ]),
+            TraceEvent::Link {
+                parent,
+                child,
+                index,
+            } => JsonValue::object([
+                kind,
+                ("parent", (*parent).into()),
+                ("child", (*child).into()),
+                ("index", (*index).into()),
+            ]),
+            TraceEvent::Unlink { parent, child } => JsonValue::object([
+                kind,
+                ("parent", (*parent).into()),
+                ("child", (*child).into()),
+            ]),
+            TraceEvent::Annotate { message } => JsonValue::object([
+                kind,
+                ("message", message.text(locale).as_str().into()),
+            ]),
+        }
+    }
+
+    pub fn to_js_value(&self, locale: Locale) -> JsValue {
+        self.to_json(locale).to_js_value()
+    }
+}
+
+/// トレース全体 (`{version, steps}`)
+pub fn trace_to_json(events: &[TraceEvent], locale: Locale) -> JsonValue {
+    JsonValue::object([
+        ("version", TRACE_VERSION.into()),
+        (
+            "steps",
+            JsonValue::Array(
+                events.iter().map(|e| e.to_json(locale)).collect(),
+            ),
+        ),
+    ])
+}
+
+/// トレースの報告先
+pub trait Tracer {
+    fn record(&mut self, event: TraceEvent);
+
+    /// falseなら報告しても捨てられる
+    ///
+    /// キーの配列の複製など、イベントを作る手間を省くのに使う
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+/// イベントを順に溜める
+impl Tracer for Vec<TraceEvent> {
+    fn record(&mut self, event: TraceEvent) {
+        self.push(event);
+    }
+}
+
+/// 何も記録しない
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopTracer;
+
+impl Tracer for NoopTracer {
+    fn record(&mut self, _: TraceEvent) {}
+
+    fn enabled(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape() {
+        let events = vec![
+            TraceEvent::Visit { node: NodeId(3) },
+            TraceEvent::MoveKey {
+                key: 7,
+                from: None,
+                to: Some(NodeId(3)),
+            },
+            TraceEvent::Annotate {
+                message: Message::Text("done".into()),
+            },
+        ];
+        assert_eq!(
+            trace_to_json(&events, Locale::En).to_string(),
+            concat!(
+                r#"{"steps":[{"description":"Visiting node #3","#,
+                r#""node":3,"type":"visit"},"#,
+                r#"{"description":"Putting key 7 into node #3","#,
+                r#""from":null,"key":7,"to":3,"type":"moveKey"},"#,
+                r#"{"description":"done","#,
+                r#""message":"done","type":"annotate"}],"version":2}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_descriptions_follow_locale() {
+        let event = TraceEvent::Compare {
+            node: NodeId(1),
+            key: 4,
+            other: 9,
+        };
+        assert_eq!(
+            event.describe(Locale::En),
+            "Comparing 4 with 9 (4 < 9)"
+        );
+        assert_eq!(
+            event.describe(Locale::Ja),
+            "4 と 9 を比較します (4 < 9)"
+        );
+
+        let event = TraceEvent::Annotate {
+            message: Message::NotFound { key: 4 },
+        };
+        assert_eq!(
+            event.to_json(Locale::Ja).to_string(),
+            concat!(
+                r#"{"description":"キー 4 は見つかりません","#,
+                r#""message":"キー 4 は見つかりません","type":"annotate"}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_node_ids_are_unique() {
+        let mut ids = NodeIds::new();
+        let a = ids.fresh();
+        let b = ids.fresh();
+        assert_ne!(a, b);
+
+        // 割り当ては持ち主ごとに独立している
+        assert_eq!(NodeIds::new().fresh(), a);
+        assert_eq!(NodeIds::after([a, NodeId(7)]).fresh(), NodeId(8));
+    }
+}
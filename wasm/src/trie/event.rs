@@ -0,0 +1,100 @@
+use js_sys::Object;
+use wasm_bindgen::prelude::*;
+
+/// トライ・基数木の操作中に発生したイベント
+///
+/// prefixはその節点に至るまでの文字列
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrieEvent {
+    /// 節点を訪問した
+    Visit { prefix: String },
+
+    /// 節点を作った
+    Create { prefix: String },
+
+    /// 基数木で辺のラベルをheadとtailに分け、prefixに中間の節点を作った
+    Split {
+        prefix: String,
+        head: String,
+        tail: String,
+    },
+
+    /// 基数木で節点とただ1つの子を併合し、ラベルをlabelにした
+    Merge { prefix: String, label: String },
+
+    /// wordの終端の印を付けた
+    Mark { word: String },
+
+    /// wordの終端の印を外した
+    Unmark { word: String },
+
+    /// 不要になった節点を取り除いた
+    Prune { prefix: String },
+
+    /// 単語 (前方一致検索では接頭辞) が見つかった
+    Found { word: String },
+
+    /// 単語 (前方一致検索では接頭辞) が見つからなかった
+    NotFound { word: String },
+
+    /// 補完の候補としてwordを集めた
+    Collect { word: String },
+}
+
+impl TrieEvent {
+    pub fn to_js_value(&self) -> JsValue {
+        let obj = Object::new();
+        let set = |name: &str, value: &str| {
+            let _ =
+                js_sys::Reflect::set(&obj, &name.into(), &value.into());
+        };
+
+        match self {
+            TrieEvent::Visit { prefix } => {
+                set("type", "visit");
+                set("prefix", prefix);
+            }
+            TrieEvent::Create { prefix } => {
+                set("type", "create");
+                set("prefix", prefix);
+            }
+            TrieEvent::Split { prefix, head, tail } => {
+                set("type", "split");
+                set("prefix", prefix);
+                set("head", head);
+                set("tail", tail);
+            }
+            TrieEvent::Merge { prefix, label } => {
+                set("type", "merge");
+                set("prefix", prefix);
+                set("label", label);
+            }
+            TrieEvent::Mark { word } => {
+                set("type", "mark");
+                set("word", word);
+            }
+            TrieEvent::Unmark { word } => {
+                set("type", "unmark");
+                set("word", word);
+            }
+            TrieEvent::Prune { prefix } => {
+                set("type", "prune");
+                set("prefix", prefix);
+            }
+            TrieEvent::Found { word } => {
+                set("type", "found");
+                set("word", word);
+            }
+            TrieEvent::NotFound { word } => {
+                set("type", "notFound");
+                set("word", word);
+            }
+            TrieEvent::Collect { word } => {
+                set("type", "collect");
+                set("word", word);
+            }
+        }
+
+        obj.into()
+    }
+}
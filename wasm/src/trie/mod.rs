@@ -0,0 +1,9 @@
+mod event;
+mod node;
+mod operation;
+mod radix;
+
+pub use event::TrieEvent;
+pub use node::TrieNode;
+pub use operation::{RadixTree, Trie};
+pub use radix::RadixNode;
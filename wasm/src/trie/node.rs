@@ -0,0 +1,149 @@
+use crate::trie::event::TrieEvent;
+use std::collections::BTreeMap;
+
+/// トライの節点 (辺は1文字)
+#[derive(Clone, Debug, Default)]
+pub struct TrieNode {
+    // 子 (文字の順)
+    children: BTreeMap<char, TrieNode>,
+
+    // ここで終わる単語があるかどうか
+    terminal: bool,
+}
+
+impl TrieNode {
+    pub fn children(&self) -> &BTreeMap<char, TrieNode> {
+        &self.children
+    }
+
+    pub fn terminal(&self) -> bool {
+        self.terminal
+    }
+
+    pub fn count_nodes(&self) -> usize {
+        1 + self.children.values().map(Self::count_nodes).sum::<usize>()
+    }
+
+    /// 単語を挿入する。既にあればfalse
+    pub fn insert(
+        &mut self,
+        word: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> bool {
+        let mut node = self;
+        let mut prefix = String::new();
+        events.push(TrieEvent::Visit {
+            prefix: prefix.clone(),
+        });
+
+        for c in word.chars() {
+            prefix.push(c);
+            if !node.children.contains_key(&c) {
+                events.push(TrieEvent::Create {
+                    prefix: prefix.clone(),
+                });
+            } else {
+                events.push(TrieEvent::Visit {
+                    prefix: prefix.clone(),
+                });
+            }
+            node = node.children.entry(c).or_default();
+        }
+
+        if node.terminal {
+            events.push(TrieEvent::Found { word: word.into() });
+            return false;
+        }
+        node.terminal = true;
+        events.push(TrieEvent::Mark { word: word.into() });
+        true
+    }
+
+    /// 単語を削除し、単語の終端でも分岐でもなくなった節点を取り除く
+    pub fn delete(
+        &mut self,
+        prefix: &mut String,
+        rest: &str,
+        word: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> bool {
+        events.push(TrieEvent::Visit {
+            prefix: prefix.clone(),
+        });
+
+        let mut chars = rest.chars();
+        let Some(c) = chars.next() else {
+            if !self.terminal {
+                return false;
+            }
+            self.terminal = false;
+            events.push(TrieEvent::Unmark { word: word.into() });
+            return true;
+        };
+        let Some(child) = self.children.get_mut(&c) else {
+            return false;
+        };
+
+        prefix.push(c);
+        let deleted = child.delete(prefix, chars.as_str(), word, events);
+        if deleted && !child.terminal && child.children.is_empty() {
+            self.children.remove(&c);
+            events.push(TrieEvent::Prune {
+                prefix: prefix.clone(),
+            });
+        }
+        prefix.pop();
+        deleted
+    }
+
+    /// prefixに対応する節点までたどる
+    pub fn find(
+        &self,
+        prefix: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> Option<&TrieNode> {
+        let mut node = self;
+        let mut path = String::new();
+        events.push(TrieEvent::Visit {
+            prefix: path.clone(),
+        });
+
+        for c in prefix.chars() {
+            node = node.children.get(&c)?;
+            path.push(c);
+            events.push(TrieEvent::Visit {
+                prefix: path.clone(),
+            });
+        }
+        Some(node)
+    }
+
+    /// この節点以下の単語を辞書順に最大limit個集める (wordはこの節点までの文字列)
+    pub fn collect(
+        &self,
+        word: &mut String,
+        limit: usize,
+        out: &mut Vec<String>,
+        events: &mut Vec<TrieEvent>,
+    ) {
+        if out.len() >= limit {
+            return;
+        }
+        if self.terminal {
+            out.push(word.clone());
+            events.push(TrieEvent::Collect { word: word.clone() });
+        }
+
+        for (&c, child) in &self.children {
+            if out.len() >= limit {
+                break;
+            }
+            word.push(c);
+            events.push(TrieEvent::Visit {
+                prefix: word.clone(),
+            });
+            child.collect(word, limit, out, events);
+            word.pop();
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use crate::trie::event::TrieEvent;
+use crate::trie::node::TrieNode;
+use crate::trie::radix::RadixNode;
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+/// 節点をBTreeと同じ形 ({keys, children, isLeaf}) に変換する
+///
+/// keysには子への辺のラベルを子と同じ順に並べる。
+/// terminalとprefixは追加の情報
+fn node_to_js_value(
+    prefix: &str,
+    terminal: bool,
+    edges: Vec<(String, JsValue)>,
+) -> JsValue {
+    let obj = js_sys::Object::new();
+    let set = |name: &str, value: JsValue| {
+        let _ = js_sys::Reflect::set(&obj, &name.into(), &value);
+    };
+
+    let is_leaf = edges.is_empty();
+    let (keys, children): (Array, Array) = edges
+        .into_iter()
+        .map(|(label, child)| (JsValue::from(label), child))
+        .unzip();
+    set("keys", keys.into());
+    set("children", children.into());
+    set("isLeaf", is_leaf.into());
+    set("terminal", terminal.into());
+    set("prefix", prefix.into());
+
+    obj.into()
+}
+
+// Trie (1文字ずつ辺をたどる標準的なトライ)
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Trie {
+    root: TrieNode,
+
+    // 単語の数
+    len: usize,
+
+    // 直前の操作で発生したイベント
+    events: Vec<TrieEvent>,
+}
+
+#[wasm_bindgen]
+impl Trie {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 単語を挿入する。既にあればfalse
+    pub fn insert(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let inserted = self.root.insert(word, &mut self.events);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// 単語を削除する。なければfalse
+    pub fn delete(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let deleted = self.root.delete(
+            &mut String::new(),
+            word,
+            word,
+            &mut self.events,
+        );
+        if deleted {
+            self.len -= 1;
+        } else {
+            self.events.push(TrieEvent::NotFound { word: word.into() });
+        }
+        deleted
+    }
+
+    /// 単語があるかどうか
+    pub fn search(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let found = self
+            .root
+            .find(word, &mut self.events)
+            .is_some_and(TrieNode::terminal);
+        self.push_result(word, found);
+        found
+    }
+
+    /// prefixで始まる単語があるかどうか
+    pub fn starts_with(&mut self, prefix: &str) -> bool {
+        self.events.clear();
+        let found = self.root.find(prefix, &mut self.events).is_some();
+        self.push_result(prefix, found);
+        found
+    }
+
+    /// prefixで始まる単語を辞書順に最大limit個返す
+    pub fn autocomplete(
+        &mut self,
+        prefix: &str,
+        limit: usize,
+    ) -> Vec<String> {
+        self.events.clear();
+        let mut words = Vec::new();
+        match self.root.find(prefix, &mut self.events) {
+            Some(node) => node.collect(
+                &mut prefix.to_string(),
+                limit,
+                &mut words,
+                &mut self.events,
+            ),
+            None => {
+                self.events.push(TrieEvent::NotFound {
+                    word: prefix.into(),
+                });
+            }
+        }
+        words
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        Self::node_to_js_value(&self.root, &mut String::new())
+    }
+
+    fn node_to_js_value(node: &TrieNode, prefix: &mut String) -> JsValue {
+        let edges = node
+            .children()
+            .iter()
+            .map(|(&c, child)| {
+                prefix.push(c);
+                let value = Self::node_to_js_value(child, prefix);
+                prefix.pop();
+                (c.to_string(), value)
+            })
+            .collect();
+        node_to_js_value(prefix, node.terminal(), edges)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(TrieEvent::to_js_value))
+    }
+
+    /// 単語の総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    /// 節点の総数 (根を含む)
+    #[wasm_bindgen]
+    pub fn get_node_count(&self) -> usize {
+        self.root.count_nodes()
+    }
+}
+
+impl Trie {
+    fn push_result(&mut self, word: &str, found: bool) {
+        let word = word.into();
+        self.events.push(if found {
+            TrieEvent::Found { word }
+        } else {
+            TrieEvent::NotFound { word }
+        });
+    }
+
+    pub fn root(&self) -> &TrieNode {
+        &self.root
+    }
+
+    pub fn events(&self) -> &[TrieEvent] {
+        &self.events
+    }
+
+    /// 全ての単語 (辞書順)
+    pub fn words(&self) -> Vec<String> {
+        let mut words = Vec::new();
+        self.root.collect(
+            &mut String::new(),
+            usize::MAX,
+            &mut words,
+            &mut Vec::new(),
+        );
+        words
+    }
+}
+
+// RadixTree (分岐しない節点の並びを1本の辺にまとめた基数木)
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct RadixTree {
+    root: RadixNode,
+
+    // 単語の数
+    len: usize,
+
+    // 直前の操作で発生したイベント
+    events: Vec<TrieEvent>,
+}
+
+#[wasm_bindgen]
+impl RadixTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 単語を挿入する。既にあればfalse
+    pub fn insert(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let inserted = self.root.insert(
+            &mut String::new(),
+            word,
+            word,
+            &mut self.events,
+        );
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// 単語を削除する。なければfalse
+    pub fn delete(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let deleted = self.root.delete(
+            &mut String::new(),
+            word,
+            word,
+            &mut self.events,
+        );
+        if deleted {
+            self.len -= 1;
+        } else {
+            self.events.push(TrieEvent::NotFound { word: word.into() });
+        }
+        deleted
+    }
+
+    /// 単語があるかどうか
+    pub fn search(&mut self, word: &str) -> bool {
+        self.events.clear();
+        let found = self
+            .root
+            .find(word, &mut self.events)
+            .is_some_and(|(node, path)| path == word && node.terminal());
+        self.push_result(word, found);
+        found
+    }
+
+    /// prefixで始まる単語があるかどうか
+    pub fn starts_with(&mut self, prefix: &str) -> bool {
+        self.events.clear();
+        let found = self.root.find(prefix, &mut self.events).is_some();
+        self.push_result(prefix, found);
+        found
+    }
+
+    /// prefixで始まる単語を辞書順に最大limit個返す
+    pub fn autocomplete(
+        &mut self,
+        prefix: &str,
+        limit: usize,
+    ) -> Vec<String> {
+        self.events.clear();
+        let mut words = Vec::new();
+        match self.root.find(prefix, &mut self.events) {
+            Some((node, mut path)) => node.collect(
+                &mut path,
+                limit,
+                &mut words,
+                &mut self.events,
+            ),
+            None => {
+                self.events.push(TrieEvent::NotFound {
+                    word: prefix.into(),
+                });
+            }
+        }
+        words
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        Self::node_to_js_value(&self.root, &mut String::new())
+    }
+
+    fn node_to_js_value(node: &RadixNode, prefix: &mut String) -> JsValue {
+        let edges = node
+            .children()
+            .values()
+            .map(|child| {
+                let len = prefix.len();
+                prefix.push_str(child.label());
+                let value = Self::node_to_js_value(child, prefix);
+                prefix.truncate(len);
+                (child.label().to_string(), value)
+            })
+            .collect();
+        node_to_js_value(prefix, node.terminal(), edges)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_events(&self) -> Array {
+        Array::from_iter(self.events.iter().map(TrieEvent::to_js_value))
+    }
+
+    /// 単語の総数を取得
+    #[wasm_bindgen]
+    pub fn get_total_keys(&self) -> usize {
+        self.len
+    }
+
+    /// 節点の総数 (根を含む)
+    #[wasm_bindgen]
+    pub fn get_node_count(&self) -> usize {
+        self.root.count_nodes()
+    }
+}
+
+impl RadixTree {
+    fn push_result(&mut self, word: &str, found: bool) {
+        let word = word.into();
+        self.events.push(if found {
+            TrieEvent::Found { word }
+        } else {
+            TrieEvent::NotFound { word }
+        });
+    }
+
+    pub fn root(&self) -> &RadixNode {
+        &self.root
+    }
+
+    pub fn events(&self) -> &[TrieEvent] {
+        &self.events
+    }
+
+    /// 全ての単語 (辞書順)
+    pub fn words(&self) -> Vec<String> {
+        let mut words = Vec::new();
+        self.root.collect(
+            &mut String::new(),
+            usize::MAX,
+            &mut words,
+            &mut Vec::new(),
+        );
+        words
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use std::collections::BTreeSet;
+
+    const WORDS: [&str; 7] =
+        ["tea", "ted", "ten", "to", "inn", "in", "team"];
+
+    /// 基数木の辺のラベルを (深さ, ラベル) で前順に並べる
+    fn labels(node: &RadixNode, depth: usize, out: &mut Vec<String>) {
+        for child in node.children().values() {
+            out.push(format!("{depth}:{}", child.label()));
+            labels(child, depth + 1, out);
+        }
+    }
+
+    fn radix_labels(tree: &RadixTree) -> Vec<String> {
+        let mut out = Vec::new();
+        labels(tree.root(), 0, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_trie_insert_search_and_prefixes() {
+        let mut trie = Trie::new();
+        for word in WORDS {
+            assert!(trie.insert(word));
+        }
+        assert!(!trie.insert("ten"));
+        assert_eq!(trie.get_total_keys(), 7);
+        // 根 + t, te, tea, team, ted, ten, to + i, in, inn
+        assert_eq!(trie.get_node_count(), 11);
+
+        assert!(trie.search("in"));
+        assert!(!trie.search("te"));
+        assert!(trie.starts_with("te"));
+        assert!(!trie.starts_with("tx"));
+
+        assert!(trie.insert("tar"));
+        assert_eq!(
+            trie.events()[..3],
+            [
+                TrieEvent::Visit { prefix: "".into() },
+                TrieEvent::Visit { prefix: "t".into() },
+                TrieEvent::Create {
+                    prefix: "ta".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trie_autocomplete() {
+        let mut trie = Trie::new();
+        for word in WORDS {
+            trie.insert(word);
+        }
+
+        assert_eq!(
+            trie.autocomplete("te", 10),
+            ["tea", "team", "ted", "ten"]
+        );
+        assert_eq!(trie.autocomplete("te", 2), ["tea", "team"]);
+        assert!(trie.autocomplete("x", 10).is_empty());
+        assert_eq!(trie.autocomplete("", 10).len(), 7);
+        assert!(
+            trie.events()
+                .contains(&TrieEvent::Collect { word: "inn".into() })
+        );
+    }
+
+    #[test]
+    fn test_trie_delete_prunes_unused_nodes() {
+        let mut trie = Trie::new();
+        for word in WORDS {
+            trie.insert(word);
+        }
+
+        assert!(trie.delete("team"));
+        assert!(trie.events().contains(&TrieEvent::Prune {
+            prefix: "team".into()
+        }));
+        assert_eq!(trie.get_node_count(), 10);
+
+        // tea の節点は消えるが、te は ted, ten と共有しているので残る
+        assert!(trie.delete("tea"));
+        assert_eq!(trie.get_node_count(), 9);
+        assert!(!trie.search("tea"));
+        assert!(trie.search("ted"));
+
+        assert!(!trie.delete("te"));
+        assert!(!trie.delete("zzz"));
+        assert_eq!(trie.get_total_keys(), 5);
+    }
+
+    #[test]
+    fn test_radix_split_and_merge() {
+        let mut tree = RadixTree::new();
+        tree.insert("test");
+        tree.insert("team");
+        assert!(tree.events().contains(&TrieEvent::Split {
+            prefix: "te".into(),
+            head: "te".into(),
+            tail: "st".into(),
+        }));
+        assert_eq!(radix_labels(&tree), ["0:te", "1:am", "1:st"]);
+
+        // ラベルの途中で終わる単語は中間の節点に印を付ける
+        tree.insert("tea");
+        assert_eq!(radix_labels(&tree), ["0:te", "1:a", "2:m", "1:st"]);
+        assert!(tree.search("tea"));
+        assert!(!tree.search("te"));
+        assert!(tree.starts_with("tes"));
+
+        // team を消すと a と m は tea に戻り、さらに tea を消すと te と st が併合される
+        assert!(tree.delete("team"));
+        assert_eq!(radix_labels(&tree), ["0:te", "1:a", "1:st"]);
+        assert!(tree.delete("tea"));
+        assert!(tree.events().contains(&TrieEvent::Merge {
+            prefix: "test".into(),
+            label: "test".into(),
+        }));
+        assert_eq!(radix_labels(&tree), ["0:test"]);
+        assert_eq!(tree.get_node_count(), 2);
+    }
+
+    #[test]
+    fn test_radix_autocomplete_from_middle_of_edge() {
+        let mut tree = RadixTree::new();
+        for word in WORDS {
+            tree.insert(word);
+        }
+
+        assert_eq!(
+            tree.autocomplete("te", 10),
+            ["tea", "team", "ted", "ten"]
+        );
+        assert_eq!(tree.autocomplete("i", 10), ["in", "inn"]);
+        assert!(tree.autocomplete("tex", 10).is_empty());
+    }
+
+    #[test]
+    fn test_trie_and_radix_match_set() {
+        let mut trie = Trie::new();
+        let mut tree = RadixTree::new();
+        let mut expected = BTreeSet::new();
+        let mut rng = SplitMix64::new(11);
+
+        for _ in 0..2000 {
+            // 'a'..'c' の短い単語 (空文字列も含む)
+            let len = (rng.next_u64() % 5) as usize;
+            let word: String = (0..len)
+                .map(|_| (b'a' + (rng.next_u64() % 3) as u8) as char)
+                .collect();
+
+            if rng.chance(0.4) {
+                let want = expected.remove(&word);
+                assert_eq!(trie.delete(&word), want, "{word}");
+                assert_eq!(tree.delete(&word), want, "{word}");
+            } else {
+                let want = expected.insert(word.clone());
+                assert_eq!(trie.insert(&word), want, "{word}");
+                assert_eq!(tree.insert(&word), want, "{word}");
+            }
+        }
+
+        let words = Vec::from_iter(expected.iter().cloned());
+        assert_eq!(trie.words(), words);
+        assert_eq!(tree.words(), words);
+        assert_eq!(tree.get_total_keys(), words.len());
+        assert!(tree.get_node_count() <= trie.get_node_count());
+    }
+
+    #[test]
+    fn test_multibyte_words() {
+        let mut tree = RadixTree::new();
+        tree.insert("ねこ");
+        tree.insert("ねずみ");
+        assert_eq!(radix_labels(&tree), ["0:ね", "1:こ", "1:ずみ"]);
+        assert_eq!(tree.autocomplete("ね", 5), ["ねこ", "ねずみ"]);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::trie::event::TrieEvent;
+use std::collections::BTreeMap;
+
+/// 基数木の節点 (辺は文字列で、子はラベルの先頭の文字で引く)
+#[derive(Clone, Debug, Default)]
+pub struct RadixNode {
+    // 親からこの節点への辺のラベル (根は空)
+    label: String,
+
+    // 子 (ラベルの先頭の文字の順)
+    children: BTreeMap<char, RadixNode>,
+
+    // ここで終わる単語があるかどうか
+    terminal: bool,
+}
+
+/// aとbの共通接頭辞のバイト長
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .take_while(|((_, x), y)| x == y)
+        .last()
+        .map_or(0, |((i, x), _)| i + x.len_utf8())
+}
+
+fn first_char(s: &str) -> char {
+    s.chars().next().expect("radix labels are never empty")
+}
+
+impl RadixNode {
+    fn leaf(label: &str) -> Self {
+        RadixNode {
+            label: label.into(),
+            children: BTreeMap::new(),
+            terminal: true,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn children(&self) -> &BTreeMap<char, RadixNode> {
+        &self.children
+    }
+
+    pub fn terminal(&self) -> bool {
+        self.terminal
+    }
+
+    pub fn count_nodes(&self) -> usize {
+        1 + self.children.values().map(Self::count_nodes).sum::<usize>()
+    }
+
+    /// 単語の残りrestを挿入する。既にあればfalse
+    pub fn insert(
+        &mut self,
+        prefix: &mut String,
+        rest: &str,
+        word: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> bool {
+        events.push(TrieEvent::Visit {
+            prefix: prefix.clone(),
+        });
+
+        if rest.is_empty() {
+            if self.terminal {
+                events.push(TrieEvent::Found { word: word.into() });
+                return false;
+            }
+            self.terminal = true;
+            events.push(TrieEvent::Mark { word: word.into() });
+            return true;
+        }
+
+        let c = first_char(rest);
+        let Some(child) = self.children.get_mut(&c) else {
+            self.children.insert(c, RadixNode::leaf(rest));
+            events.push(TrieEvent::Create {
+                prefix: word.into(),
+            });
+            events.push(TrieEvent::Mark { word: word.into() });
+            return true;
+        };
+
+        let common = common_prefix_len(&child.label, rest);
+        if common == child.label.len() {
+            let len = prefix.len();
+            prefix.push_str(&child.label);
+            let inserted =
+                child.insert(prefix, &rest[common..], word, events);
+            prefix.truncate(len);
+            return inserted;
+        }
+
+        // ラベルの途中で分かれるので、共通部分を中間の節点にする
+        let mut old = self.children.remove(&c).unwrap();
+        let head = old.label[..common].to_string();
+        let tail = old.label[common..].to_string();
+        old.label = tail.clone();
+
+        let mut middle = RadixNode {
+            label: head.clone(),
+            children: BTreeMap::from([(first_char(&tail), old)]),
+            terminal: false,
+        };
+        prefix.push_str(&head);
+        events.push(TrieEvent::Split {
+            prefix: prefix.clone(),
+            head,
+            tail,
+        });
+
+        let rest = &rest[common..];
+        if rest.is_empty() {
+            middle.terminal = true;
+        } else {
+            middle
+                .children
+                .insert(first_char(rest), RadixNode::leaf(rest));
+            events.push(TrieEvent::Create {
+                prefix: word.into(),
+            });
+        }
+        events.push(TrieEvent::Mark { word: word.into() });
+        prefix.truncate(prefix.len() - common);
+        self.children.insert(c, middle);
+        true
+    }
+
+    /// 単語の残りrestを削除し、不要な節点を取り除いたり併合したりする
+    pub fn delete(
+        &mut self,
+        prefix: &mut String,
+        rest: &str,
+        word: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> bool {
+        events.push(TrieEvent::Visit {
+            prefix: prefix.clone(),
+        });
+
+        if rest.is_empty() {
+            if !self.terminal {
+                return false;
+            }
+            self.terminal = false;
+            events.push(TrieEvent::Unmark { word: word.into() });
+            return true;
+        }
+
+        let c = first_char(rest);
+        let Some(child) = self.children.get_mut(&c) else {
+            return false;
+        };
+        let Some(child_rest) = rest.strip_prefix(child.label.as_str())
+        else {
+            return false;
+        };
+
+        let len = prefix.len();
+        prefix.push_str(&child.label);
+        let deleted = child.delete(prefix, child_rest, word, events);
+        if deleted && !child.terminal {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(&c);
+                    events.push(TrieEvent::Prune {
+                        prefix: prefix.clone(),
+                    });
+                }
+                // 分岐しない中間の節点は子と1つにまとめる
+                1 => {
+                    let (_, grandchild) =
+                        child.children.pop_first().unwrap();
+                    child.label.push_str(&grandchild.label);
+                    child.children = grandchild.children;
+                    child.terminal = grandchild.terminal;
+                    prefix.push_str(&grandchild.label);
+                    events.push(TrieEvent::Merge {
+                        prefix: prefix.clone(),
+                        label: child.label.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        prefix.truncate(len);
+        deleted
+    }
+
+    /// prefixをたどり、prefixを含む最初の節点と、その節点までの文字列を返す
+    ///
+    /// prefixが辺の途中で終わる場合は、その辺の先の節点を返す
+    pub fn find(
+        &self,
+        prefix: &str,
+        events: &mut Vec<TrieEvent>,
+    ) -> Option<(&RadixNode, String)> {
+        let mut node = self;
+        let mut path = String::new();
+        events.push(TrieEvent::Visit {
+            prefix: path.clone(),
+        });
+
+        while path.len() < prefix.len() {
+            let rest = &prefix[path.len()..];
+            let child = node.children.get(&first_char(rest))?;
+            let common = common_prefix_len(&child.label, rest);
+            if common < child.label.len() && common < rest.len() {
+                return None;
+            }
+            node = child;
+            path.push_str(&child.label);
+            events.push(TrieEvent::Visit {
+                prefix: path.clone(),
+            });
+        }
+        Some((node, path))
+    }
+
+    /// この節点以下の単語を辞書順に最大limit個集める (wordはこの節点までの文字列)
+    pub fn collect(
+        &self,
+        word: &mut String,
+        limit: usize,
+        out: &mut Vec<String>,
+        events: &mut Vec<TrieEvent>,
+    ) {
+        if out.len() >= limit {
+            return;
+        }
+        if self.terminal {
+            out.push(word.clone());
+            events.push(TrieEvent::Collect { word: word.clone() });
+        }
+
+        for child in self.children.values() {
+            if out.len() >= limit {
+                break;
+            }
+            let len = word.len();
+            word.push_str(&child.label);
+            events.push(TrieEvent::Visit {
+                prefix: word.clone(),
+            });
+            child.collect(word, limit, out, events);
+            word.truncate(len);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::json::JsonValue;
+use crate::trace::NodeId;
+use crate::wal::log::LogRecord;
+use std::collections::{BTreeMap, HashSet};
+
+/// ディスク上の1ページ (1ノード)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Page {
+    /// 昇順 (重複あり)
+    pub keys: Vec<i32>,
+    pub children: Vec<NodeId>,
+}
+
+/// ディスク上の全ページ
+///
+/// 根は親を持たない唯一のページ
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageImage {
+    pages: BTreeMap<NodeId, Page>,
+}
+
+impl PageImage {
+    pub fn pages(&self) -> &BTreeMap<NodeId, Page> {
+        &self.pages
+    }
+
+    fn page_mut(&mut self, id: NodeId) -> Result<&mut Page, String> {
+        self.pages
+            .get_mut(&id)
+            .ok_or(format!("page {} does not exist", id.0))
+    }
+
+    /// 記録を1つ適用する
+    pub fn apply(&mut self, record: &LogRecord) -> Result<(), String> {
+        match *record {
+            LogRecord::CreatePage { page } => {
+                if self.pages.insert(page, Page::default()).is_some() {
+                    return Err(format!("page {} already exists", page.0));
+                }
+            }
+            LogRecord::FreePage { page } => {
+                let old = self
+                    .pages
+                    .remove(&page)
+                    .ok_or(format!("page {} does not exist", page.0))?;
+                if old != Page::default() {
+                    return Err(format!("page {} is not empty", page.0));
+                }
+            }
+            LogRecord::MoveKey { key, from, to } => {
+                if let Some(from) = from {
+                    let keys = &mut self.page_mut(from)?.keys;
+                    let pos = keys.binary_search(&key).map_err(|_| {
+                        format!("key {key} is not in page {}", from.0)
+                    })?;
+                    keys.remove(pos);
+                }
+                if let Some(to) = to {
+                    let keys = &mut self.page_mut(to)?.keys;
+                    let pos = keys.partition_point(|&k| k < key);
+                    keys.insert(pos, key);
+                }
+            }
+            LogRecord::Link {
+                parent,
+                child,
+                index,
+            } => {
+                let children = &mut self.page_mut(parent)?.children;
+                if index > children.len() {
+                    return Err(format!(
+                        "page {} has no slot {index}",
+                        parent.0
+                    ));
+                }
+                children.insert(index, child);
+            }
+            LogRecord::Unlink {
+                parent,
+                child,
+                index,
+            } => {
+                let children = &mut self.page_mut(parent)?.children;
+                if children.get(index) != Some(&child) {
+                    return Err(format!(
+                        "page {} is not child {index} of page {}",
+                        child.0, parent.0
+                    ));
+                }
+                children.remove(index);
+            }
+            LogRecord::Begin { .. }
+            | LogRecord::Commit
+            | LogRecord::Split { .. }
+            | LogRecord::Merge { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// 根のページ (ページがなければNone)
+    pub fn root(&self) -> Option<NodeId> {
+        let children: HashSet<NodeId> = self
+            .pages
+            .values()
+            .flat_map(|p| p.children.iter().copied())
+            .collect();
+        self.pages.keys().copied().find(|id| !children.contains(id))
+    }
+
+    /// 最小次数tのB-Treeとして正しいか
+    pub fn validate(&self, t: usize) -> Result<(), String> {
+        let Some(root) = self.root() else {
+            return Ok(());
+        };
+        let mut seen = HashSet::new();
+        let mut leaf_depth = None;
+        let mut stack = vec![(root, 0, None, None)];
+        while let Some((id, depth, lower, upper)) = stack.pop() {
+            if !seen.insert(id) {
+                return Err(format!("page {} is reachable twice", id.0));
+            }
+            let page = self
+                .pages
+                .get(&id)
+                .ok_or(format!("page {} does not exist", id.0))?;
+            let keys = &page.keys;
+            let min = if id == root { 0 } else { t - 1 };
+            if keys.len() < min || keys.len() > 2 * t - 1 {
+                return Err(format!(
+                    "page {} has {} keys",
+                    id.0,
+                    keys.len()
+                ));
+            }
+            let in_range = |&k: &i32| {
+                lower.is_none_or(|l| k >= l)
+                    && upper.is_none_or(|u| k <= u)
+            };
+            if !keys.windows(2).all(|w| w[0] <= w[1])
+                || !keys.iter().all(in_range)
+            {
+                return Err(format!("page {} is out of order", id.0));
+            }
+
+            if page.children.is_empty() {
+                if *leaf_depth.get_or_insert(depth) != depth {
+                    return Err("leaves are at different depths".into());
+                }
+                continue;
+            }
+            if page.children.len() != keys.len() + 1 {
+                return Err(format!(
+                    "page {} has {} keys and {} children",
+                    id.0,
+                    keys.len(),
+                    page.children.len()
+                ));
+            }
+            for (i, &child) in page.children.iter().enumerate() {
+                let lo = if i == 0 { lower } else { Some(keys[i - 1]) };
+                let hi = keys.get(i).copied().or(upper);
+                stack.push((child, depth + 1, lo, hi));
+            }
+        }
+        if seen.len() != self.pages.len() {
+            return Err(
+                "some pages are not reachable from the root".into()
+            );
+        }
+        Ok(())
+    }
+
+    /// 深さごとのページのキー (根から順に、各段は左から)
+    pub fn levels(&self) -> Vec<Vec<Vec<i32>>> {
+        let mut levels = Vec::new();
+        let mut ids: Vec<NodeId> = self.root().into_iter().collect();
+        while !ids.is_empty() {
+            let pages: Vec<&Page> =
+                ids.iter().map(|id| &self.pages[id]).collect();
+            levels.push(pages.iter().map(|p| p.keys.clone()).collect());
+            ids = pages.iter().flat_map(|p| p.children.clone()).collect();
+        }
+        levels
+    }
+
+    /// 木の構造 ({id, keys, children, isLeaf})
+    pub fn to_json(&self) -> JsonValue {
+        match self.root() {
+            Some(root) => self.page_to_json(root),
+            None => JsonValue::Null,
+        }
+    }
+
+    fn page_to_json(&self, id: NodeId) -> JsonValue {
+        let page = &self.pages[&id];
+        JsonValue::object([
+            ("id", id.into()),
+            ("keys", page.keys.clone().into()),
+            (
+                "children",
+                JsonValue::Array(
+                    page.children
+                        .iter()
+                        .map(|&c| self.page_to_json(c))
+                        .collect(),
+                ),
+            ),
+            ("isLeaf", page.children.is_empty().into()),
+        ])
+    }
+}
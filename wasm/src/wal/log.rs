@@ -0,0 +1,400 @@
+use crate::correspondence::Operation;
+use crate::json::JsonValue;
+use crate::locale::Message;
+use crate::trace::{NodeId, TraceEvent, Tracer};
+use crate::wal::image::PageImage;
+
+/// ログの記録
+///
+/// ページを変える記録は、適用する前にログへ書く (write-ahead)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogRecord {
+    /// 操作の始まり
+    Begin { op: Operation },
+
+    /// 操作の完了。ここまで書かれた操作は復旧後も残る
+    Commit,
+
+    /// ページの分割 (説明のための記録で、ページは変えない)
+    Split { keys: Vec<i32>, median: i32 },
+
+    /// ページのマージ (説明のための記録で、ページは変えない)
+    Merge {
+        left: Vec<i32>,
+        separator: i32,
+        right: Vec<i32>,
+    },
+
+    /// 空のページを作った
+    CreatePage { page: NodeId },
+
+    /// 空になったページを解放した
+    FreePage { page: NodeId },
+
+    /// キーをfromからtoへ移した (Noneはページの外)
+    MoveKey {
+        key: i32,
+        from: Option<NodeId>,
+        to: Option<NodeId>,
+    },
+
+    /// childをparentのindex番目の子にした
+    Link {
+        parent: NodeId,
+        child: NodeId,
+        index: usize,
+    },
+
+    /// parentのindex番目の子childを外した
+    Unlink {
+        parent: NodeId,
+        child: NodeId,
+        index: usize,
+    },
+}
+
+impl LogRecord {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LogRecord::Begin { .. } => "begin",
+            LogRecord::Commit => "commit",
+            LogRecord::Split { .. } => "split",
+            LogRecord::Merge { .. } => "merge",
+            LogRecord::CreatePage { .. } => "createPage",
+            LogRecord::FreePage { .. } => "freePage",
+            LogRecord::MoveKey { .. } => "moveKey",
+            LogRecord::Link { .. } => "link",
+            LogRecord::Unlink { .. } => "unlink",
+        }
+    }
+
+    /// この記録の効果を打ち消す記録 (ページを変えない記録はNone)
+    pub fn inverse(&self) -> Option<LogRecord> {
+        Some(match *self {
+            LogRecord::CreatePage { page } => LogRecord::FreePage { page },
+            LogRecord::FreePage { page } => LogRecord::CreatePage { page },
+            LogRecord::MoveKey { key, from, to } => LogRecord::MoveKey {
+                key,
+                from: to,
+                to: from,
+            },
+            LogRecord::Link {
+                parent,
+                child,
+                index,
+            } => LogRecord::Unlink {
+                parent,
+                child,
+                index,
+            },
+            LogRecord::Unlink {
+                parent,
+                child,
+                index,
+            } => LogRecord::Link {
+                parent,
+                child,
+                index,
+            },
+            LogRecord::Begin { .. }
+            | LogRecord::Commit
+            | LogRecord::Split { .. }
+            | LogRecord::Merge { .. } => return None,
+        })
+    }
+
+    /// 番号lsnを付けて書き出す
+    pub fn to_json(&self, lsn: usize) -> JsonValue {
+        let head = [("lsn", lsn.into()), ("type", self.kind().into())];
+        let JsonValue::Object(mut obj) = (match self {
+            LogRecord::Begin { op } => {
+                let (name, key) = match *op {
+                    Operation::Insert(k) => ("insert", k),
+                    Operation::Delete(k) => ("delete", k),
+                };
+                JsonValue::object([
+                    ("operation", name.into()),
+                    ("key", key.into()),
+                ])
+            }
+            LogRecord::Commit => JsonValue::object([]),
+            LogRecord::Split { keys, median } => JsonValue::object([
+                ("keys", keys.clone().into()),
+                ("median", (*median).into()),
+            ]),
+            LogRecord::Merge {
+                left,
+                separator,
+                right,
+            } => JsonValue::object([
+                ("left", left.clone().into()),
+                ("separator", (*separator).into()),
+                ("right", right.clone().into()),
+            ]),
+            LogRecord::CreatePage { page }
+            | LogRecord::FreePage { page } => {
+                JsonValue::object([("page", (*page).into())])
+            }
+            LogRecord::MoveKey { key, from, to } => JsonValue::object([
+                ("key", (*key).into()),
+                ("from", (*from).into()),
+                ("to", (*to).into()),
+            ]),
+            LogRecord::Link {
+                parent,
+                child,
+                index,
+            }
+            | LogRecord::Unlink {
+                parent,
+                child,
+                index,
+            } => JsonValue::object([
+                ("parent", (*parent).into()),
+                ("child", (*child).into()),
+                ("index", (*index).into()),
+            ]),
+        }) else {
+            unreachable!("log records are objects");
+        };
+        obj.extend(head.map(|(k, v)| (k.to_string(), v)));
+        JsonValue::Object(obj)
+    }
+}
+
+/// チェックポイント: その時点の全ページの写し
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// これより前の記録は全てimageに反映済み
+    pub lsn: usize,
+    pub image: PageImage,
+
+    /// それまでに完了した操作の数
+    pub committed: usize,
+}
+
+/// 先行書き込みログ
+///
+/// 木の操作からトレースを受け取って記録に直し、
+/// 記録を書いてからページの写しに適用する
+#[derive(Clone, Debug)]
+pub struct Wal {
+    records: Vec<LogRecord>,
+    checkpoints: Vec<Checkpoint>,
+
+    // 全ての記録を適用したページ
+    image: PageImage,
+    committed: usize,
+}
+
+impl Default for Wal {
+    fn default() -> Self {
+        Wal::new()
+    }
+}
+
+impl Wal {
+    pub fn new() -> Self {
+        Wal {
+            records: Vec::new(),
+            checkpoints: vec![Checkpoint {
+                lsn: 0,
+                image: PageImage::default(),
+                committed: 0,
+            }],
+            image: PageImage::default(),
+            committed: 0,
+        }
+    }
+
+    pub fn records(&self) -> &[LogRecord] {
+        &self.records
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// 全ての記録を適用したページ
+    pub fn image(&self) -> &PageImage {
+        &self.image
+    }
+
+    /// 完了した操作の数
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    fn append(&mut self, record: LogRecord) {
+        if let Err(e) = self.image.apply(&record) {
+            panic!("log record {record:?} does not apply: {e}");
+        }
+        self.records.push(record);
+    }
+
+    pub fn begin(&mut self, op: Operation) {
+        self.append(LogRecord::Begin { op });
+    }
+
+    pub fn commit(&mut self) {
+        self.append(LogRecord::Commit);
+        self.committed += 1;
+    }
+
+    /// 今のページをチェックポイントとして残す (操作の間で呼ぶ)
+    pub fn checkpoint(&mut self) {
+        self.checkpoints.push(Checkpoint {
+            lsn: self.records.len(),
+            image: self.image.clone(),
+            committed: self.committed,
+        });
+    }
+}
+
+impl Tracer for Wal {
+    fn record(&mut self, event: TraceEvent) {
+        let record = match event {
+            TraceEvent::CreateNode { node, keys } => {
+                self.append(LogRecord::CreatePage { page: node });
+                for key in keys {
+                    self.append(LogRecord::MoveKey {
+                        key,
+                        from: None,
+                        to: Some(node),
+                    });
+                }
+                return;
+            }
+            TraceEvent::DeleteNode { node } => {
+                LogRecord::FreePage { page: node }
+            }
+            TraceEvent::MoveKey { key, from, to } => {
+                LogRecord::MoveKey { key, from, to }
+            }
+            TraceEvent::Link {
+                parent,
+                child,
+                index,
+            } => LogRecord::Link {
+                parent,
+                child,
+                index,
+            },
+            TraceEvent::Unlink { parent, child } => {
+                let index = self.image.pages()[&parent]
+                    .children
+                    .iter()
+                    .position(|&c| c == child)
+                    .expect("unlinked page is a child");
+                LogRecord::Unlink {
+                    parent,
+                    child,
+                    index,
+                }
+            }
+            TraceEvent::Annotate {
+                message: Message::Split { keys, median },
+            } => LogRecord::Split { keys, median },
+            TraceEvent::Annotate {
+                message:
+                    Message::Merge {
+                        left,
+                        separator,
+                        right,
+                    },
+            } => LogRecord::Merge {
+                left,
+                separator,
+                right,
+            },
+            TraceEvent::Visit { .. }
+            | TraceEvent::Compare { .. }
+            | TraceEvent::Highlight { .. }
+            | TraceEvent::Annotate { .. } => return,
+        };
+        self.append(record);
+    }
+}
+
+/// 復旧の結果
+#[derive(Clone, Debug)]
+pub struct Recovery {
+    pub image: PageImage,
+
+    /// 復旧後に残った操作の数 (完了していた操作の数)
+    pub committed: usize,
+
+    /// やり直した記録の数
+    pub redone: usize,
+
+    /// 取り消した記録の数
+    pub undone: usize,
+}
+
+impl Wal {
+    /// 先頭からcut個の記録だけがディスクに届いた時点でクラッシュしたとして復旧する
+    ///
+    /// 1. cut以前で最後のチェックポイントからページを作り直し、
+    ///    残っている記録を全てやり直す (完了していない操作も含む)
+    /// 2. 完了していない操作の記録を逆順に取り消す
+    pub fn recover(&self, cut: usize) -> Result<Recovery, String> {
+        if cut > self.records.len() {
+            return Err(format!(
+                "log has only {} records",
+                self.records.len()
+            ));
+        }
+        let checkpoint = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|c| c.lsn <= cut)
+            .expect("the first checkpoint is at lsn 0");
+
+        let mut image = checkpoint.image.clone();
+        let mut committed = checkpoint.committed;
+        let mut redone = 0;
+        let mut active = None;
+        for (lsn, record) in self
+            .records
+            .iter()
+            .enumerate()
+            .take(cut)
+            .skip(checkpoint.lsn)
+        {
+            image
+                .apply(record)
+                .map_err(|e| format!("redo of lsn {lsn} failed: {e}"))?;
+            match record {
+                LogRecord::Begin { .. } => active = Some(lsn),
+                LogRecord::Commit => {
+                    active = None;
+                    committed += 1;
+                }
+                _ if record.inverse().is_some() => redone += 1,
+                _ => {}
+            }
+        }
+
+        let mut undone = 0;
+        if let Some(begin) = active {
+            for (lsn, record) in
+                self.records[begin..cut].iter().enumerate().rev()
+            {
+                if let Some(inverse) = record.inverse() {
+                    image.apply(&inverse).map_err(|e| {
+                        format!("undo of lsn {} failed: {e}", begin + lsn)
+                    })?;
+                    undone += 1;
+                }
+            }
+        }
+
+        Ok(Recovery {
+            image,
+            committed,
+            redone,
+            undone,
+        })
+    }
+}
@@ -0,0 +1,7 @@
+mod image;
+mod log;
+mod operation;
+
+pub use image::{Page, PageImage};
+pub use log::{Checkpoint, LogRecord, Recovery, Wal};
+pub use operation::{WalBTree, crash_after_every_record};
@@ -0,0 +1,222 @@
+use crate::btree::BTree;
+use crate::correspondence::Operation;
+use crate::json::JsonValue;
+use crate::wal::image::PageImage;
+use crate::wal::log::Wal;
+use wasm_bindgen::prelude::*;
+
+/// 先行書き込みログを付けたB-Tree
+///
+/// 各操作のページ変更をログに書き、任意の位置でクラッシュさせて復旧できる
+#[wasm_bindgen]
+pub struct WalBTree {
+    tree: BTree,
+    wal: Wal,
+}
+
+#[wasm_bindgen]
+impl WalBTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(t: usize) -> Result<WalBTree, String> {
+        if t < 2 {
+            return Err("min degree must be at least 2".into());
+        }
+        Ok(WalBTree {
+            tree: BTree::new(t),
+            wal: Wal::new(),
+        })
+    }
+
+    pub fn insert(&mut self, k: i32) {
+        self.apply(Operation::Insert(k));
+    }
+
+    pub fn delete(&mut self, k: i32) -> bool {
+        self.apply(Operation::Delete(k))
+    }
+
+    /// 今のページをチェックポイントとして残す
+    pub fn checkpoint(&mut self) {
+        self.wal.checkpoint();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_log_length(&self) -> usize {
+        self.wal.records().len()
+    }
+
+    /// ログの全ての記録 ([{lsn, type, ...}])
+    #[wasm_bindgen]
+    pub fn get_log(&self) -> JsValue {
+        let records = self.wal.records().iter().enumerate();
+        JsonValue::Array(records.map(|(lsn, r)| r.to_json(lsn)).collect())
+            .to_js_value()
+    }
+
+    /// 先頭からcut個の記録だけが残った状態から復旧した結果
+    /// ({committed, redone, undone, structure})
+    pub fn recover(&self, cut: usize) -> Result<JsValue, String> {
+        let recovery = self.wal.recover(cut)?;
+        Ok(JsonValue::object([
+            ("committed", recovery.committed.into()),
+            ("redone", recovery.redone.into()),
+            ("undone", recovery.undone.into()),
+            ("structure", recovery.image.to_json()),
+        ])
+        .to_js_value())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_structure(&self) -> JsValue {
+        self.tree.get_structure()
+    }
+}
+
+impl WalBTree {
+    /// 操作をログに書きながら適用する
+    pub fn apply(&mut self, op: Operation) -> bool {
+        self.wal.begin(op);
+        let applied = match op {
+            Operation::Insert(k) => {
+                self.tree.insert_with(k, &mut self.wal);
+                true
+            }
+            Operation::Delete(k) => {
+                self.tree.delete_with(k, &mut self.wal)
+            }
+        };
+        self.wal.commit();
+        applied
+    }
+
+    pub fn tree(&self) -> &BTree {
+        &self.tree
+    }
+
+    pub fn wal(&self) -> &Wal {
+        &self.wal
+    }
+}
+
+/// opsを順に適用し、ログの全ての位置でクラッシュさせて復旧を確かめる
+///
+/// checkpoint_every個の操作ごとにチェックポイントを取る (0なら取らない)。
+/// 復旧した木が正しいB-Treeで、完了した操作までを適用した木と一致すれば、
+/// 試したクラッシュの位置の数を返す
+pub fn crash_after_every_record(
+    t: usize,
+    ops: &[Operation],
+    checkpoint_every: usize,
+) -> Result<usize, String> {
+    let mut tree = WalBTree::new(t)?;
+    // snapshots[i]はi個の操作を完了したときのページ
+    let mut snapshots = vec![PageImage::default()];
+    for (i, &op) in ops.iter().enumerate() {
+        tree.apply(op);
+        snapshots.push(tree.wal().image().clone());
+        if checkpoint_every > 0 && (i + 1) % checkpoint_every == 0 {
+            tree.checkpoint();
+        }
+    }
+
+    let wal = tree.wal();
+    for cut in 0..=wal.records().len() {
+        let recovery = wal.recover(cut)?;
+        recovery
+            .image
+            .validate(t)
+            .map_err(|e| format!("crash at lsn {cut}: {e}"))?;
+        if recovery.image != snapshots[recovery.committed] {
+            return Err(format!(
+                "crash at lsn {cut}: recovered tree differs from the \
+                 tree after {} operations",
+                recovery.committed
+            ));
+        }
+    }
+    Ok(wal.records().len() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::SplitMix64;
+    use crate::wal::log::LogRecord;
+
+    #[test]
+    fn test_log_mirrors_tree() {
+        let mut tree = WalBTree::new(2).unwrap();
+        for k in 1..=4 {
+            tree.insert(k);
+            assert_eq!(tree.wal().image().levels(), tree.tree().levels());
+        }
+        for k in [4, 99, 2] {
+            tree.delete(k);
+            assert_eq!(tree.wal().image().levels(), tree.tree().levels());
+        }
+        let records = tree.wal().records();
+        assert_eq!(
+            records[0],
+            LogRecord::Begin {
+                op: Operation::Insert(1),
+            }
+        );
+        assert!(
+            records.iter().any(|r| matches!(r, LogRecord::Split { .. }))
+        );
+        assert!(
+            records.iter().any(|r| matches!(r, LogRecord::Merge { .. }))
+        );
+        assert_eq!(tree.wal().committed(), 7);
+    }
+
+    #[test]
+    fn test_recover_undoes_unfinished_operation() {
+        let mut tree = WalBTree::new(2).unwrap();
+        for k in 1..=3 {
+            tree.insert(k);
+        }
+        let before = tree.wal().image().clone();
+        let cut = tree.get_log_length();
+
+        // 根の分割の途中でクラッシュ
+        tree.insert(4);
+        let split = tree.wal().records()[cut..]
+            .iter()
+            .position(|r| matches!(r, LogRecord::Link { .. }))
+            .unwrap();
+        let recovery = tree.wal().recover(cut + split + 1).unwrap();
+        assert_eq!(recovery.committed, 3);
+        assert!(recovery.undone > 0);
+        assert_eq!(recovery.image, before);
+
+        // 完了の記録まで残っていれば操作は消えない
+        let all = tree.wal().recover(tree.get_log_length()).unwrap();
+        assert_eq!(all.committed, 4);
+        assert_eq!(all.undone, 0);
+        assert_eq!(all.image.levels(), tree.tree().levels());
+    }
+
+    #[test]
+    fn test_crash_after_every_record() {
+        let mut rng = SplitMix64::new(11);
+        for t in 2..=3 {
+            let ops: Vec<Operation> = (0..300)
+                .map(|_| {
+                    let k = (rng.next_u64() % 50) as i32;
+                    if rng.chance(0.4) {
+                        Operation::Delete(k)
+                    } else {
+                        Operation::Insert(k)
+                    }
+                })
+                .collect();
+            for checkpoint_every in [0, 7] {
+                let cuts =
+                    crash_after_every_record(t, &ops, checkpoint_every)
+                        .unwrap();
+                assert!(cuts > ops.len() * 2);
+            }
+        }
+    }
+}
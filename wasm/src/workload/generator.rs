@@ -0,0 +1,769 @@
+use crate::btree::{BTree, BTreeNode, MAX_MIN_DEGREE};
+use crate::json::JsonValue;
+use crate::rng::SplitMix64;
+use crate::trace::NoopTracer;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// キーの選び方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// 範囲内の一様乱数
+    Uniform,
+
+    /// 0から昇順 (範囲の端で0に戻る)
+    Sequential,
+
+    /// 範囲の最大値から降順
+    Reverse,
+
+    /// 順位rのキーが1/rに比例して選ばれる (小さいキーほど多い)
+    Zipf,
+
+    /// 90%が範囲の10%の連続した区間に集まる
+    HotSpot,
+
+    /// 間隔を空けて昇順に進み、端で少しずらして最初に戻る
+    Sawtooth,
+
+    /// 次数tのB-Treeで分割が最も多く起きるキーを候補から選ぶ
+    MaxSplits,
+
+    /// 次数tのB-Treeでマージが最も多く起きるキーを候補から選ぶ
+    MaxMerges,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 8] = [
+        Pattern::Uniform,
+        Pattern::Sequential,
+        Pattern::Reverse,
+        Pattern::Zipf,
+        Pattern::HotSpot,
+        Pattern::Sawtooth,
+        Pattern::MaxSplits,
+        Pattern::MaxMerges,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::Uniform => "uniform",
+            Pattern::Sequential => "sequential",
+            Pattern::Reverse => "reverse",
+            Pattern::Zipf => "zipf",
+            Pattern::HotSpot => "hotspot",
+            Pattern::Sawtooth => "sawtooth",
+            Pattern::MaxSplits => "maxsplits",
+            Pattern::MaxMerges => "maxmerges",
+        }
+    }
+
+    fn is_adversarial(self) -> bool {
+        matches!(self, Pattern::MaxSplits | Pattern::MaxMerges)
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::ALL
+            .into_iter()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| format!("unknown workload pattern: {s}"))
+    }
+}
+
+/// 生成する1操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkloadOp {
+    Insert(i32),
+    Delete(i32),
+    Search(i32),
+}
+
+impl WorkloadOp {
+    pub fn key(self) -> i32 {
+        match self {
+            WorkloadOp::Insert(k)
+            | WorkloadOp::Delete(k)
+            | WorkloadOp::Search(k) => k,
+        }
+    }
+
+    pub fn kind(self) -> &'static str {
+        match self {
+            WorkloadOp::Insert(_) => "insert",
+            WorkloadOp::Delete(_) => "delete",
+            WorkloadOp::Search(_) => "search",
+        }
+    }
+
+    pub fn to_json(self) -> JsonValue {
+        JsonValue::object([
+            ("type", self.kind().into()),
+            ("key", self.key().into()),
+        ])
+    }
+}
+
+/// 挿入・削除・探索の重み
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mix {
+    pub insert: f64,
+    pub delete: f64,
+    pub search: f64,
+}
+
+impl Mix {
+    pub const INSERT_ONLY: Mix = Mix {
+        insert: 1.0,
+        delete: 0.0,
+        search: 0.0,
+    };
+
+    pub fn new(
+        insert: f64,
+        delete: f64,
+        search: f64,
+    ) -> Result<Mix, String> {
+        let weights = [insert, delete, search];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0)
+            || weights.iter().sum::<f64>() <= 0.0
+        {
+            return Err(format!(
+                "invalid mix: {insert}, {delete}, {search}"
+            ));
+        }
+        Ok(Mix {
+            insert,
+            delete,
+            search,
+        })
+    }
+}
+
+/// 敵対的なパターンが候補を試す木 (分割とマージはトップダウン)
+struct Adversary {
+    tree: BTree,
+
+    /// 木に入っているキー (重複を含む)
+    live: Vec<i32>,
+
+    /// 直前に挿入・削除したキー。同じキーの出し入れで分割とマージを
+    /// 繰り返させるため、最初の候補にする
+    last_insert: Option<i32>,
+    last_delete: Option<i32>,
+}
+
+/// 敵対的なパターンが1操作ごとに試す候補の数
+const CANDIDATES: usize = 8;
+
+/// キーの範囲の上限 (Zipfの累積分布を範囲の大きさだけ確保するため)
+pub const MAX_KEY_RANGE: usize = 1 << 20;
+
+/// JSから1回に生成できる操作の数の上限
+pub const MAX_OPS: usize = 1 << 20;
+
+/// シード付きの操作列の生成器
+///
+/// デモの「ランダムに埋める」、テスト、ベンチマークで同じ列を再現するために使う
+#[wasm_bindgen]
+pub struct WorkloadGenerator {
+    pattern: Pattern,
+
+    /// キーは0..range
+    range: usize,
+    rng: SplitMix64,
+
+    /// 生成したキーの数 (順番に進むパターンで使う)
+    step: usize,
+
+    /// Zipfの累積分布
+    cdf: Vec<f64>,
+
+    /// HotSpotの区間の先頭
+    hot_start: usize,
+    adversary: Option<Adversary>,
+}
+
+#[wasm_bindgen]
+impl WorkloadGenerator {
+    /// pattern ("uniform" / "sequential" / "reverse" / "zipf" / "hotspot" /
+    /// "sawtooth" / "maxsplits" / "maxmerges") でキーを0..key_rangeから選ぶ
+    ///
+    /// 敵対的なパターンは次数2の木を相手にする。
+    /// 変えるには生成する前に`set_min_degree`を呼ぶ
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        pattern: &str,
+        key_range: usize,
+        seed: u64,
+    ) -> Result<WorkloadGenerator, String> {
+        if key_range > MAX_KEY_RANGE {
+            return Err(format!(
+                "key range too large: {key_range} (at most {MAX_KEY_RANGE})"
+            ));
+        }
+        Ok(WorkloadGenerator::with_pattern(
+            pattern.parse()?,
+            key_range,
+            seed,
+        ))
+    }
+
+    /// 敵対的なパターンが相手にする木の最小次数を設定し、木を空に戻す
+    #[wasm_bindgen]
+    pub fn set_min_degree(&mut self, t: usize) -> Result<(), String> {
+        if t < 2 {
+            return Err(format!("t must be at least 2: {t}"));
+        }
+        if t > MAX_MIN_DEGREE {
+            return Err(format!(
+                "t too large: {t} (at most {MAX_MIN_DEGREE})"
+            ));
+        }
+        if let Some(adversary) = &mut self.adversary {
+            *adversary = Adversary::new(t);
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_pattern(&self) -> String {
+        self.pattern.name().to_string()
+    }
+
+    /// 挿入するキーをn個生成
+    #[wasm_bindgen]
+    pub fn keys(&mut self, n: usize) -> Result<Vec<i32>, String> {
+        check_count(n)?;
+        Ok(self
+            .ops(n, Mix::INSERT_ONLY)
+            .into_iter()
+            .map(WorkloadOp::key)
+            .collect())
+    }
+
+    /// 重みに従って操作をn個生成 ([{type, key}])
+    #[wasm_bindgen]
+    pub fn operations(
+        &mut self,
+        n: usize,
+        insert: f64,
+        delete: f64,
+        search: f64,
+    ) -> Result<JsValue, String> {
+        check_count(n)?;
+        let mix = Mix::new(insert, delete, search)?;
+        Ok(JsonValue::Array(
+            self.ops(n, mix)
+                .into_iter()
+                .map(WorkloadOp::to_json)
+                .collect(),
+        )
+        .to_js_value())
+    }
+}
+
+impl WorkloadGenerator {
+    /// key_rangeはMAX_KEY_RANGE以下でなければならない
+    pub fn with_pattern(
+        pattern: Pattern,
+        key_range: usize,
+        seed: u64,
+    ) -> Self {
+        assert!(key_range <= MAX_KEY_RANGE, "key range too large");
+        let range = key_range.max(1);
+        let mut rng = SplitMix64::new(seed);
+        let cdf = match pattern {
+            Pattern::Zipf => zipf_cdf(range),
+            _ => Vec::new(),
+        };
+        let hot_start = match pattern {
+            Pattern::HotSpot => {
+                let width = hot_width(range);
+                (rng.next_u64() % (range - width + 1) as u64) as usize
+            }
+            _ => 0,
+        };
+        WorkloadGenerator {
+            pattern,
+            range,
+            rng,
+            step: 0,
+            cdf,
+            hot_start,
+            adversary: pattern.is_adversarial().then(|| Adversary::new(2)),
+        }
+    }
+
+    pub fn pattern(&self) -> Pattern {
+        self.pattern
+    }
+
+    /// 重みに従って操作をn個生成
+    pub fn ops(&mut self, n: usize, mix: Mix) -> Vec<WorkloadOp> {
+        (0..n).map(|_| self.next_op(mix)).collect()
+    }
+
+    pub fn next_op(&mut self, mix: Mix) -> WorkloadOp {
+        let total = mix.insert + mix.delete + mix.search;
+        let p = self.rng.next_f64() * total;
+        if p < mix.insert {
+            self.next_insert()
+        } else if p < mix.insert + mix.delete {
+            self.next_delete()
+        } else {
+            WorkloadOp::Search(self.next_key())
+        }
+    }
+
+    fn next_insert(&mut self) -> WorkloadOp {
+        if self.adversary.is_none() {
+            return WorkloadOp::Insert(self.next_key());
+        }
+        let mut candidates: Vec<i32> =
+            (0..CANDIDATES).map(|_| self.uniform() as i32).collect();
+        let adversary = self.adversary.as_mut().unwrap();
+        if let Some(k) = adversary.last_delete {
+            candidates[0] = k;
+        }
+        let k = adversary.choose(self.pattern, &candidates, true);
+        WorkloadOp::Insert(k)
+    }
+
+    fn next_delete(&mut self) -> WorkloadOp {
+        let len = match &self.adversary {
+            None => return WorkloadOp::Delete(self.next_key()),
+            Some(adversary) => adversary.live.len() as u64,
+        };
+        if len == 0 {
+            return WorkloadOp::Delete(self.uniform() as i32);
+        }
+        let adversary = self.adversary.as_mut().unwrap();
+        let mut candidates: Vec<i32> = (0..CANDIDATES)
+            .map(|_| adversary.live[(self.rng.next_u64() % len) as usize])
+            .collect();
+        if let Some(k) = adversary.last_insert {
+            candidates[0] = k;
+        }
+        let k = adversary.choose(self.pattern, &candidates, false);
+        WorkloadOp::Delete(k)
+    }
+
+    /// パターンに従って次のキーを選ぶ (敵対的なパターンでは一様乱数)
+    pub fn next_key(&mut self) -> i32 {
+        let range = self.range;
+        let c = self.step;
+        self.step += 1;
+        let key = match self.pattern {
+            Pattern::Sequential => c % range,
+            Pattern::Reverse => range - 1 - c % range,
+            Pattern::Zipf => {
+                let u = self.rng.next_f64();
+                self.cdf.partition_point(|&x| x <= u).min(range - 1)
+            }
+            Pattern::HotSpot => {
+                if self.rng.chance(0.9) {
+                    let width = hot_width(range) as u64;
+                    self.hot_start + (self.rng.next_u64() % width) as usize
+                } else {
+                    self.uniform()
+                }
+            }
+            Pattern::Sawtooth => {
+                // 長さlenの歯を、stride間隔で昇順に進む
+                let len = (range / 8).max(1);
+                let stride = range / len;
+                ((c % len) * stride + (c / len) % stride) % range
+            }
+            Pattern::Uniform | Pattern::MaxSplits | Pattern::MaxMerges => {
+                self.uniform()
+            }
+        };
+        key as i32
+    }
+
+    fn uniform(&mut self) -> usize {
+        (self.rng.next_u64() % self.range as u64) as usize
+    }
+}
+
+impl Adversary {
+    fn new(t: usize) -> Self {
+        Adversary {
+            tree: BTree::new(t),
+            live: Vec::new(),
+            last_insert: None,
+            last_delete: None,
+        }
+    }
+
+    /// 各候補で起きる構造変更を数え、目的の変更が最も多いものを選んで木に適用する
+    ///
+    /// 同点なら先の候補を選ぶ
+    fn choose(
+        &mut self,
+        pattern: Pattern,
+        candidates: &[i32],
+        insert: bool,
+    ) -> i32 {
+        let t = self.tree.get_min_degree();
+        let mut best = (candidates[0], 0);
+        if let Some(root) = self.tree.root() {
+            for &k in candidates {
+                // 挿入でマージは起きず、削除で分割は起きない
+                let s = match (pattern, insert) {
+                    (Pattern::MaxMerges, false) => {
+                        delete_merges(root, t, k)
+                    }
+                    (Pattern::MaxSplits, true) => insert_splits(root, k),
+                    _ => 0,
+                };
+                if s > best.1 {
+                    best = (k, s);
+                }
+            }
+        }
+
+        let k = best.0;
+        if insert {
+            self.tree.insert_with(k, &mut NoopTracer);
+            self.live.push(k);
+            self.last_insert = Some(k);
+        } else {
+            self.tree.delete_with(k, &mut NoopTracer);
+            if let Some(i) = self.live.iter().position(|&x| x == k) {
+                self.live.swap_remove(i);
+                self.last_delete = Some(k);
+            }
+        }
+        k
+    }
+}
+
+/// トップダウンの挿入でkを入れたときの分割の数
+///
+/// 下りる途中の満杯のノードを全て分割するので、根から葉への経路上の
+/// 満杯のノードを数える。分割しても下りる先の子は変わらない
+fn insert_splits(root: &BTreeNode, k: i32) -> usize {
+    let mut splits = 0;
+    let mut node = root;
+    loop {
+        let keys = node.keys();
+        let mut i = keys.partition_point(|&x| x <= k);
+        if node.is_full() {
+            splits += 1;
+            // 中央のキーと等しいkは左半分の最後の子へ下りる
+            let mid = keys.len() / 2;
+            if keys[mid] == k {
+                i = mid;
+            }
+        }
+        match node.child_nodes().get(i) {
+            Some(child) if !node.leaf() => node = child,
+            _ => return splits,
+        }
+    }
+}
+
+/// トップダウンの削除でkを消したときのマージの数
+///
+/// 下りる先のノードを借用やマージの後の形で組み立て直しながら、
+/// BTreeNode::deleteと同じ順で補強を試す。木は変更しない
+fn delete_merges(root: &BTreeNode, t: usize, k: i32) -> usize {
+    let mut merges = 0;
+    let mut k = k;
+    let mut node = View::of(root);
+    while !node.children.is_empty() {
+        let idx = node.keys.partition_point(|&x| x < k);
+        if node.keys.get(idx) == Some(&k) {
+            let (left, right) =
+                (node.children[idx], node.children[idx + 1]);
+            node = if left.keys_len() >= t {
+                k = last_key(left);
+                View::of(left)
+            } else if right.keys_len() >= t {
+                k = first_key(right);
+                View::of(right)
+            } else {
+                merges += 1;
+                View::merge(left, k, right)
+            };
+            continue;
+        }
+
+        let child = node.children[idx];
+        let prev = idx.checked_sub(1).map(|i| node.children[i]);
+        let next = node.children.get(idx + 1).copied();
+        node = if child.keys_len() >= t {
+            View::of(child)
+        } else if let Some(prev) = prev.filter(|p| p.keys_len() >= t) {
+            let mut view = View::of(child);
+            view.keys.insert(0, node.keys[idx - 1]);
+            view.children.splice(
+                0..0,
+                prev.child_nodes().last().map(|c| c.as_ref()),
+            );
+            view
+        } else if let Some(next) = next.filter(|n| n.keys_len() >= t) {
+            let mut view = View::of(child);
+            view.keys.push(node.keys[idx]);
+            view.children
+                .extend(next.child_nodes().first().map(|c| c.as_ref()));
+            view
+        } else if let Some(next) = next {
+            merges += 1;
+            View::merge(child, node.keys[idx], next)
+        } else {
+            merges += 1;
+            View::merge(prev.unwrap(), node.keys[idx - 1], child)
+        };
+    }
+    merges
+}
+
+/// 部分木の最大のキー
+fn last_key(mut node: &BTreeNode) -> i32 {
+    while let Some(child) = node.child_nodes().last() {
+        node = child;
+    }
+    node.keys()[node.keys_len() - 1]
+}
+
+/// 部分木の最小のキー
+fn first_key(mut node: &BTreeNode) -> i32 {
+    while let Some(child) = node.child_nodes().first() {
+        node = child;
+    }
+    node.keys()[0]
+}
+
+/// 削除で下りる途中のノード。キーは補強の後の形で、子は元の木を指す
+struct View<'a> {
+    keys: Vec<i32>,
+    children: Vec<&'a BTreeNode>,
+}
+
+impl<'a> View<'a> {
+    fn of(node: &'a BTreeNode) -> Self {
+        View {
+            keys: node.keys(),
+            children: node
+                .child_nodes()
+                .iter()
+                .map(|c| c.as_ref())
+                .collect(),
+        }
+    }
+
+    fn merge(left: &'a BTreeNode, key: i32, right: &'a BTreeNode) -> Self {
+        let mut view = View::of(left);
+        view.keys.push(key);
+        view.keys.extend(right.keys());
+        view.children
+            .extend(right.child_nodes().iter().map(|c| c.as_ref()));
+        view
+    }
+}
+
+fn check_count(n: usize) -> Result<(), String> {
+    if n > MAX_OPS {
+        return Err(format!(
+            "too many operations: {n} (at most {MAX_OPS})"
+        ));
+    }
+    Ok(())
+}
+
+fn hot_width(range: usize) -> usize {
+    (range / 10).max(1)
+}
+
+/// 指数1のZipf分布の累積分布
+fn zipf_cdf(range: usize) -> Vec<f64> {
+    let mut total = 0.0;
+    let mut cdf: Vec<f64> = (1..=range)
+        .map(|r| {
+            total += 1.0 / r as f64;
+            total
+        })
+        .collect();
+    for c in &mut cdf {
+        *c /= total;
+    }
+    cdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btree::OperationStats;
+    use std::time::{Duration, Instant};
+
+    fn generator(pattern: Pattern, range: usize) -> WorkloadGenerator {
+        WorkloadGenerator::with_pattern(pattern, range, 42)
+    }
+
+    /// 操作列を次数tの木に適用し、構造変更を合計する
+    fn replay(t: usize, ops: &[WorkloadOp]) -> OperationStats {
+        let mut tree = BTree::new(t);
+        let mut total = OperationStats::default();
+        for &op in ops {
+            match op {
+                WorkloadOp::Insert(k) => tree.insert(k),
+                WorkloadOp::Delete(k) => {
+                    tree.delete(k);
+                }
+                WorkloadOp::Search(k) => {
+                    tree.search(k);
+                }
+            }
+            total += tree.get_last_stats();
+        }
+        total
+    }
+
+    #[test]
+    fn test_same_seed_same_stream() {
+        let mix = Mix::new(2.0, 1.0, 1.0).unwrap();
+        for pattern in Pattern::ALL {
+            let a = generator(pattern, 100).ops(300, mix);
+            let b = generator(pattern, 100).ops(300, mix);
+            assert_eq!(a, b, "{pattern:?}");
+            assert!(a.iter().all(|op| (0..100).contains(&op.key())));
+            assert_eq!(pattern.name().parse(), Ok(pattern));
+        }
+        let c = WorkloadGenerator::with_pattern(Pattern::Uniform, 100, 43)
+            .ops(300, mix);
+        assert_ne!(generator(Pattern::Uniform, 100).ops(300, mix), c);
+
+        assert!(WorkloadGenerator::new("gaussian", 10, 1).is_err());
+        assert!(WorkloadGenerator::new("zipf", usize::MAX, 1).is_err());
+        assert!(
+            WorkloadGenerator::new("uniform", MAX_KEY_RANGE + 1, 1)
+                .is_err()
+        );
+        let mut g =
+            WorkloadGenerator::new("reverse", MAX_KEY_RANGE, 1).unwrap();
+        assert_eq!(g.keys(1).unwrap(), vec![MAX_KEY_RANGE as i32 - 1]);
+        assert!(Mix::new(1.0, -1.0, 0.0).is_err());
+        assert!(Mix::new(0.0, 0.0, 0.0).is_err());
+        let mut g = WorkloadGenerator::new("maxsplits", 10, 1).unwrap();
+        assert!(g.set_min_degree(1).is_err());
+        assert!(g.set_min_degree(MAX_MIN_DEGREE + 1).is_err());
+        assert!(g.keys(MAX_OPS + 1).is_err());
+        assert!(g.operations(MAX_OPS + 1, 1.0, 0.0, 0.0).is_err());
+        assert_eq!(g.get_pattern(), "maxsplits");
+    }
+
+    #[test]
+    fn test_ordered_patterns() {
+        assert_eq!(
+            generator(Pattern::Sequential, 4).keys(6).unwrap(),
+            vec![0, 1, 2, 3, 0, 1]
+        );
+        assert_eq!(
+            generator(Pattern::Reverse, 4).keys(5).unwrap(),
+            vec![3, 2, 1, 0, 3]
+        );
+
+        // 長さ2の歯が間隔8で昇り、16個で全てのキーを1回ずつ使う
+        let keys = generator(Pattern::Sawtooth, 16).keys(16).unwrap();
+        assert_eq!(&keys[..6], &[0, 8, 1, 9, 2, 10]);
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_skewed_patterns() {
+        let mut g = generator(Pattern::HotSpot, 1000);
+        let hot = g.hot_start as i32..g.hot_start as i32 + 100;
+        let keys = g.keys(2000).unwrap();
+        let count = keys.iter().filter(|k| hot.contains(k)).count();
+        assert!(count > 1700, "{count}");
+
+        let keys = generator(Pattern::Zipf, 1000).keys(2000).unwrap();
+        let low = keys.iter().filter(|&&k| k < 10).count();
+        assert!(low > 700, "{low}");
+    }
+
+    #[test]
+    fn test_adversarial_patterns() {
+        for t in [2, 3] {
+            let mut splits = generator(Pattern::MaxSplits, 500);
+            splits.set_min_degree(t).unwrap();
+            let ops: Vec<WorkloadOp> = splits
+                .keys(400)
+                .unwrap()
+                .into_iter()
+                .map(WorkloadOp::Insert)
+                .collect();
+            let uniform: Vec<WorkloadOp> =
+                generator(Pattern::Uniform, 500)
+                    .keys(400)
+                    .unwrap()
+                    .into_iter()
+                    .map(WorkloadOp::Insert)
+                    .collect();
+            assert!(
+                replay(t, &ops).splits > replay(t, &uniform).splits,
+                "t={t}"
+            );
+
+            let mix = Mix::new(1.0, 1.0, 0.0).unwrap();
+            let mut merges = generator(Pattern::MaxMerges, 500);
+            merges.set_min_degree(t).unwrap();
+            let ops = merges.ops(400, mix);
+            let uniform = generator(Pattern::Uniform, 500).ops(400, mix);
+            assert!(
+                replay(t, &ops).merges > replay(t, &uniform).merges,
+                "t={t}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scores_match_the_tree() {
+        // 木の複製に実際に適用した結果と、複製せずに数えた結果を比べる
+        let mut rng = SplitMix64::new(7);
+        for t in 2..=4 {
+            let mut tree = BTree::new(t);
+            for _ in 0..600 {
+                let k = (rng.next_u64() % 200) as i32;
+                let Some(root) = tree.root() else {
+                    tree.insert(k);
+                    continue;
+                };
+                let mut copy = BTree::from_root(
+                    t,
+                    tree.root().map(|r| Box::new(r.clone())),
+                );
+                if rng.chance(0.6) {
+                    copy.insert(k);
+                    let stats = copy.get_last_stats();
+                    assert_eq!(insert_splits(root, k), stats.splits);
+                    tree.insert(k);
+                } else {
+                    copy.delete(k);
+                    let stats = copy.get_last_stats();
+                    assert_eq!(delete_merges(root, t, k), stats.merges);
+                    tree.delete(k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adversarial_stream_is_fast() {
+        let start = Instant::now();
+        let mix = Mix::new(1.0, 1.0, 0.0).unwrap();
+        let mut splits = generator(Pattern::MaxSplits, MAX_KEY_RANGE);
+        assert_eq!(splits.ops(50_000, Mix::INSERT_ONLY).len(), 50_000);
+        let mut merges = generator(Pattern::MaxMerges, MAX_KEY_RANGE);
+        assert_eq!(merges.ops(50_000, mix).len(), 50_000);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,5 @@
+mod generator;
+
+pub use generator::{
+    MAX_KEY_RANGE, MAX_OPS, Mix, Pattern, WorkloadGenerator, WorkloadOp,
+};